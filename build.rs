@@ -0,0 +1,42 @@
+//! Records an identifier of this build, which keys CirC's artifact cache (see
+//! `src/target/r1cs/cache.rs`).
+//!
+//! The identifier is the git commit and the compiler version. A build from a modified tree also
+//! includes the build time, so it never shares cached artifacts with another build.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn output(cmd: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(cmd).args(args).output().ok()?;
+    if out.status.success() {
+        Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let compiler = output(&rustc, &["--version"]).unwrap_or_else(|| "unknown rustc".into());
+    let commit = output("git", &["rev-parse", "HEAD"]);
+    let clean = output("git", &["status", "--porcelain", "--untracked-files=no"])
+        .map(|s| s.is_empty())
+        .unwrap_or(false);
+    let id = match commit {
+        Some(commit) if clean => format!("{commit}, {compiler}"),
+        commit => {
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default();
+            format!(
+                "{} (modified, built at {}), {}",
+                commit.as_deref().unwrap_or("unknown commit"),
+                time,
+                compiler
+            )
+        }
+    };
+    println!("cargo:rustc-env=CIRC_BUILD_ID={id}");
+}
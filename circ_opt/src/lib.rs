@@ -9,6 +9,8 @@
 //!    * `c` : [COpt]
 //!    * `field`: [FieldOpt]
 //!    * `fmt`: [FmtOpt]
+//!    * `smt`: [SmtOpt]
+//!    * `ilp`: [IlpOpt]
 //!    * all options types implement:
 //!       * std's [Default]
 //!       * clap's [Args]; all options are settable by
@@ -41,6 +43,7 @@
 use clap::{ArgAction, Args, ValueEnum};
 
 use std::default::Default;
+use std::path::PathBuf;
 
 /// Re-export our version of clap.
 pub use clap;
@@ -72,6 +75,12 @@ pub struct CircOpt {
     /// Options for C frontend
     #[command(flatten)]
     pub c: COpt,
+    /// Options for the SMT backend
+    #[command(flatten)]
+    pub smt: SmtOpt,
+    /// Options for the ILP backend (and ILP-based MPC share assignment)
+    #[command(flatten)]
+    pub ilp: IlpOpt,
 }
 
 /// Options for the R1cs backend
@@ -101,6 +110,20 @@ pub struct R1csOpt {
     )]
     /// linear combination constraints up to this size will be eliminated
     pub lc_elim_thresh: usize,
+
+    /// Attribute R1CS constraints and variables to source lines, writing flamegraph stacks to
+    /// `PREFIX.constraints.folded` and `PREFIX.vars.folded` and an annotated listing to
+    /// `PREFIX.annotated.txt`, for this PREFIX (if non-empty)
+    #[arg(
+        long = "r1cs-source-profile",
+        env = "R1CS_SOURCE_PROFILE",
+        default_value = ""
+    )]
+    pub source_profile: String,
+
+    /// Print the messages of `log!` statements while computing the witness
+    #[arg(long = "trace-witness", env = "TRACE_WITNESS", action = ArgAction::Set, default_value = "false")]
+    pub trace_witness: bool,
 }
 
 impl Default for R1csOpt {
@@ -110,6 +133,8 @@ impl Default for R1csOpt {
             profile: false,
             div_by_zero: FieldDivByZero::Incomplete,
             lc_elim_thresh: 50,
+            source_profile: String::new(),
+            trace_witness: false,
         }
     }
 }
@@ -374,6 +399,21 @@ pub struct ZsharpOpt {
     /// [ZoKrates](https://zokrates.github.io/language/control_flow.html).
     #[arg(long = "zsharp-isolate-asserts", env = "ZSHARP_ISOLATE_ASSERTS", action = ArgAction::Set, default_value = "false")]
     pub isolate_asserts: bool,
+
+    /// Directories to search for Z# imports (separated by `:`), after the importing file's
+    /// directory and packages, and before the standard library
+    #[arg(
+        long = "zsharp-search-path",
+        env = "ZSHARP_SEARCH_PATH",
+        name = "DIRS",
+        value_delimiter = ':'
+    )]
+    pub search_path: Vec<PathBuf>,
+
+    /// How deeply a recursive Z# function may call itself (0: no limit); `#[unroll(N)]` on a
+    /// function overrides this
+    #[arg(long = "zsharp-rec-limit", env = "ZSHARP_REC_LIMIT", default_value = "0")]
+    pub rec_limit: usize,
 }
 
 /// Options for the datalog frontend
@@ -391,6 +431,10 @@ pub struct DatalogOpt {
     /// Lint recursions that are allegedly primitive recursive
     #[arg(long = "datalog-lint-prim-rec", env = "DATALOG_LINT_PRIM_REC", action = ArgAction::Set, default_value = "false")]
     pub lint_prim_rec: bool,
+
+    /// The entry predicate: the rule whose arguments are the inputs
+    #[arg(long = "datalog-entry", env = "DATALOG_ENTRY", default_value = "main")]
+    pub entry: String,
 }
 
 impl Default for DatalogOpt {
@@ -398,6 +442,7 @@ impl Default for DatalogOpt {
         Self {
             rec_limit: 5,
             lint_prim_rec: false,
+            entry: "main".into(),
         }
     }
 }
@@ -414,6 +459,78 @@ pub struct COpt {
     pub assert_no_ub: bool,
 }
 
+/// Options for the SMT backend
+#[derive(Args, Debug, Default, Clone, PartialEq, Eq)]
+pub struct SmtOpt {
+    /// Which SMT solver to use
+    #[arg(
+        long = "smt-solver",
+        env = "SMT_SOLVER",
+        value_enum,
+        default_value = "cvc5"
+    )]
+    pub solver: SmtSolver,
+
+    /// The solver's invocation command (if empty, the solver's usual command)
+    #[arg(long = "smt-solver-cmd", env = "SMT_SOLVER_CMD", default_value = "")]
+    pub solver_cmd: String,
+}
+
+#[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
+/// Which SMT solver to use
+pub enum SmtSolver {
+    /// cvc5 (or CVC4); supports finite fields and tuples
+    Cvc5,
+    /// Z3
+    Z3,
+    /// Yices 2
+    Yices,
+    /// Bitwuzla; supports bit-vectors and arrays
+    Bitwuzla,
+}
+
+impl Default for SmtSolver {
+    fn default() -> Self {
+        SmtSolver::Cvc5
+    }
+}
+
+/// Options for the ILP backend
+#[derive(Args, Debug, Default, Clone, PartialEq, Eq)]
+pub struct IlpOpt {
+    /// Which ILP solver to use
+    #[arg(
+        long = "ilp-solver",
+        env = "ILP_SOLVER",
+        value_enum,
+        default_value = "auto"
+    )]
+    pub solver: IlpSolver,
+}
+
+#[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
+/// Which ILP solver to use
+pub enum IlpSolver {
+    /// CBC if it is linked, and microlp otherwise
+    Auto,
+    /// CBC, linked (feature lp-cbc)
+    Cbc,
+    /// HiGHS, linked (feature lp-highs)
+    Highs,
+    /// microlp, in pure Rust
+    Microlp,
+    /// GLPK, as a subprocess (glpsol)
+    Glpk,
+    /// CBC, as a subprocess (cbc)
+    CbcCmd,
+}
+
+impl Default for IlpSolver {
+    fn default() -> Self {
+        IlpSolver::Auto
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -13,6 +13,8 @@ cargo_features = {
     "aby",
     "c",
     "lp",
+    "lp-cbc",
+    "lp-highs",
     "r1cs",
     "smt",
     "zok",
@@ -25,7 +25,7 @@ use circ::front::zsharp::{self, ZSharpFE};
 use circ::front::{FrontEnd, Mode};
 use circ::ir::term::{Node, Op, BV_LSHR, BV_SHL};
 use circ::ir::{
-    opt::{opt, Opt},
+    opt::{opt, opt_with_stats, Opt, PassStats},
     term::{
         check,
         text::{parse_computations, parse_value_map, serialize_value_map},
@@ -33,8 +33,13 @@ use circ::ir::{
 };
 #[cfg(feature = "aby")]
 use circ::target::aby::trans::to_aby;
+#[cfg(feature = "r1cs")]
+use circ::target::air::{trans::to_air, WINTERFELL_FIELDS};
+use circ::target::bristol::trans::to_bristol;
 #[cfg(feature = "lp")]
 use circ::target::ilp::{assignment_to_values, trans::to_ilp};
+#[cfg(feature = "r1cs")]
+use circ::target::plonkish::{trans::to_plonkish, CustomGate};
 #[cfg(feature = "spartan")]
 use circ::target::r1cs::spartan::write_data;
 #[cfg(feature = "bellman")]
@@ -44,14 +49,19 @@ use circ::target::r1cs::{
     proof::{CommitProofSystem, ProofSystem},
 };
 #[cfg(feature = "r1cs")]
-use circ::target::r1cs::{opt::reduce_linearities, trans::to_r1cs};
+use circ::target::r1cs::{
+    cache::{ArtifactCache, CacheKey, CacheKeyBuilder},
+    opt::reduce_linearities,
+    trans::to_r1cs,
+    R1csStats, VarType,
+};
 #[cfg(feature = "smt")]
-use circ::target::smt::find_model;
+use circ::target::smt::{equiv::find_distinguishing_input, find_model};
 use circ_fields::FieldT;
 use fxhash::FxHashMap as HashMap;
-#[cfg(feature = "lp")]
-use good_lp::default_solver;
 use log::trace;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Read;
 use std::io::Write;
@@ -76,6 +86,10 @@ struct Options {
     #[arg(long, default_value = "2", name = "PARTIES")]
     parties: u8,
 
+    /// Write a JSON report of compilation statistics to this path
+    #[arg(long)]
+    stats_json: Option<PathBuf>,
+
     #[structopt(subcommand)]
     backend: Backend,
 }
@@ -110,6 +124,12 @@ enum Backend {
         action: ProofAction,
         #[arg(long, default_value = "groth16")]
         proof_impl: ProofImpl,
+        /// Directory in which to cache setup artifacts
+        #[arg(long, default_value = ".circ_cache")]
+        cache_dir: PathBuf,
+        /// Always recompile, ignoring (and not updating) the setup cache
+        #[arg(long)]
+        no_cache: bool,
     },
     Smt {},
     Ilp {},
@@ -119,6 +139,38 @@ enum Backend {
         #[arg(long, default_value = "lp", name = "selection_scheme")]
         selection_scheme: String,
     },
+    /// A Bristol Fashion boolean circuit, for garbled-circuit MPC
+    Bristol {
+        /// Where to write the circuit
+        #[arg(long, default_value = "circuit.txt")]
+        output: PathBuf,
+    },
+    /// A PLONKish gate table, described as JSON for halo2-style provers
+    Plonkish {
+        /// Where to write the description
+        #[arg(long, default_value = "plonkish.json")]
+        output: PathBuf,
+        /// Custom gates to use (comma-separated): pow5, ec-add
+        #[arg(long, value_delimiter = ',')]
+        custom_gates: Vec<String>,
+    },
+    /// An AIR for a Z# `main` that iterates a step function, described as JSON for STARK provers
+    Air {
+        /// Where to write the description
+        #[arg(long, default_value = "air.json")]
+        output: PathBuf,
+        /// Initial state (a value map); if given, generate the execution trace
+        #[arg(long)]
+        inputs: Option<PathBuf>,
+        /// Where to write the execution trace
+        #[arg(long, default_value = "trace.json")]
+        trace: PathBuf,
+    },
+    /// Check that PATH and another Z# program compute the same function
+    Equiv {
+        /// The other program
+        other: PathBuf,
+    },
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, ValueEnum)]
@@ -182,6 +234,196 @@ fn determine_language(l: &Language, input_path: &Path) -> DeterminedLanguage {
     }
 }
 
+/// Compilation statistics, written by `--stats-json`.
+#[derive(Debug, Default, Serialize)]
+struct StatsReport {
+    /// Whether the setup was restored from the cache (in which case nothing else is reported)
+    cached: bool,
+    /// Frontend wall-clock time, in seconds
+    frontend_seconds: f64,
+    /// Per-pass term counts and times
+    passes: Vec<PassStats>,
+    /// Per-computation operator counts, after optimization
+    ops: BTreeMap<String, OpCounts>,
+    #[cfg(feature = "r1cs")]
+    r1cs: Option<R1csReport>,
+}
+
+/// Operator counts for the main computation and its precomputation.
+#[derive(Debug, Default, Serialize)]
+struct OpCounts {
+    main: BTreeMap<String, usize>,
+    prec: BTreeMap<String, usize>,
+}
+
+#[cfg(feature = "r1cs")]
+#[derive(Debug, Default, Serialize)]
+struct R1csReport {
+    /// Before `reduce_linearities`
+    initial: R1csStats,
+    /// After `reduce_linearities`
+    optimized: R1csStats,
+    /// Final variable counts, by type
+    vars_by_type: BTreeMap<String, usize>,
+    spartan: Option<SpartanReport>,
+}
+
+#[cfg(feature = "r1cs")]
+#[derive(Debug, Default, Serialize)]
+struct SpartanReport {
+    num_cons: usize,
+    num_vars: usize,
+    num_inputs: usize,
+    gens_bytes: u64,
+    instance_bytes: u64,
+}
+
+impl StatsReport {
+    fn write(&self, path: &Path) {
+        let f = File::create(path)
+            .unwrap_or_else(|e| panic!("Could not create {}: {}", path.display(), e));
+        serde_json::to_writer_pretty(f, self).unwrap();
+    }
+}
+
+/// Compile two Z# programs and search for an input on which they differ.
+#[cfg(all(feature = "smt", feature = "zok"))]
+fn equiv(a: &Path, b: &Path) {
+    let compile = |file: &Path| {
+        let cs = ZSharpFE::gen(zsharp::Inputs {
+            file: file.to_owned(),
+            mode: Mode::Proof,
+        });
+        opt(cs, vec![Opt::ConstantFold(Box::new([]))])
+            .get("main")
+            .clone()
+    };
+    let (a, b) = (compile(a), compile(b));
+    match find_distinguishing_input(&a, &b).unwrap_or_else(|e| panic!("{}", e)) {
+        Some(m) => {
+            println!("Not equivalent. Distinguishing input:");
+            print!("{}", serialize_value_map(&m.into_iter().collect()));
+            std::process::exit(1)
+        }
+        None => {
+            println!("equivalent");
+        }
+    }
+}
+
+/// The IR optimization passes to run for this mode.
+fn passes(mode: Mode, circ: &CircOpt) -> Vec<Opt> {
+    match mode {
+        Mode::Opt => vec![Opt::ScalarizeVars, Opt::ConstantFold(Box::new([]))],
+        Mode::Mpc(_) => {
+            let ignore = [BV_LSHR, BV_SHL];
+            vec![
+                Opt::ScalarizeVars,
+                Opt::Flatten,
+                Opt::Sha,
+                Opt::ConstantFold(Box::new(ignore.clone())),
+                Opt::Flatten,
+                // Function calls return tuples
+                Opt::Tuple,
+                Opt::Obliv,
+                // The obliv elim pass produces more tuples, that must be eliminated
+                Opt::Tuple,
+                Opt::LinearScan,
+                // The linear scan pass produces more tuples, that must be eliminated
+                Opt::Tuple,
+                Opt::ConstantFold(Box::new(ignore)),
+                // Binarize nary terms
+                Opt::Binarize,
+            ]
+            // vec![Opt::Sha, Opt::ConstantFold, Opt::Mem, Opt::ConstantFold],
+        }
+        Mode::Proof | Mode::ProofOfHighValue(_) => {
+            let mut opts = Vec::new();
+
+            opts.push(Opt::ConstantFold(Box::new([])));
+            opts.push(Opt::DeskolemizeWitnesses);
+            opts.push(Opt::ScalarizeVars);
+            opts.push(Opt::Flatten);
+            opts.push(Opt::Sha);
+            opts.push(Opt::ConstantFold(Box::new([])));
+            opts.push(Opt::ParseCondStores);
+            // Tuples must be eliminated before oblivious array elim
+            opts.push(Opt::ConstantFold(Box::new([])));
+            opts.push(Opt::Obliv);
+            // The obliv elim pass produces more tuples, that must be eliminated
+            opts.push(Opt::SetMembership);
+            opts.push(Opt::PersistentRam);
+            opts.push(Opt::VolatileRam);
+            if circ.ir.fits_in_bits_ip {
+                opts.push(Opt::FitsInBitsIp);
+            }
+            opts.push(Opt::SkolemizeChallenges);
+            opts.push(Opt::ScalarizeVars);
+            opts.push(Opt::ConstantFold(Box::new([])));
+            opts.push(Opt::Obliv);
+            opts.push(Opt::LinearScan);
+            // The linear scan pass produces more tuples, that must be eliminated
+            opts.push(Opt::Tuple);
+            opts.push(Opt::Flatten);
+            opts.push(Opt::ConstantFold(Box::new([])));
+            opts
+        }
+    }
+}
+
+/// The cache entry for a Spartan setup, if the setup should be cached.
+///
+/// Returns the cache, the key for this compilation, and the (cache name, output path) pairs of
+/// the artifacts.
+#[cfg(feature = "r1cs")]
+fn setup_cache(
+    options: &Options,
+    language: &DeterminedLanguage,
+    mode: Mode,
+    passes: &[Opt],
+) -> Option<(ArtifactCache, CacheKey, Vec<(&'static str, PathBuf)>)> {
+    let (prover_key, verifier_key, gens, instance, cache_dir) = match &options.backend {
+        Backend::R1cs {
+            action: ProofAction::SpartanSetup,
+            no_cache: false,
+            prover_key,
+            verifier_key,
+            gens,
+            instance,
+            cache_dir,
+            ..
+        } => (prover_key, verifier_key, gens, instance, cache_dir),
+        _ => return None,
+    };
+    let sources = match language {
+        #[cfg(all(feature = "smt", feature = "zok"))]
+        DeterminedLanguage::Zsharp => ZSharpFE::imports(&zsharp::Inputs {
+            file: options.path.clone(),
+            mode,
+        }),
+        DeterminedLanguage::CircIr | DeterminedLanguage::Datalog => vec![options.path.clone()],
+        // We don't know the full set of source files (e.g., included headers)
+        _ => return None,
+    };
+    let mut key = CacheKeyBuilder::new();
+    for source in &sources {
+        key.file(source)
+            .unwrap_or_else(|e| panic!("Could not read {}: {}", source.display(), e));
+    }
+    key.str("mode", &format!("{mode}"))
+        .str("options", &format!("{:?}", options.circ))
+        .str("passes", &format!("{passes:?}"));
+    let cache = ArtifactCache::open(cache_dir)
+        .unwrap_or_else(|e| panic!("Could not open cache {}: {}", cache_dir.display(), e));
+    let artifacts = vec![
+        ("prover_data", prover_key.clone()),
+        ("verifier_data", verifier_key.clone()),
+        ("gens", gens.clone()),
+        ("instance", instance.clone()),
+    ];
+    Some((cache, key.finish(), artifacts))
+}
+
 #[allow(unused_variables, unreachable_code)]
 fn main() {
     env_logger::Builder::from_default_env()
@@ -190,6 +432,15 @@ fn main() {
         .init();
     let options = Options::parse();
     circ::cfg::set(&options.circ);
+    if let Backend::Equiv { other } = &options.backend {
+        #[cfg(all(feature = "smt", feature = "zok"))]
+        {
+            equiv(&options.path, other);
+            return;
+        }
+        #[cfg(not(all(feature = "smt", feature = "zok")))]
+        panic!("Missing feature: smt,zok");
+    }
     let path_buf = options.path.clone();
     let mode = match options.backend {
         Backend::R1cs { .. } => match options.frontend.value_threshold {
@@ -197,11 +448,41 @@ fn main() {
             None => Mode::Proof,
         },
         Backend::Ilp { .. } => Mode::Opt,
-        Backend::Mpc { .. } => Mode::Mpc(options.parties),
-        Backend::Smt { .. } => Mode::Proof,
+        Backend::Mpc { .. } | Backend::Bristol { .. } => Mode::Mpc(options.parties),
+        Backend::Smt { .. }
+        | Backend::Plonkish { .. }
+        | Backend::Air { .. }
+        | Backend::Equiv { .. } => Mode::Proof,
     };
     let language = determine_language(&options.frontend.language, &options.path);
+    let passes = passes(mode, &options.circ);
+    #[cfg(feature = "r1cs")]
+    let cached = setup_cache(&options, &language, mode, &passes);
+    let mut report = StatsReport::default();
+    #[cfg(feature = "r1cs")]
+    if let Some((cache, key, artifacts)) = &cached {
+        if cache.restore(*key, artifacts).unwrap() {
+            println!("Restored setup from cache (entry {key})");
+            if let Some(path) = &options.stats_json {
+                report.cached = true;
+                report.write(path);
+            }
+            return;
+        }
+    }
     println!("Running frontend");
+    let frontend_start = Instant::now();
+    #[cfg(all(feature = "smt", feature = "zok"))]
+    let uniform_loop = match (&options.backend, &language) {
+        (Backend::Air { .. }, DeterminedLanguage::Zsharp) => Some(
+            ZSharpFE::uniform_loop(&zsharp::Inputs {
+                file: options.path.clone(),
+                mode,
+            })
+            .unwrap_or_else(|e| panic!("{}", e)),
+        ),
+        _ => None,
+    };
     let cs = match language {
         #[cfg(all(feature = "smt", feature = "zok"))]
         DeterminedLanguage::Zsharp => {
@@ -209,7 +490,10 @@ fn main() {
                 file: options.path,
                 mode,
             };
-            ZSharpFE::gen(inputs)
+            match &uniform_loop {
+                Some(l) => ZSharpFE::gen_step(inputs, l),
+                None => ZSharpFE::gen(inputs),
+            }
         }
         DeterminedLanguage::CircIr => parse_computations(&std::fs::read(&options.path).unwrap()),
         #[cfg(not(all(feature = "smt", feature = "zok")))]
@@ -218,7 +502,10 @@ fn main() {
         }
         #[cfg(all(feature = "smt", feature = "datalog"))]
         DeterminedLanguage::Datalog => {
-            let inputs = datalog::Inputs { file: options.path };
+            let inputs = datalog::Inputs {
+                file: options.path,
+                entry: options.circ.datalog.entry.clone(),
+            };
             Datalog::gen(inputs)
         }
         #[cfg(not(all(feature = "smt", feature = "datalog")))]
@@ -240,68 +527,30 @@ fn main() {
             panic!("Missing feature: c");
         }
     };
+    report.frontend_seconds = frontend_start.elapsed().as_secs_f64();
     println!("Running IR optimizations");
-    let cs = match mode {
-        Mode::Opt => opt(
-            cs,
-            vec![Opt::ScalarizeVars, Opt::ConstantFold(Box::new([]))],
-        ),
-        Mode::Mpc(_) => {
-            let ignore = [BV_LSHR, BV_SHL];
-            opt(
-                cs,
-                vec![
-                    Opt::ScalarizeVars,
-                    Opt::Flatten,
-                    Opt::Sha,
-                    Opt::ConstantFold(Box::new(ignore.clone())),
-                    Opt::Flatten,
-                    // Function calls return tuples
-                    Opt::Tuple,
-                    Opt::Obliv,
-                    // The obliv elim pass produces more tuples, that must be eliminated
-                    Opt::Tuple,
-                    Opt::LinearScan,
-                    // The linear scan pass produces more tuples, that must be eliminated
-                    Opt::Tuple,
-                    Opt::ConstantFold(Box::new(ignore)),
-                    // Binarize nary terms
-                    Opt::Binarize,
-                ],
-                // vec![Opt::Sha, Opt::ConstantFold, Opt::Mem, Opt::ConstantFold],
-            )
-        }
-        Mode::Proof | Mode::ProofOfHighValue(_) => {
-            let mut opts = Vec::new();
-
-            opts.push(Opt::ConstantFold(Box::new([])));
-            opts.push(Opt::DeskolemizeWitnesses);
-            opts.push(Opt::ScalarizeVars);
-            opts.push(Opt::Flatten);
-            opts.push(Opt::Sha);
-            opts.push(Opt::ConstantFold(Box::new([])));
-            opts.push(Opt::ParseCondStores);
-            // Tuples must be eliminated before oblivious array elim
-            opts.push(Opt::ConstantFold(Box::new([])));
-            opts.push(Opt::Obliv);
-            // The obliv elim pass produces more tuples, that must be eliminated
-            opts.push(Opt::SetMembership);
-            opts.push(Opt::PersistentRam);
-            opts.push(Opt::VolatileRam);
-            if options.circ.ir.fits_in_bits_ip {
-                opts.push(Opt::FitsInBitsIp);
-            }
-            opts.push(Opt::SkolemizeChallenges);
-            opts.push(Opt::ScalarizeVars);
-            opts.push(Opt::ConstantFold(Box::new([])));
-            opts.push(Opt::Obliv);
-            opts.push(Opt::LinearScan);
-            // The linear scan pass produces more tuples, that must be eliminated
-            opts.push(Opt::Tuple);
-            opts.push(Opt::Flatten);
-            opts.push(Opt::ConstantFold(Box::new([])));
-            opt(cs, opts)
+    let cs = if options.stats_json.is_some() {
+        let (cs, pass_stats) = opt_with_stats(cs, passes);
+        report.passes = pass_stats;
+        for (name, c) in &cs.comps {
+            let stats = c.detailed_stats();
+            let counts = |n_ops: Vec<(usize, Op)>| {
+                n_ops
+                    .into_iter()
+                    .map(|(n, op)| (format!("{op}"), n))
+                    .collect()
+            };
+            report.ops.insert(
+                name.clone(),
+                OpCounts {
+                    main: counts(stats.main.n_ops),
+                    prec: counts(stats.prec.n_ops),
+                },
+            );
         }
+        cs
+    } else {
+        opt(cs, passes)
     };
     println!("Running backend");
 
@@ -322,6 +571,7 @@ fn main() {
             if cfg().r1cs.profile {
                 println!("R1CS stats: {:#?}", r1cs.stats());
             }
+            let initial_stats = r1cs.stats().clone();
 
             println!("Running r1cs optimizations ");
             r1cs = reduce_linearities(r1cs, cfg());
@@ -330,7 +580,18 @@ fn main() {
             if cfg().r1cs.profile {
                 println!("R1CS stats: {:#?}", r1cs.stats());
             }
+            let optimized_stats = r1cs.stats().clone();
             let (prover_data, verifier_data) = r1cs.finalize(cs);
+            let mut vars_by_type: BTreeMap<String, usize> = BTreeMap::new();
+            for v in &prover_data.r1cs.vars {
+                *vars_by_type.entry(format!("{:?}", v.ty())).or_default() += 1;
+            }
+            report.r1cs = Some(R1csReport {
+                initial: initial_stats,
+                optimized: optimized_stats,
+                vars_by_type,
+                spartan: None,
+            });
             match action {
                 ProofAction::Count => (),
                 #[cfg(feature = "bellman")]
@@ -375,7 +636,25 @@ fn main() {
                 ProofAction::SpartanSetup => {
                     write_data::<_, _>(prover_key, verifier_key, &prover_data, &verifier_data)
                         .unwrap();
-                    write_preprocessed_spartan::<_, _>(gens, instance, &prover_data).unwrap();
+                    write_preprocessed_spartan::<_, _>(&gens, &instance, &prover_data).unwrap();
+                    if let Some(r) = report.r1cs.as_mut() {
+                        let n_wits = prover_data
+                            .r1cs
+                            .vars
+                            .iter()
+                            .filter(|v| matches!(v.ty(), VarType::FinalWit | VarType::RoundWit))
+                            .count();
+                        r.spartan = Some(SpartanReport {
+                            num_cons: prover_data.r1cs.constraints.len(),
+                            num_vars: n_wits,
+                            num_inputs: prover_data.r1cs.vars.len() - n_wits,
+                            gens_bytes: std::fs::metadata(&gens).unwrap().len(),
+                            instance_bytes: std::fs::metadata(&instance).unwrap().len(),
+                        });
+                    }
+                    if let Some((cache, key, artifacts)) = &cached {
+                        cache.store(*key, artifacts).unwrap();
+                    }
                 }
                 #[cfg(not(feature = "spartan"))]
                 ProofAction::SpartanSetup => panic!("Missing feature: spartan"),
@@ -404,6 +683,91 @@ fn main() {
         Backend::Mpc { .. } => {
             panic!("Missing feature: aby");
         }
+        Backend::Bristol { output } => {
+            println!("Converting to bristol");
+            let circuit = to_bristol(cs.get("main"));
+            println!(
+                "Bristol circuit: {} gates ({} AND), {} wires",
+                circuit.gates.len(),
+                circuit.n_and(),
+                circuit.n_wires
+            );
+            std::fs::write(&output, circuit.to_string())
+                .unwrap_or_else(|e| panic!("Could not write {}: {}", output.display(), e));
+        }
+        #[cfg(feature = "r1cs")]
+        Backend::Plonkish {
+            output,
+            custom_gates,
+        } => {
+            println!("Converting to plonkish");
+            let custom_gates: Vec<CustomGate> = custom_gates
+                .iter()
+                .map(|g| g.parse().unwrap_or_else(|e| panic!("{}", e)))
+                .collect();
+            let plonkish = to_plonkish(cs.get("main"), cfg(), &custom_gates);
+            println!(
+                "Plonkish table: {} rows, {} wires",
+                plonkish.rows().len(),
+                plonkish.n_wires()
+            );
+            for (gate, n) in plonkish.gate_counts() {
+                println!("  {gate}: {n}");
+            }
+            let json = serde_json::to_string_pretty(&plonkish.to_json()).unwrap();
+            std::fs::write(&output, json)
+                .unwrap_or_else(|e| panic!("Could not write {}: {}", output.display(), e));
+        }
+        #[cfg(not(feature = "r1cs"))]
+        Backend::Plonkish { .. } => {
+            panic!("Missing feature: r1cs");
+        }
+        #[cfg(all(feature = "r1cs", feature = "smt", feature = "zok"))]
+        Backend::Air {
+            output,
+            inputs,
+            trace,
+        } => {
+            let l = uniform_loop.expect("The AIR backend only supports Z#");
+            println!("Converting to air");
+            let pd = to_air(cs.get("main"), &l.param, &l.state, l.steps, cfg())
+                .unwrap_or_else(|e| panic!("{}", e));
+            let air = &pd.air;
+            if air.winterfell_field().is_none() {
+                let fields: Vec<String> = WINTERFELL_FIELDS
+                    .iter()
+                    .map(|(name, m)| format!("{m} ({name})"))
+                    .collect();
+                eprintln!(
+                    "Warning: Winterfell does not support this field. Use --field-custom-modulus {}",
+                    fields.join(" or ")
+                );
+            }
+            println!(
+                "AIR: {} columns ({} state), {} transition constraints, {} rows",
+                air.width(),
+                air.n_state(),
+                air.transitions().len(),
+                air.trace_len()
+            );
+            let json = serde_json::to_string_pretty(&air.to_json()).unwrap();
+            std::fs::write(&output, json)
+                .unwrap_or_else(|e| panic!("Could not write {}: {}", output.display(), e));
+            if let Some(inputs) = inputs {
+                let init = parse_value_map(&std::fs::read(&inputs).unwrap());
+                let t = pd.trace(&init).unwrap_or_else(|e| panic!("{}", e));
+                for (name, v) in air.columns().iter().zip(t.state(air, air.steps())) {
+                    println!("  {name}: {v}");
+                }
+                let json = serde_json::to_string(&t.to_json()).unwrap();
+                std::fs::write(&trace, json)
+                    .unwrap_or_else(|e| panic!("Could not write {}: {}", trace.display(), e));
+            }
+        }
+        #[cfg(not(all(feature = "r1cs", feature = "smt", feature = "zok")))]
+        Backend::Air { .. } => {
+            panic!("Missing feature: r1cs,smt,zok");
+        }
         #[cfg(feature = "lp")]
         Backend::Ilp { .. } => {
             println!("Converting to ilp");
@@ -419,7 +783,7 @@ fn main() {
                 })
                 .collect();
             let ilp = to_ilp(cs.get("main").clone());
-            let solver_result = ilp.solve(default_solver);
+            let solver_result = ilp.default_solve();
             let (max, vars) = solver_result.expect("ILP could not be solved");
             println!("Max value: {}", max.round() as u64);
             println!("Assignment:");
@@ -471,5 +835,9 @@ fn main() {
         Backend::Smt { .. } => {
             panic!("Missing feature: smt");
         }
+        Backend::Equiv { .. } => unreachable!("checked before running the frontend"),
+    }
+    if let Some(path) = &options.stats_json {
+        report.write(path);
     }
 }
@@ -0,0 +1,4 @@
+allowed(req: public {user: u32, level: u8}, acl: (u32, u8)[4]) :-
+    count(i in acl where acl[i].0 = req.user: acl[i].1 >= req.level) = 1,
+    max(i in acl: acl[i].1) >= req.level,
+    sum(i in acl where acl[i].1 > 0x00: 1) = 2.
@@ -12,10 +12,18 @@ use libspartan::{Instance, NIZK, NIZKGens};
 #[cfg(feature = "bellman")]
 use circ::target::r1cs::{bellman::Bellman, mirage::Mirage, proof::ProofSystem};
 
-#[cfg(feature = "spartan")]
-use circ::ir::term::text::parse_value_map;
 #[cfg(feature = "spartan")]
 use circ::target::r1cs::spartan;
+#[cfg(any(feature = "bellman", feature = "spartan"))]
+use circ::ir::term::text::parse_value_map;
+#[cfg(any(feature = "bellman", feature = "spartan"))]
+use circ::ir::term::Value;
+#[cfg(all(feature = "zok", any(feature = "bellman", feature = "spartan")))]
+use circ::front::zsharp::{Inputs, ZSharpFE};
+#[cfg(all(feature = "zok", any(feature = "bellman", feature = "spartan")))]
+use circ::front::Mode;
+#[cfg(any(feature = "bellman", feature = "spartan"))]
+use fxhash::FxHashMap as HashMap;
 
 #[derive(Debug, Parser)]
 #[command(name = "zk", about = "The CirC ZKP runner")]
@@ -32,6 +40,9 @@ struct Options {
     pin: PathBuf,
     #[arg(long, default_value = "vin")]
     vin: PathBuf,
+    /// The Z# file whose `main` signature types JSON (`.json`) input files
+    #[arg(long)]
+    zsharp: Option<PathBuf>,
     #[arg(long, default_value = "groth16")]
     proof_impl: ProofImpl,
     #[arg(long)]
@@ -56,6 +67,32 @@ enum ProofImpl {
     Mirage,
 }
 
+/// Read a value map, or JSON inputs to the `main` of `zsharp`
+#[cfg(any(feature = "bellman", feature = "spartan"))]
+fn read_inputs(path: &Path, zsharp: Option<&Path>) -> HashMap<String, Value> {
+    let src = std::fs::read(path).unwrap();
+    if path.extension().map_or(false, |e| e == "json") {
+        let zsharp = zsharp.expect("JSON inputs require --zsharp");
+        read_json_inputs(&src, zsharp)
+    } else {
+        parse_value_map(&src)
+    }
+}
+
+#[cfg(all(feature = "zok", any(feature = "bellman", feature = "spartan")))]
+fn read_json_inputs(src: &[u8], zsharp: &Path) -> HashMap<String, Value> {
+    let inputs = Inputs {
+        file: zsharp.to_path_buf(),
+        mode: Mode::Proof,
+    };
+    ZSharpFE::json_inputs(&inputs, src).unwrap_or_else(|e| panic!("{}", e))
+}
+
+#[cfg(all(not(feature = "zok"), any(feature = "bellman", feature = "spartan")))]
+fn read_json_inputs(_src: &[u8], _zsharp: &Path) -> HashMap<String, Value> {
+    panic!("Missing feature: zok")
+}
+
 fn main() {
     env_logger::Builder::from_default_env()
         .format_level(false)
@@ -63,30 +100,48 @@ fn main() {
         .init();
     let opts = Options::parse();
     circ::cfg::set(&opts.circ);
+    #[cfg(any(feature = "bellman", feature = "spartan"))]
+    let zsharp = opts.zsharp.as_deref();
     match (opts.action, opts.proof_impl) {
         #[cfg(feature = "bellman")]
         (ProofAction::Prove, ProofImpl::Groth16) => {
             println!("Proving");
-            Bellman::<Bls12>::prove_fs(opts.prover_key, opts.inputs, opts.proof).unwrap();
+            let witness = read_inputs(&opts.inputs, zsharp);
+            Bellman::<Bls12>::prove_fs_with_witness(opts.prover_key, &witness, opts.proof)
+                .unwrap();
         }
         #[cfg(feature = "bellman")]
         (ProofAction::Prove, ProofImpl::Mirage) => {
             println!("Proving");
-            Mirage::<Bls12>::prove_fs(opts.prover_key, opts.inputs, opts.proof).unwrap();
+            let witness = read_inputs(&opts.inputs, zsharp);
+            Mirage::<Bls12>::prove_fs_with_witness(opts.prover_key, &witness, opts.proof)
+                .unwrap();
         }
         #[cfg(feature = "bellman")]
         (ProofAction::Verify, ProofImpl::Groth16) => {
             println!("Verifying");
+            let instance = read_inputs(&opts.inputs, zsharp);
             assert!(
-                Bellman::<Bls12>::verify_fs(opts.verifier_key, opts.inputs, opts.proof).unwrap(),
+                Bellman::<Bls12>::verify_fs_with_instance(
+                    opts.verifier_key,
+                    &instance,
+                    opts.proof
+                )
+                .unwrap(),
                 "invalid proof"
             );
         }
         #[cfg(feature = "bellman")]
         (ProofAction::Verify, ProofImpl::Mirage) => {
             println!("Verifying");
+            let instance = read_inputs(&opts.inputs, zsharp);
             assert!(
-                Mirage::<Bls12>::verify_fs(opts.verifier_key, opts.inputs, opts.proof).unwrap(),
+                Mirage::<Bls12>::verify_fs_with_instance(
+                    opts.verifier_key,
+                    &instance,
+                    opts.proof
+                )
+                .unwrap(),
                 "invalid proof"
             );
         }
@@ -94,10 +149,10 @@ fn main() {
         (ProofAction::Prove | ProofAction::Verify, _) => panic!("Missing feature: bellman"),
         #[cfg(feature = "spartan")]
         (ProofAction::Spartan, _) => {
-            let prover_input_map = parse_value_map(&std::fs::read(opts.pin).unwrap());
+            let prover_input_map = read_inputs(&opts.pin, zsharp);
             let (gens, inst, proof) = spartan::prove(opts.prover_key, &prover_input_map).unwrap();
 
-            let verifier_input_map = parse_value_map(&std::fs::read(opts.vin).unwrap());
+            let verifier_input_map = read_inputs(&opts.vin, zsharp);
             spartan::verify(opts.verifier_key, &verifier_input_map, &gens, &inst, proof).unwrap();
         }
         // #[cfg(feature = "spartan")]
@@ -0,0 +1,64 @@
+use circ::front::zsharp::{Inputs, ZSharpFE};
+
+use circ::cfg::{
+    clap::{self, Parser},
+    CircOpt,
+};
+use circ::front::Mode;
+use std::collections::HashMap;
+use std::panic::{set_hook, take_hook};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "zxcheck",
+    about = "Parses and type-checks a Z# program, without generating constraints"
+)]
+struct Options {
+    /// Input file
+    #[arg()]
+    zsharp_path: PathBuf,
+
+    /// Print the errors (and inferred types) as JSON
+    #[arg(long)]
+    json: bool,
+
+    #[command(flatten)]
+    /// CirC options
+    circ: CircOpt,
+}
+
+fn main() {
+    env_logger::Builder::from_default_env()
+        .format_level(false)
+        .format_timestamp(None)
+        .init();
+    let options = Options::parse();
+    circ::cfg::set(&options.circ);
+    let inputs = Inputs {
+        file: options.zsharp_path,
+        mode: Mode::Proof,
+    };
+    // errors are reported as diagnostics, not printed
+    let hook = take_hook();
+    set_hook(Box::new(|_| ()));
+    let c = ZSharpFE::check(&inputs, HashMap::new());
+    set_hook(hook);
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&c).unwrap());
+    } else {
+        for d in &c.diagnostics {
+            let (line, col) = d.range.start;
+            println!(
+                "{}:{}:{}: error: {}",
+                d.range.file.display(),
+                line,
+                col,
+                d.message
+            );
+        }
+    }
+    if !c.diagnostics.is_empty() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,162 @@
+use circ::front::zsharp::debug::{Breakpoint, DebugFrontend, DebugState, Debugger, Resume};
+use circ::front::zsharp::{Inputs, ZSharpFE};
+use circ::ir::term::text::parse_value_map;
+
+use circ::cfg::{
+    clap::{self, Parser},
+    CircOpt,
+};
+use circ::front::Mode;
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(name = "zxdb", about = "The Z# debugger")]
+struct Options {
+    /// Input file
+    #[arg()]
+    zsharp_path: PathBuf,
+
+    /// Scalar input values
+    #[arg()]
+    inputs_path: Option<PathBuf>,
+
+    /// Initial breakpoints (LINE or FILE:LINE); if any are given, run until the first one
+    #[arg(long = "break", short = 'b')]
+    breakpoints: Vec<Breakpoint>,
+
+    #[command(flatten)]
+    /// CirC options
+    circ: CircOpt,
+}
+
+const HELP: &str = "\
+Commands:
+  s, step             run to the next statement, entering calls
+  n, next             run to the next statement in this function
+  f, finish           run until this function returns
+  c, continue         run to the next breakpoint
+  b, break [F:]LINE   add a breakpoint
+  d, delete [F:]LINE  remove a breakpoint
+  bl                  list breakpoints
+  p, print PATH       print a variable, e.g., `p s.x[2]`
+  l, locals           print all locals
+  bt, stack           print the call stack (with generics)
+  w, where            print the current statement
+  q, quit             exit
+  h, help             print this message";
+
+struct Repl;
+
+impl Repl {
+    fn where_(state: &DebugState) {
+        let loc = state.location();
+        println!("{}:{}:{}: {}", loc.file.display(), loc.line, loc.col, loc.text);
+    }
+}
+
+impl DebugFrontend for Repl {
+    fn stopped(&mut self, state: &DebugState, breakpoints: &mut BTreeSet<Breakpoint>) -> Resume {
+        Self::where_(state);
+        let stdin = std::io::stdin();
+        loop {
+            print!("(zxdb) ");
+            std::io::stdout().flush().unwrap();
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap() == 0 {
+                // EOF
+                std::process::exit(0);
+            }
+            let mut words = line.split_whitespace();
+            let cmd = words.next().unwrap_or("");
+            let arg = words.collect::<Vec<_>>().join(" ");
+            match cmd {
+                "s" | "step" => return Resume::Step,
+                "n" | "next" | "" => return Resume::Next,
+                "f" | "finish" => return Resume::Finish,
+                "c" | "continue" => return Resume::Continue,
+                "b" | "break" => match arg.parse::<Breakpoint>() {
+                    Ok(b) => {
+                        println!("Breakpoint at {b}");
+                        breakpoints.insert(b);
+                    }
+                    Err(e) => println!("{e}"),
+                },
+                "d" | "delete" => match arg.parse::<Breakpoint>() {
+                    Ok(b) => {
+                        if !breakpoints.remove(&b) {
+                            println!("No breakpoint at {b}");
+                        }
+                    }
+                    Err(e) => println!("{e}"),
+                },
+                "bl" => breakpoints.iter().for_each(|b| println!("  {b}")),
+                "p" | "print" => match state.inspect(&arg) {
+                    Ok(v) => println!("{arg} = {v}"),
+                    Err(e) => println!("{e}"),
+                },
+                "l" | "locals" => state
+                    .locals()
+                    .into_iter()
+                    .for_each(|(n, v)| println!("  {n} = {v}")),
+                "bt" | "stack" => {
+                    for (i, frame) in state.stack().iter().enumerate().rev() {
+                        let generics = frame
+                            .generics
+                            .iter()
+                            .map(|(n, v)| format!("{n} = {v}"))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        println!(
+                            "  #{i} {}<{generics}> in {}",
+                            frame.function,
+                            frame.file.display()
+                        );
+                    }
+                }
+                "w" | "where" => Self::where_(state),
+                "q" | "quit" => std::process::exit(0),
+                "h" | "help" => println!("{HELP}"),
+                _ => println!("Unknown command '{cmd}'; try 'help'"),
+            }
+        }
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_default_env()
+        .format_level(false)
+        .format_timestamp(None)
+        .init();
+    let mut options = Options::parse();
+    options.circ.ir.field_to_bv = circ_opt::FieldToBv::Panic;
+    circ::cfg::set(&options.circ);
+    let inputs = Inputs {
+        file: options.zsharp_path,
+        mode: Mode::Proof,
+    };
+    let scalar_input_values = match options.inputs_path.as_ref() {
+        Some(p) => parse_value_map(&std::fs::read(p).unwrap()),
+        None => Default::default(),
+    };
+    let mut debugger = Debugger::new(Box::new(Repl));
+    if !options.breakpoints.is_empty() {
+        debugger.set_resume(Resume::Continue);
+    }
+    for b in options.breakpoints {
+        debugger.add_breakpoint(b);
+    }
+    match ZSharpFE::debug(inputs, scalar_input_values, debugger) {
+        Ok(v) => {
+            print!("Returned: ");
+            v.pretty(&mut std::io::stdout().lock())
+                .expect("error pretty-printing value");
+            println!();
+        }
+        Err(e) => {
+            println!("Error: {e}");
+            std::process::exit(1);
+        }
+    }
+}
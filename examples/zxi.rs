@@ -15,7 +15,7 @@ struct Options {
     #[arg()]
     zsharp_path: PathBuf,
 
-    /// Scalar input values
+    /// Scalar input values, or JSON inputs to `main` (if the file ends in `.json`)
     #[arg()]
     inputs_path: Option<PathBuf>,
 
@@ -37,6 +37,10 @@ fn main() {
         mode: Mode::Proof,
     };
     let scalar_input_values = match options.inputs_path.as_ref() {
+        Some(p) if p.extension().map_or(false, |e| e == "json") => {
+            ZSharpFE::json_inputs(&inputs, &std::fs::read(p).unwrap())
+                .unwrap_or_else(|e| panic!("{}", e))
+        }
         Some(p) => parse_value_map(&std::fs::read(p).unwrap()),
         None => Default::default(),
     };
@@ -0,0 +1,254 @@
+//! A minimal language server for Z#.
+//!
+//! Speaks the Language Server Protocol over stdin/stdout. It re-checks a file whenever it is
+//! opened or changed (using the editor's unsaved contents of all open files), publishes the
+//! errors as diagnostics, and answers hovers over identifiers with their types.
+use circ::front::zsharp::{check::Check, Inputs, ZSharpFE};
+
+use circ::cfg::{
+    clap::{self, Parser},
+    CircOpt,
+};
+use circ::front::Mode;
+use serde_json::{json, Value as Json};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Read, Write};
+use std::panic::{set_hook, take_hook};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Parser)]
+#[command(name = "zxls", about = "A language server for Z#")]
+struct Options {
+    #[command(flatten)]
+    /// CirC options
+    circ: CircOpt,
+}
+
+/// Read one message (a `Content-Length` header, then a JSON body); `None` at end of input.
+fn read_message(r: &mut impl BufRead) -> Option<Json> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if r.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(l) = line.strip_prefix("Content-Length:") {
+            len = l.trim().parse::<usize>().ok();
+        }
+    }
+    let mut body = vec![0; len?];
+    r.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+fn write_message(w: &mut impl Write, msg: &Json) {
+    let body = msg.to_string();
+    write!(w, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    w.flush().unwrap();
+}
+
+fn uri_to_path(uri: &str) -> PathBuf {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let mut bytes = Vec::new();
+    let mut i = 0;
+    let b = path.as_bytes();
+    while i < b.len() {
+        match (b[i], path.get(i + 1..i + 3)) {
+            (b'%', Some(h)) if u8::from_str_radix(h, 16).is_ok() => {
+                bytes.push(u8::from_str_radix(h, 16).unwrap());
+                i += 3;
+            }
+            (c, _) => {
+                bytes.push(c);
+                i += 1;
+            }
+        }
+    }
+    let path = PathBuf::from(String::from_utf8_lossy(&bytes).into_owned());
+    path.canonicalize().unwrap_or(path)
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = "file://".to_string();
+    for c in path.display().to_string().chars() {
+        match c {
+            ' ' | '%' | '#' | '?' => uri += &format!("%{:02X}", c as u32),
+            c => uri.push(c),
+        }
+    }
+    uri
+}
+
+/// An LSP position (0-based) from a (line, column) (1-based).
+fn position((line, col): (usize, usize)) -> Json {
+    json!({"line": line.saturating_sub(1), "character": col.saturating_sub(1)})
+}
+
+#[derive(Default)]
+struct Server {
+    /// The contents of open documents
+    docs: HashMap<PathBuf, String>,
+    /// The last check of each open document
+    checks: HashMap<PathBuf, Check>,
+    /// The files each open document last published diagnostics for
+    published: HashMap<PathBuf, HashSet<PathBuf>>,
+}
+
+impl Server {
+    /// Check `file`, and return the `publishDiagnostics` notifications to send.
+    fn check(&mut self, file: PathBuf) -> Vec<Json> {
+        let inputs = Inputs {
+            file: file.clone(),
+            mode: Mode::Proof,
+        };
+        // errors are published as diagnostics, not printed
+        let hook = take_hook();
+        set_hook(Box::new(|_| ()));
+        let c = ZSharpFE::check(&inputs, self.docs.clone());
+        set_hook(hook);
+        let mut by_file: HashMap<PathBuf, Vec<Json>> = HashMap::new();
+        by_file.insert(file.clone(), Vec::new());
+        for d in &c.diagnostics {
+            by_file
+                .entry(d.range.file.clone())
+                .or_default()
+                .push(json!({
+                    "range": {"start": position(d.range.start), "end": position(d.range.end)},
+                    "severity": 1,
+                    "source": "zxls",
+                    "message": d.message,
+                }));
+        }
+        // clear the diagnostics of files that no longer have any
+        let files: HashSet<PathBuf> = by_file.keys().cloned().collect();
+        for old in self
+            .published
+            .insert(file.clone(), files)
+            .unwrap_or_default()
+        {
+            by_file.entry(old).or_default();
+        }
+        self.checks.insert(file, c);
+        by_file
+            .into_iter()
+            .map(|(f, diagnostics)| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": {"uri": path_to_uri(&f), "diagnostics": diagnostics},
+                })
+            })
+            .collect()
+    }
+
+    /// Close `file`, and return the `publishDiagnostics` notifications that clear its diagnostics.
+    fn close(&mut self, file: &Path) -> Vec<Json> {
+        self.docs.remove(file);
+        self.checks.remove(file);
+        self.published
+            .remove(file)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|f| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": {"uri": path_to_uri(&f), "diagnostics": []},
+                })
+            })
+            .collect()
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let file = uri_to_path(params["textDocument"]["uri"].as_str().unwrap_or_default());
+        let line = params["position"]["line"].as_u64().unwrap_or_default() as usize + 1;
+        let col = params["position"]["character"].as_u64().unwrap_or_default() as usize + 1;
+        let t = self
+            .checks
+            .values()
+            .find_map(|c| c.type_at(&file, line, col));
+        match t {
+            Some(t) => json!({
+                "contents": {"kind": "plaintext", "value": format!("{}: {}", t.name, t.ty)},
+                "range": {"start": position(t.range.start), "end": position(t.range.end)},
+            }),
+            None => Json::Null,
+        }
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_default_env()
+        .format_level(false)
+        .format_timestamp(None)
+        .init();
+    let options = Options::parse();
+    circ::cfg::set(&options.circ);
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    let stdout = std::io::stdout();
+    let mut output = stdout.lock();
+    let mut server = Server::default();
+    let mut shut_down = false;
+    while let Some(msg) = read_message(&mut input) {
+        let method = msg["method"].as_str().unwrap_or_default();
+        let params = &msg["params"];
+        let doc = || uri_to_path(params["textDocument"]["uri"].as_str().unwrap_or_default());
+        let mut notifications = Vec::new();
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    // full document sync
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                },
+                "serverInfo": {"name": "zxls"},
+            })),
+            "shutdown" => {
+                shut_down = true;
+                Ok(Json::Null)
+            }
+            "exit" => std::process::exit(if shut_down { 0 } else { 1 }),
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                server.docs.insert(doc(), text.to_string());
+                notifications = server.check(doc());
+                Ok(Json::Null)
+            }
+            "textDocument/didChange" => {
+                // with full sync, the last change is the whole document
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                    server.docs.insert(doc(), text.to_string());
+                }
+                notifications = server.check(doc());
+                Ok(Json::Null)
+            }
+            "textDocument/didSave" => {
+                notifications = server.check(doc());
+                Ok(Json::Null)
+            }
+            "textDocument/didClose" => {
+                notifications = server.close(&doc());
+                Ok(Json::Null)
+            }
+            "textDocument/hover" => Ok(server.hover(params)),
+            _ => Err(json!({"code": -32601, "message": format!("Unknown method {}", method)})),
+        };
+        for n in &notifications {
+            write_message(&mut output, n);
+        }
+        // only requests (which have ids) get responses
+        if let Some(id) = msg.get("id") {
+            let response = match result {
+                Ok(r) => json!({"jsonrpc": "2.0", "id": id, "result": r}),
+                Err(e) => json!({"jsonrpc": "2.0", "id": id, "error": e}),
+            };
+            write_message(&mut output, &response);
+        }
+    }
+}
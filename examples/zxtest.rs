@@ -0,0 +1,71 @@
+use circ::front::zsharp::{Inputs, ZSharpFE};
+
+use circ::cfg::{
+    clap::{self, Parser},
+    CircOpt,
+};
+use circ::front::Mode;
+use std::panic::{set_hook, take_hook};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "zxtest",
+    about = "Runs the #[test] functions of a Z# program and its imports"
+)]
+struct Options {
+    /// Input file
+    #[arg()]
+    zsharp_path: PathBuf,
+
+    /// Only run tests whose names contain this string
+    #[arg()]
+    filter: Option<String>,
+
+    #[command(flatten)]
+    /// CirC options
+    circ: CircOpt,
+}
+
+fn main() {
+    env_logger::Builder::from_default_env()
+        .format_level(false)
+        .format_timestamp(None)
+        .init();
+    let options = Options::parse();
+    circ::cfg::set(&options.circ);
+    let inputs = Inputs {
+        file: options.zsharp_path,
+        mode: Mode::Proof,
+    };
+    // failures are reported, not printed
+    let hook = take_hook();
+    set_hook(Box::new(|_| ()));
+    let results = ZSharpFE::test(&inputs, options.filter.as_deref());
+    set_hook(hook);
+    println!("running {} tests", results.len());
+    for r in &results {
+        let status = if r.result.is_ok() { "ok" } else { "FAILED" };
+        println!("test {}::{} ... {}", r.file.display(), r.name, status);
+    }
+    let failures: Vec<_> = results.iter().filter(|r| r.result.is_err()).collect();
+    if !failures.is_empty() {
+        println!();
+        println!("failures:");
+        for r in &failures {
+            println!();
+            println!("---- {} ({}:{}) ----", r.name, r.file.display(), r.line);
+            println!("{}", r.result.as_ref().unwrap_err().trim_end());
+        }
+    }
+    println!();
+    println!(
+        "test result: {}. {} passed; {} failed",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        results.len() - failures.len(),
+        failures.len()
+    );
+    if !failures.is_empty() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,83 @@
+use circ::front::zsharp::{Inputs, ZSharpFE};
+use circ::ir::term::text::{parse_value_map, serialize_value_map};
+
+use circ::cfg::{
+    clap::{self, Parser},
+    CircOpt,
+};
+use circ::front::Mode;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "zxw",
+    about = "Computes the prover (.pin) and verifier (.vin) inputs of a Z# program"
+)]
+struct Options {
+    /// Input file
+    #[arg()]
+    zsharp_path: PathBuf,
+
+    /// The "true" inputs: scalar input values, or JSON inputs (if the file ends in `.json`),
+    /// keyed by the parameters of the witness function and `main`
+    #[arg()]
+    inputs_path: PathBuf,
+
+    /// The function that computes the rest of `main`'s inputs
+    #[arg(long, default_value = "witness")]
+    witness_fn: String,
+
+    /// Where to write the prover inputs (default: the input file with `.pin` appended)
+    #[arg(long)]
+    pin: Option<PathBuf>,
+
+    /// Where to write the verifier inputs (default: the input file with `.vin` appended)
+    #[arg(long)]
+    vin: Option<PathBuf>,
+
+    #[command(flatten)]
+    /// CirC options
+    circ: CircOpt,
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut s = path.as_os_str().to_owned();
+    s.push(suffix);
+    s.into()
+}
+
+fn main() {
+    env_logger::Builder::from_default_env()
+        .format_level(false)
+        .format_timestamp(None)
+        .init();
+    let mut options = Options::parse();
+    options.circ.ir.field_to_bv = circ_opt::FieldToBv::Panic;
+    circ::cfg::set(&options.circ);
+    let pin_path = options
+        .pin
+        .unwrap_or_else(|| with_suffix(&options.zsharp_path, ".pin"));
+    let vin_path = options
+        .vin
+        .unwrap_or_else(|| with_suffix(&options.zsharp_path, ".vin"));
+    let inputs = Inputs {
+        file: options.zsharp_path,
+        mode: Mode::Proof,
+    };
+    let src = std::fs::read(&options.inputs_path).unwrap();
+    let scalar_input_values = if options
+        .inputs_path
+        .extension()
+        .map_or(false, |e| e == "json")
+    {
+        ZSharpFE::witness_json_inputs(&inputs, &[options.witness_fn.as_str()], &src)
+            .unwrap_or_else(|e| panic!("{}", e))
+    } else {
+        parse_value_map(&src)
+    };
+    let (pin, vin) = ZSharpFE::witness(inputs, &options.witness_fn, scalar_input_values)
+        .unwrap_or_else(|e| panic!("{}", e));
+    std::fs::write(&pin_path, serialize_value_map(&pin.into_iter().collect())).unwrap();
+    std::fs::write(&vin_path, serialize_value_map(&vin.into_iter().collect())).unwrap();
+    println!("Wrote {} and {}", pin_path.display(), vin_path.display());
+}
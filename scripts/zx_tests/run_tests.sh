@@ -2,11 +2,17 @@
 
 TESTDIR=$(dirname -- "$0")
 ZXI=${TESTDIR}/../../target/release/examples/zxi
+ZXCHECK=${TESTDIR}/../../target/release/examples/zxcheck
+ZXTEST=$(realpath ${TESTDIR}/../../target/release/examples/zxtest)
 error=0
 
 echo Running zx should-pass tests:
 for i in ${TESTDIR}/*.zx; do
     infile="${i}.in"
+    if [[ ! -a $infile ]]
+    then
+        infile="${i}.json"
+    fi
     if [[ -a $infile ]]
     then
         output=$(${ZXI} "$i" "$infile")
@@ -64,5 +70,39 @@ for i in ${TESTDIR}/*.zxf; do
     fi
 done
 echo Done.
+echo
+
+echo Running zxcheck tests:
+for outfile in ${TESTDIR}/*.check; do
+    i="${outfile%.check}"
+    # paths are reported in full; compare them relative to this directory
+    output=$(${ZXCHECK} "$i" | sed "s|$(realpath ${TESTDIR})/||")
+    if [ "$(cat $outfile)" != "$output" ]; then
+        echo "[failure: check] $i"
+        echo "expected output: "
+        cat $outfile
+        echo "got output: "
+        echo "$output"
+        error=1
+    fi
+done
+echo Done.
+echo
+
+echo Running zxtest tests:
+for i in ${TESTDIR}/*.zxt; do
+    outfile="${i}.out"
+    # run from this directory, so that paths are reported relative to it
+    output=$(cd ${TESTDIR} && ${ZXTEST} $(basename "$i"))
+    if [ "$(cat $outfile)" != "$output" ]; then
+        echo "[failure: zxtest] $i"
+        echo "expected output: "
+        cat $outfile
+        echo "got output: "
+        echo "$output"
+        error=1
+    fi
+done
+echo Done.
 
 exit $error
@@ -17,6 +17,9 @@ pub enum ErrorKind {
     #[error("Cannot apply operator '{0}' to\n\t{1}\nand\n\t{2}")]
     /// Cannot apply this operator to these terms
     InvalidBinOp(String, T, T),
+    #[error("Cannot aggregate ('{0}') over an empty domain")]
+    /// Cannot aggregate over an empty domain
+    EmptyAggregate(String),
     #[error("Could not find entry rule '{0}'")]
     /// Could not find the entry rule
     MissingEntry(String),
@@ -1,11 +1,22 @@
 //! Datalog implementation
+//!
+//! Beyond rules, `exists` conditions, and `decreasing` recursion arguments, the language has:
+//!
+//! * tuple types, like `(u32, bool)`, whose members are accessed as `x.0`, `x.1`, ...
+//! * record types, like `{user: u32, level: u8}`, whose members are accessed as `x.user`, ...
+//! * bounded aggregates over the indices of an array: `count`, `sum`, `min`, or `max` of
+//!   `(i in xs where cond: value)`, where the `where` filter is optional. For `count`, the value is
+//!   a condition. `count` is a field element; `sum` has the values' type; `min` and `max` are of
+//!   unsigned integers.
+//!
+//! The entry rule (by default, `main`; see [Inputs]) gives the inputs.
 
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
 use std::str::FromStr;
 
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use log::debug;
 use rug::Integer;
 
@@ -28,8 +39,10 @@ use parser::ast;
 
 /// Inputs to the datalog compilier
 pub struct Inputs {
-    /// The file to look for `main` in.
+    /// The file to look for the entry rule in.
     pub file: PathBuf,
+    /// The entry rule (e.g., `main`); its arguments are the inputs.
+    pub entry: String,
 }
 
 struct Gen<'ast> {
@@ -90,19 +103,7 @@ impl<'ast> Gen<'ast> {
     /// Returns (ty, public)
     fn ty(&self, ty: &ast::QualType<'ast>) -> (ty::Ty, bool) {
         (
-            ty.ty.array_sizes.iter().fold(
-                match &ty.ty.base {
-                    ast::BaseType::Bool(_) => ty::Ty::Bool,
-                    ast::BaseType::Field(_) => ty::Ty::Field,
-                    ast::BaseType::Uint(u) => {
-                        ty::Ty::Uint(u8::from_str(&u.type_name[1..]).expect("bad uN"))
-                    }
-                },
-                |t, size| {
-                    let size = usize::from_str(size.value).expect("bad array size");
-                    ty::Ty::Array(size, Box::new(t))
-                },
-            ),
+            self.type_(&ty.ty),
             ty.qualifier
                 .as_ref()
                 .map(|q| match q {
@@ -113,11 +114,52 @@ impl<'ast> Gen<'ast> {
         )
     }
 
-    fn entry_rule(&mut self, name: &'ast str) -> Result<()> {
+    fn type_(&self, ty: &ast::Type<'ast>) -> ty::Ty {
+        ty.array_sizes.iter().fold(
+            match &ty.elem {
+                ast::ElemType::Base(ast::BaseType::Bool(_)) => ty::Ty::Bool,
+                ast::ElemType::Base(ast::BaseType::Field(_)) => ty::Ty::Field,
+                ast::ElemType::Base(ast::BaseType::Uint(u)) => {
+                    ty::Ty::Uint(u8::from_str(&u.type_name[1..]).expect("bad uN"))
+                }
+                ast::ElemType::Tuple(t) => ty::Ty::Tuple(
+                    t.fields
+                        .iter()
+                        .enumerate()
+                        .map(|(i, f)| (i.to_string(), self.type_(f)))
+                        .collect(),
+                ),
+                ast::ElemType::Record(r) => {
+                    let mut names = FxHashSet::default();
+                    ty::Ty::Tuple(
+                        r.fields
+                            .iter()
+                            .map(|f| {
+                                assert!(
+                                    names.insert(f.name.value),
+                                    "Duplicate field {} at {:?}",
+                                    f.name.value,
+                                    f.span
+                                );
+                                (f.name.value.to_owned(), self.type_(&f.ty))
+                            })
+                            .collect(),
+                    )
+                }
+            },
+            |t, size| {
+                let size = usize::from_str(size.value).expect("bad array size");
+                ty::Ty::Array(size, Box::new(t))
+            },
+        )
+    }
+
+    fn entry_rule(&mut self, name: &str) -> Result<'ast, ()> {
         let rule = *self
             .rules
             .get(name)
             .ok_or_else(|| ErrorKind::MissingEntry(name.into()))?;
+        let name = rule.name.value;
         self.enter_function(name, None);
         for d in &rule.args {
             let (ty, public) = self.ty(&d.ty);
@@ -171,11 +213,17 @@ impl<'ast> Gen<'ast> {
             ast::Expression::Literal(ref i) => self.literal(i),
             ast::Expression::Access(ref c) => {
                 let arr = self.ident(&c.arr)?;
-                c.idxs.iter().try_fold(arr, |arr, idx| {
-                    let idx_v = self.expr(idx, false)?;
-                    term::array_idx(&arr, &idx_v).map_err(|err| Error::new(err, *idx.span()))
+                c.accesses.iter().try_fold(arr, |arr, access| match access {
+                    ast::Access::Array(a) => {
+                        let idx_v = self.expr(&a.idx, false)?;
+                        term::array_idx(&arr, &idx_v).map_err(|err| Error::new(err, a.span))
+                    }
+                    ast::Access::Member(m) => {
+                        term::member(&arr, m.member.value).map_err(|err| Error::new(err, m.span))
+                    }
                 })
             }
+            ast::Expression::Aggregate(ref a) => self.aggregate(a),
             ast::Expression::Call(ref c) => {
                 let args = c
                     .args
@@ -234,6 +282,48 @@ impl<'ast> Gen<'ast> {
             }
         }
     }
+    /// Generate IR for an aggregate, by unrolling it over its domain's indices.
+    fn aggregate(&mut self, a: &'ast ast::AggregateExpression) -> Result<'ast, term::T> {
+        let domain = self.expr(&a.domain, false)?;
+        let n = match &domain.ty {
+            ty::Ty::Array(n, _) => *n,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidUnOp("aggregate over".into(), domain),
+                    *a.domain.span(),
+                ))
+            }
+        };
+        // (guard, value) for each index
+        let mut elems = Vec::new();
+        for i in 0..n {
+            self.circ.enter_scope();
+            self.circ.declare_init(
+                a.var.value.into(),
+                ty::Ty::Field,
+                Val::Term(term::pf_lit(i)),
+            )?;
+            let guard = match &a.filter {
+                Some(f) => self.expr(&f.cond, false)?,
+                None => term::bool_lit(true),
+            };
+            let value = self.expr(&a.value, false)?;
+            self.circ.exit_scope();
+            elems.push((guard, value));
+        }
+        let res = match &a.op {
+            ast::AggregateOperator::Count(_) => elems
+                .iter()
+                .map(|(g, v)| term::and(g, v))
+                .collect::<term::Result<Vec<_>>>()
+                .and_then(|conds| term::count(&conds)),
+            ast::AggregateOperator::Sum(_) => term::sum(&elems),
+            ast::AggregateOperator::Min(_) => term::min(&elems),
+            ast::AggregateOperator::Max(_) => term::max(&elems),
+        };
+        res.map_err(|err| Error::new(err, a.span))
+    }
+
     fn literal(&mut self, e: &ast::Literal) -> Result<'ast, term::T> {
         match e {
             ast::Literal::BinLiteral(ref b) => {
@@ -394,7 +484,7 @@ impl FrontEnd for Datalog {
         let r = if cfg().datalog.lint_prim_rec {
             g.lint_rules()
         } else {
-            g.entry_rule("main")
+            g.entry_rule(&i.entry)
         };
         if let Err(e) = r {
             eprintln!("{e}");
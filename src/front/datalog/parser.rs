@@ -165,6 +165,7 @@ pub mod ast {
         Access(AccessExpression<'ast>),
         Unary(UnaryExpression<'ast>),
         Paren(Box<Expression<'ast>>, Span<'ast>),
+        Aggregate(AggregateExpression<'ast>),
     }
 
     #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
@@ -185,11 +186,98 @@ pub mod ast {
         pub span: Span<'ast>,
     }
 
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::array_access))]
+    pub struct ArrayAccess<'ast> {
+        pub idx: Expression<'ast>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::member_access))]
+    pub struct MemberAccess<'ast> {
+        pub member: Ident<'ast>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::access))]
+    pub enum Access<'ast> {
+        Array(ArrayAccess<'ast>),
+        Member(MemberAccess<'ast>),
+    }
+
+    impl<'ast> Access<'ast> {
+        pub fn span(&self) -> &Span<'ast> {
+            match self {
+                Access::Array(a) => &a.span,
+                Access::Member(a) => &a.span,
+            }
+        }
+    }
+
     #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
     #[pest_ast(rule(Rule::access_expr))]
     pub struct AccessExpression<'ast> {
         pub arr: Ident<'ast>,
-        pub idxs: Vec<Expression<'ast>>,
+        pub accesses: Vec<Access<'ast>>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::agg_count))]
+    pub struct Count<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::agg_sum))]
+    pub struct Sum<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::agg_min))]
+    pub struct Min<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::agg_max))]
+    pub struct Max<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, FromPest, Clone)]
+    #[pest_ast(rule(Rule::agg_op))]
+    pub enum AggregateOperator<'ast> {
+        Count(Count<'ast>),
+        Sum(Sum<'ast>),
+        Min(Min<'ast>),
+        Max(Max<'ast>),
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::agg_filter))]
+    pub struct AggregateFilter<'ast> {
+        pub cond: Expression<'ast>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    /// `op(var in domain where filter: value)`
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::aggregate))]
+    pub struct AggregateExpression<'ast> {
+        pub op: AggregateOperator<'ast>,
+        pub var: Ident<'ast>,
+        pub domain: Box<Expression<'ast>>,
+        pub filter: Option<Box<AggregateFilter<'ast>>>,
+        pub value: Box<Expression<'ast>>,
         #[pest_ast(outer())]
         pub span: Span<'ast>,
     }
@@ -235,6 +323,7 @@ pub mod ast {
                 Expression::Call(u) => &u.span,
                 Expression::Access(u) => &u.span,
                 Expression::Paren(_, s) => s,
+                Expression::Aggregate(u) => &u.span,
             }
         }
     }
@@ -357,6 +446,9 @@ pub mod ast {
                     Rule::access_expr => Expression::Access(
                         AccessExpression::from_pest(&mut pair.into_inner()).unwrap(),
                     ),
+                    Rule::aggregate => Expression::Aggregate(
+                        AggregateExpression::from_pest(&mut pair.into_inner()).unwrap(),
+                    ),
                     r => unreachable!("expected `term`, found {:#?}", r),
                 }
             }
@@ -412,10 +504,43 @@ pub mod ast {
         }
     }
 
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::ty_tuple))]
+    pub struct TupleType<'ast> {
+        pub fields: Vec<Type<'ast>>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::ty_record_field))]
+    pub struct RecordField<'ast> {
+        pub name: Ident<'ast>,
+        pub ty: Type<'ast>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::ty_record))]
+    pub struct RecordType<'ast> {
+        pub fields: Vec<RecordField<'ast>>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
+    #[pest_ast(rule(Rule::elem_ty))]
+    pub enum ElemType<'ast> {
+        Base(BaseType<'ast>),
+        Tuple(TupleType<'ast>),
+        Record(RecordType<'ast>),
+    }
+
     #[derive(Debug, FromPest, PartialEq, Eq, Clone)]
     #[pest_ast(rule(Rule::ty))]
     pub struct Type<'ast> {
-        pub base: BaseType<'ast>,
+        pub elem: ElemType<'ast>,
         pub array_sizes: Vec<DecimalLiteral<'ast>>,
         #[pest_ast(outer())]
         pub span: Span<'ast>,
@@ -34,6 +34,11 @@ impl T {
             (Ty::Bool, Sort::Bool) | (Ty::Field, Sort::Field(_)) => {}
             (Ty::Uint(w), Sort::BitVector(w2)) if *w as usize == *w2 => {}
             (Ty::Array(l, t), Sort::Array(a)) if *l == a.size => Self::check_ty(&a.val, t),
+            (Ty::Tuple(fields), Sort::Tuple(sorts)) if fields.len() == sorts.len() => {
+                for ((_, t), s) in fields.iter().zip(sorts.iter()) {
+                    Self::check_ty(s, t);
+                }
+            }
             _ => panic!("IR sort {} doesn't match datalog type {}", ir, ty),
         }
     }
@@ -83,6 +88,7 @@ impl Ty {
             Self::Uint(w) => Sort::BitVector(*w as usize),
             Self::Field => Sort::Field(cfg().field().clone()),
             Self::Array(n, b) => Sort::new_array(Sort::Field(cfg().field().clone()), b.sort(), *n),
+            Self::Tuple(fields) => Sort::new_tuple(fields.iter().map(|(_, t)| t.sort()).collect()),
         }
     }
     fn default_ir_term(&self) -> Term {
@@ -336,6 +342,88 @@ pub fn array_idx(a: &T, i: &T) -> Result<T> {
     }
 }
 
+/// Tuple or record member
+pub fn member(t: &T, name: &str) -> Result<T> {
+    match &t.ty {
+        Ty::Tuple(fields) => match fields.iter().position(|(n, _)| n == name) {
+            Some(i) => Ok(T::new(
+                term![Op::Field(i); t.ir.clone()],
+                fields[i].1.clone(),
+            )),
+            None => Err(ErrorKind::InvalidUnOp(format!(".{name}"), t.clone())),
+        },
+        _ => Err(ErrorKind::InvalidUnOp(format!(".{name}"), t.clone())),
+    }
+}
+
+/// If-then-else
+pub fn ite(c: &T, t: &T, f: &T) -> Result<T> {
+    match &c.ty {
+        Ty::Bool if t.ty == f.ty => Ok(T::new(
+            term![Op::Ite; c.ir.clone(), t.ir.clone(), f.ir.clone()],
+            t.ty.clone(),
+        )),
+        _ => Err(ErrorKind::InvalidBinOp("ite".into(), t.clone(), f.clone())),
+    }
+}
+
+/// Aggregate `count`: how many of `conds` hold, as a field element
+pub fn count(conds: &[T]) -> Result<T> {
+    conds.iter().try_fold(pf_lit(0), |acc, c| {
+        add(&acc, &ite(c, &pf_lit(1), &pf_lit(0))?)
+    })
+}
+
+/// Aggregate `sum`: the sum of the values whose guards hold
+///
+/// Each element is a (guard, value) pair.
+pub fn sum(elems: &[(T, T)]) -> Result<T> {
+    let (_, first) = elems
+        .first()
+        .ok_or_else(|| ErrorKind::EmptyAggregate("sum".into()))?;
+    elems.iter().try_fold(first.ty.default(), |acc, (g, v)| {
+        add(&acc, &ite(g, v, &v.ty.default())?)
+    })
+}
+
+/// Aggregate `min`: the least value whose guard holds, or the greatest integer if none do
+///
+/// Each element is a (guard, value) pair.
+pub fn min(elems: &[(T, T)]) -> Result<T> {
+    extremum("min", elems, lt, |w| {
+        T::new(
+            bv_lit((Integer::from(1) << w as u32) - 1, w as usize),
+            Ty::Uint(w),
+        )
+    })
+}
+
+/// Aggregate `max`: the greatest value whose guard holds, or zero if none do
+///
+/// Each element is a (guard, value) pair.
+pub fn max(elems: &[(T, T)]) -> Result<T> {
+    extremum("max", elems, gt, |w| uint_lit(0, w))
+}
+
+fn extremum(
+    name: &str,
+    elems: &[(T, T)],
+    better: fn(&T, &T) -> Result<T>,
+    init: fn(u8) -> T,
+) -> Result<T> {
+    let (_, first) = elems
+        .first()
+        .ok_or_else(|| ErrorKind::EmptyAggregate(name.into()))?;
+    let w = match &first.ty {
+        Ty::Uint(w) => *w,
+        _ => return Err(ErrorKind::InvalidUnOp(name.into(), first.clone())),
+    };
+    elems.iter().try_fold(init(w), |acc, (g, v)| {
+        let take = and(g, &better(v, &acc)?)?;
+        ite(&take, v, &acc)
+    })
+}
+
 /// Datalog lang def
 pub struct Datalog;
 
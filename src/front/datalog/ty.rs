@@ -13,6 +13,8 @@ pub enum Ty {
     Uint(u8),
     /// array
     Array(usize, Box<Ty>),
+    /// tuple or record: named fields (a tuple's are named `0`, `1`, ...)
+    Tuple(Vec<(String, Ty)>),
 }
 
 impl Display for Ty {
@@ -22,6 +24,24 @@ impl Display for Ty {
             Ty::Field => write!(f, "field"),
             Ty::Uint(w) => write!(f, "u{w}"),
             Ty::Array(l, t) => write!(f, "{t}[{l}]"),
+            Ty::Tuple(fields) => {
+                let is_tuple = fields
+                    .iter()
+                    .enumerate()
+                    .all(|(i, (name, _))| *name == i.to_string());
+                write!(f, "{}", if is_tuple { "(" } else { "{" })?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if is_tuple {
+                        write!(f, "{ty}")?;
+                    } else {
+                        write!(f, "{name}: {ty}")?;
+                    }
+                }
+                write!(f, "{}", if is_tuple { ")" } else { "}" })
+            }
         }
     }
 }
@@ -0,0 +1,161 @@
+//! Checking Z# without generating constraints, for editors and other tools.
+//!
+//! [super::ZSharpFE::check] parses a file (and its imports) and type-checks every declaration
+//! with the same inference that precedes elaboration, but does not elaborate anything. It returns
+//! a [Check]: every error it found, as a [Diagnostic], and the inferred types of identifiers, as
+//! [TypeInfo]s (e.g., for hovers).
+//!
+//! Checking stops at the first error in each declaration (constant, struct, type, or function),
+//! but continues with the next declaration.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use zokrates_pest_ast as ast;
+
+/// A range of source text. Lines and columns start at 1; the end is exclusive.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct SrcRange {
+    /// The file
+    pub file: PathBuf,
+    /// The (line, column) of the first character
+    pub start: (usize, usize),
+    /// The (line, column) just after the last character
+    pub end: (usize, usize),
+}
+
+impl SrcRange {
+    pub(super) fn from_span(file: &Path, span: &ast::Span) -> Self {
+        Self {
+            file: file.to_owned(),
+            start: span.start_pos().line_col(),
+            end: span.end_pos().line_col(),
+        }
+    }
+
+    /// Does this range contain the character at `(line, col)` of `file`?
+    pub fn contains(&self, file: &Path, line: usize, col: usize) -> bool {
+        self.file == file && self.start <= (line, col) && (line, col) < self.end
+    }
+}
+
+/// An error in a Z# program.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Where the error is
+    pub range: SrcRange,
+    /// What the error is
+    pub message: String,
+}
+
+/// The type of an identifier (a use or a declaration).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TypeInfo {
+    /// Where the identifier is
+    pub range: SrcRange,
+    /// The identifier
+    pub name: String,
+    /// Its type
+    pub ty: String,
+}
+
+/// The result of [super::ZSharpFE::check].
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Check {
+    /// All errors found
+    pub diagnostics: Vec<Diagnostic>,
+    /// The types of identifiers
+    pub types: Vec<TypeInfo>,
+}
+
+impl Check {
+    /// The type of the innermost identifier at `(line, col)` of `file`, if any.
+    pub fn type_at(&self, file: &Path, line: usize, col: usize) -> Option<&TypeInfo> {
+        self.types
+            .iter()
+            .filter(|t| t.range.contains(file, line, col))
+            .min_by_key(|t| (t.range.end.0 - t.range.start.0, t.range.end.1))
+    }
+
+    /// Canonicalize the paths of all ranges, and drop duplicate types (an identifier may be
+    /// visited more than once).
+    pub(super) fn canonical(mut self) -> Self {
+        let canonical = |r: &mut SrcRange| {
+            if let Ok(f) = r.file.canonicalize() {
+                r.file = f;
+            }
+        };
+        self.diagnostics
+            .iter_mut()
+            .for_each(|d| canonical(&mut d.range));
+        self.types.iter_mut().for_each(|t| canonical(&mut t.range));
+        let mut seen = HashSet::new();
+        self.types.retain(|t| seen.insert(t.range.clone()));
+        self
+    }
+}
+
+/// The payload of the unwinding that aborts checking a declaration, after its error has been
+/// recorded.
+pub(super) struct Abort;
+
+/// The message of a panic payload.
+pub(super) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else {
+        "internal error".to_string()
+    }
+}
+
+/// Write a type as it would appear in Z# source.
+pub(super) fn type_string(ty: &ast::Type) -> String {
+    match ty {
+        ast::Type::Basic(b) => basic_type_string(b).to_string(),
+        ast::Type::Array(a) => {
+            let elem = match &a.ty {
+                ast::BasicOrStructType::Basic(b) => basic_type_string(b).to_string(),
+                ast::BasicOrStructType::Struct(s) => struct_type_string(s),
+            };
+            a.dimensions
+                .iter()
+                .fold(elem, |acc, d| format!("{}[{}]", acc, d.span().as_str()))
+        }
+        ast::Type::Struct(s) => struct_type_string(s),
+        ast::Type::Tuple(t) => format!(
+            "({})",
+            t.elements
+                .iter()
+                .map(type_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+fn basic_type_string(ty: &ast::BasicType) -> &'static str {
+    use ast::BasicType::*;
+    match ty {
+        Field(_) => "field",
+        Boolean(_) => "bool",
+        U8(_) => "u8",
+        U16(_) => "u16",
+        U32(_) => "u32",
+        U64(_) => "u64",
+        U128(_) => "u128",
+        U256(_) => "u256",
+        I8(_) => "i8",
+        I16(_) => "i16",
+        I32(_) => "i32",
+        I64(_) => "i64",
+    }
+}
+
+fn struct_type_string(ty: &ast::StructType) -> String {
+    match &ty.explicit_generics {
+        Some(g) => format!("{}{}", ty.id.value, g.span.as_str()),
+        None => ty.id.value.clone(),
+    }
+}
@@ -0,0 +1,395 @@
+//! A source-level debugger for the Z# interpreter.
+//!
+//! The interpreter ([super::ZSharpFE::debug]) consults a [Debugger] before executing each
+//! statement. The debugger decides (based on breakpoints and the current stepping mode) whether
+//! to stop; when it does, it hands a [DebugState] to a [DebugFrontend] (e.g., a REPL), which can
+//! inspect locals and the call stack, edit breakpoints, and decide how to resume.
+
+use super::term::{array_select, const_val, field_select, tuple_select, T};
+use super::{ZAccess, ZGen};
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use zokrates_pest_ast as ast;
+
+/// A breakpoint: a line, optionally restricted to files whose path ends with `file`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Breakpoint {
+    /// The file suffix (e.g., `sponge.zok`). `None` matches any file.
+    pub file: Option<String>,
+    /// The (1-indexed) line number.
+    pub line: usize,
+}
+
+impl Breakpoint {
+    fn matches(&self, loc: &Location) -> bool {
+        self.line == loc.line
+            && self
+                .file
+                .as_ref()
+                .map(|f| loc.file.ends_with(f))
+                .unwrap_or(true)
+    }
+}
+
+impl std::str::FromStr for Breakpoint {
+    type Err = String;
+    /// Parses `LINE` or `FILE:LINE`.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (file, line) = match s.rsplit_once(':') {
+            Some((f, l)) => (Some(f.to_string()), l),
+            None => (None, s),
+        };
+        let line = line
+            .parse::<usize>()
+            .map_err(|e| format!("Bad line number '{line}': {e}"))?;
+        Ok(Breakpoint { file, line })
+    }
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}", file, self.line),
+            None => write!(f, "{}", self.line),
+        }
+    }
+}
+
+/// A source location: the statement about to execute.
+#[derive(Clone, Debug)]
+pub struct Location {
+    /// The file containing the statement
+    pub file: PathBuf,
+    /// The (1-indexed) line of the start of the statement
+    pub line: usize,
+    /// The (1-indexed) column of the start of the statement
+    pub col: usize,
+    /// The first line of the statement's text
+    pub text: String,
+}
+
+/// A call-stack frame.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    /// The function name
+    pub function: String,
+    /// The file the function is defined in
+    pub file: PathBuf,
+    /// The generic arguments of this call, rendered as strings
+    pub generics: Vec<(String, String)>,
+}
+
+/// How to resume after a stop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resume {
+    /// Run until the next breakpoint
+    Continue,
+    /// Stop at the next statement, entering calls
+    Step,
+    /// Stop at the next statement in this function (or its callers)
+    Next,
+    /// Stop at the next statement after this function returns
+    Finish,
+}
+
+/// A user interface for the debugger.
+pub trait DebugFrontend {
+    /// Called when the interpreter stops before a statement. The frontend may edit
+    /// `breakpoints`, and returns how to resume.
+    fn stopped(&mut self, state: &DebugState, breakpoints: &mut BTreeSet<Breakpoint>) -> Resume;
+}
+
+/// Breakpoint and stepping state, driving a [DebugFrontend].
+pub struct Debugger {
+    frontend: Box<dyn DebugFrontend>,
+    breakpoints: BTreeSet<Breakpoint>,
+    resume: Resume,
+    // the call depth at which we last stopped
+    depth: usize,
+}
+
+impl Debugger {
+    /// Create a debugger that stops at the first statement.
+    pub fn new(frontend: Box<dyn DebugFrontend>) -> Self {
+        Self {
+            frontend,
+            breakpoints: BTreeSet::new(),
+            resume: Resume::Step,
+            depth: 0,
+        }
+    }
+
+    /// Add a breakpoint
+    pub fn add_breakpoint(&mut self, b: Breakpoint) {
+        self.breakpoints.insert(b);
+    }
+
+    /// Set the initial resume mode (by default, stop at the first statement).
+    pub fn set_resume(&mut self, r: Resume) {
+        self.resume = r;
+    }
+
+    fn should_stop(&self, loc: &Location, depth: usize) -> bool {
+        self.breakpoints.iter().any(|b| b.matches(loc))
+            || match self.resume {
+                Resume::Continue => false,
+                Resume::Step => true,
+                Resume::Next => depth <= self.depth,
+                Resume::Finish => depth < self.depth,
+            }
+    }
+
+    pub(super) fn before_stmt(&mut self, state: &DebugState) {
+        let depth = state.depth();
+        if self.should_stop(&state.loc, depth) {
+            self.depth = depth;
+            self.resume = self.frontend.stopped(state, &mut self.breakpoints);
+        }
+    }
+}
+
+/// The interpreter state at a stop.
+pub struct DebugState<'a, 'ast> {
+    gen: &'a ZGen<'ast>,
+    loc: Location,
+}
+
+impl<'a, 'ast> DebugState<'a, 'ast> {
+    pub(super) fn new(gen: &'a ZGen<'ast>, s: &ast::Statement<'ast>) -> Self {
+        let span = s.span();
+        let (line, col) = span.start_pos().line_col();
+        let text = span
+            .as_str()
+            .lines()
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        Self {
+            gen,
+            loc: Location {
+                file: gen.cur_path(),
+                line,
+                col,
+                text,
+            },
+        }
+    }
+
+    /// The statement about to execute.
+    pub fn location(&self) -> &Location {
+        &self.loc
+    }
+
+    /// The number of active (non-builtin) function calls.
+    pub fn depth(&self) -> usize {
+        self.gen.fn_stack.borrow().len()
+    }
+
+    /// The call stack, innermost frame last.
+    pub fn stack(&self) -> Vec<Frame> {
+        let files = self.gen.file_stack.borrow();
+        let generics = self.gen.generics_stack.borrow();
+        let calls = self.gen.fn_stack.borrow();
+        // the file and generics stacks have one extra (outermost) entry for the entry file
+        let off = files.len() - calls.len();
+        calls
+            .iter()
+            .enumerate()
+            .map(|(i, (file, function))| {
+                let mut generics: Vec<(String, String)> = generics
+                    .get(i + off)
+                    .map(|g| g.iter().map(|(k, v)| (k.clone(), render(v))).collect())
+                    .unwrap_or_default();
+                generics.sort();
+                Frame {
+                    function: function.clone(),
+                    file: file.clone(),
+                    generics,
+                }
+            })
+            .collect()
+    }
+
+    /// All local variables visible in the current function, innermost scope last.
+    pub fn locals(&self) -> Vec<(String, String)> {
+        let stack = self.gen.cvars_stack.borrow();
+        let mut out = Vec::new();
+        if let Some(scopes) = stack.last() {
+            for scope in scopes {
+                let mut vars: Vec<_> = scope.iter().map(|(k, v)| (k.clone(), render(v))).collect();
+                vars.sort();
+                out.extend(vars);
+            }
+        }
+        out
+    }
+
+    /// Look up a variable, with optional member, tuple, and constant-index accesses, e.g.,
+    /// `s.x[2].0`.
+    ///
+    /// Locals are searched first, then generic parameters, then constants.
+    pub fn inspect(&self, path: &str) -> Result<String, String> {
+        let (name, accs) = parse_access_path(path)?;
+        let val = self
+            .gen
+            .cvar_lookup(&name)
+            .or_else(|| self.gen.generic_lookup_(&name))
+            .or_else(|| self.gen.const_lookup_(&name).cloned())
+            .ok_or_else(|| format!("No variable '{name}' in scope"))?;
+        let val = accs.into_iter().try_fold(val, |v, acc| match acc {
+            ZAccess::Member(m) => field_select(&v, &m),
+            ZAccess::Idx(i) => array_select(v, i),
+            ZAccess::Elem(i) => tuple_select(&v, i),
+        })?;
+        Ok(render(&const_val(val)?))
+    }
+}
+
+fn render(v: &T) -> String {
+    let mut buf = Vec::new();
+    match v.pretty(&mut buf) {
+        Ok(()) => String::from_utf8_lossy(&buf).into_owned(),
+        // not a constant
+        Err(_) => format!("{v}"),
+    }
+}
+
+fn parse_access_path(path: &str) -> Result<(String, Vec<ZAccess>), String> {
+    let path = path.trim();
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    let name = path[..end].to_string();
+    if name.is_empty() {
+        return Err(format!("Bad variable path '{path}'"));
+    }
+    let mut rest = &path[end..];
+    let mut accs = Vec::new();
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('.') {
+            let end = r.find(['.', '[']).unwrap_or(r.len());
+            let seg = &r[..end];
+            accs.push(match seg.parse::<usize>() {
+                Ok(i) => ZAccess::Elem(i),
+                Err(_) => ZAccess::Member(seg.to_string()),
+            });
+            rest = &r[end..];
+        } else if let Some(r) = rest.strip_prefix('[') {
+            let end = r
+                .find(']')
+                .ok_or_else(|| format!("Unclosed '[' in '{path}'"))?;
+            let idx = r[..end]
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| format!("Bad index in '{path}': {e}"))?;
+            accs.push(ZAccess::Idx(T::new_field(idx)));
+            rest = &r[end + 1..];
+        } else {
+            return Err(format!("Bad variable path '{path}'"));
+        }
+    }
+    Ok((name, accs))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn breakpoint_from_str() {
+        let b: Breakpoint = "12".parse().unwrap();
+        assert_eq!(
+            b,
+            Breakpoint {
+                file: None,
+                line: 12
+            }
+        );
+        let b: Breakpoint = "hash/sponge.zok:7".parse().unwrap();
+        assert_eq!(b.file.as_deref(), Some("hash/sponge.zok"));
+        assert_eq!(b.line, 7);
+        assert_eq!(b.to_string(), "hash/sponge.zok:7");
+        assert!("sponge.zok:".parse::<Breakpoint>().is_err());
+        assert!("sponge.zok".parse::<Breakpoint>().is_err());
+        assert!("-1".parse::<Breakpoint>().is_err());
+    }
+
+    /// Render accesses as Z# syntax, for comparison.
+    fn accesses(path: &str) -> Result<(String, Vec<String>), String> {
+        let (name, accs) = parse_access_path(path)?;
+        let accs = accs
+            .into_iter()
+            .map(|a| match a {
+                ZAccess::Member(m) => format!(".{m}"),
+                ZAccess::Idx(i) => format!("[{i}]"),
+                ZAccess::Elem(i) => format!(".{i}"),
+            })
+            .collect();
+        Ok((name, accs))
+    }
+
+    #[test]
+    fn access_paths() {
+        // indices are field elements
+        crate::cfg::cfg_or_default();
+        assert_eq!(accesses(" x ").unwrap(), ("x".to_string(), vec![]));
+        let two = format!("[{}]", T::new_field(2));
+        assert_eq!(
+            accesses("s.x[2].0").unwrap(),
+            (
+                "s".to_string(),
+                vec![".x".to_string(), two.clone(), ".0".to_string()]
+            )
+        );
+        assert_eq!(accesses("a[ 2 ][2]").unwrap().1, vec![two.clone(), two]);
+        assert!(accesses("s.x[2").unwrap_err().contains("Unclosed"));
+        assert!(accesses("a[i]").unwrap_err().contains("Bad index"));
+        assert!(accesses(".x").is_err());
+        assert!(accesses("[0]").is_err());
+    }
+
+    struct NoFrontend;
+
+    impl DebugFrontend for NoFrontend {
+        fn stopped(&mut self, _: &DebugState, _: &mut BTreeSet<Breakpoint>) -> Resume {
+            unreachable!()
+        }
+    }
+
+    fn loc(file: &str, line: usize) -> Location {
+        Location {
+            file: PathBuf::from(file),
+            line,
+            col: 1,
+            text: String::new(),
+        }
+    }
+
+    #[test]
+    fn stepping_depths() {
+        let mut d = Debugger::new(Box::new(NoFrontend));
+        let l = loc("main.zok", 3);
+        // we last stopped at depth 1
+        d.depth = 1;
+        d.set_resume(Resume::Step);
+        assert!(d.should_stop(&l, 0) && d.should_stop(&l, 1) && d.should_stop(&l, 2));
+        d.set_resume(Resume::Next);
+        assert!(d.should_stop(&l, 0) && d.should_stop(&l, 1) && !d.should_stop(&l, 2));
+        d.set_resume(Resume::Finish);
+        assert!(d.should_stop(&l, 0) && !d.should_stop(&l, 1) && !d.should_stop(&l, 2));
+        d.set_resume(Resume::Continue);
+        assert!(!d.should_stop(&l, 0) && !d.should_stop(&l, 1));
+    }
+
+    #[test]
+    fn breakpoints_stop_at_any_depth() {
+        let mut d = Debugger::new(Box::new(NoFrontend));
+        d.set_resume(Resume::Finish);
+        d.add_breakpoint("lib/sponge.zok:3".parse().unwrap());
+        d.add_breakpoint("9".parse().unwrap());
+        assert!(d.should_stop(&loc("/a/lib/sponge.zok", 3), 4));
+        assert!(!d.should_stop(&loc("/a/lib/other.zok", 3), 4));
+        assert!(!d.should_stop(&loc("/a/lib/sponge.zok", 4), 4));
+        assert!(d.should_stop(&loc("/a/main.zok", 9), 4));
+    }
+}
@@ -1,8 +1,11 @@
 //! Utilities for interpretting zsharp
 
 use super::term::{Ty, T};
+use crate::cfg::cfg;
 use crate::ir::term::*;
 use fxhash::FxHashMap as HashMap;
+use rug::Integer;
+use serde_json::Value as Json;
 
 /// Given
 /// * a variable name,
@@ -17,7 +20,7 @@ pub fn extract(
     scalar_input_values: &mut HashMap<String, Value>,
 ) -> Result<T, String> {
     match ty {
-        Ty::Bool | Ty::Field | Ty::Uint(..) => {
+        Ty::Bool | Ty::Field | Ty::Uint(..) | Ty::Int(..) => {
             let ir_val = scalar_input_values
                 .remove(name)
                 .ok_or_else(|| format!("Could not find scalar variable {name} in the input map"))?;
@@ -45,5 +48,187 @@ pub fn extract(
                 })
                 .collect::<Result<Vec<_>, _>>()?,
         )),
+        Ty::Tuple(tys) => Ok(T::new_tuple(
+            tys.iter()
+                .enumerate()
+                .map(|(i, ty)| extract(&format!("{name}.{i}"), ty, scalar_input_values))
+                .collect::<Result<Vec<_>, _>>()?,
+        )),
+    }
+}
+
+/// Given
+/// * a variable name,
+/// * a variable type, and
+/// * a constant value of that type,
+///
+/// adds its scalar values to a map from delimited names (e.g., "x", "x.0", "x.field_name") to
+/// values. This is the inverse of [extract].
+pub fn flatten(
+    name: &str,
+    ty: &Ty,
+    value: &Value,
+    scalar_input_values: &mut HashMap<String, Value>,
+) -> Result<(), String> {
+    match (ty, value) {
+        (Ty::Bool, Value::Bool(_)) | (Ty::Field, Value::Field(_)) => {
+            scalar_input_values.insert(name.to_string(), value.clone());
+            Ok(())
+        }
+        (Ty::Uint(w) | Ty::Int(w), Value::BitVector(bv)) if bv.width() == *w => {
+            scalar_input_values.insert(name.to_string(), value.clone());
+            Ok(())
+        }
+        (Ty::Array(elem_count, elem_ty), Value::Array(arr)) => arr
+            .key_sort
+            .elems_iter_values()
+            .take(*elem_count)
+            .enumerate()
+            .try_for_each(|(i, idx)| {
+                let v = arr.select(&idx);
+                flatten(&format!("{name}.{i}"), elem_ty, &v, scalar_input_values)
+            }),
+        (Ty::MutArray(elem_count), Value::Array(arr)) => arr
+            .key_sort
+            .elems_iter_values()
+            .take(*elem_count)
+            .enumerate()
+            .try_for_each(|(i, idx)| {
+                let v = arr.select(&idx);
+                flatten(&format!("{name}.{i}"), &Ty::Field, &v, scalar_input_values)
+            }),
+        (Ty::Struct(_, fields), Value::Tuple(vs)) => {
+            fields
+                .fields()
+                .zip(vs.iter())
+                .try_for_each(|((f_name, f_ty), v)| {
+                    flatten(&format!("{name}.{f_name}"), f_ty, v, scalar_input_values)
+                })
+        }
+        (Ty::Tuple(tys), Value::Tuple(vs)) => {
+            tys.iter()
+                .zip(vs.iter())
+                .enumerate()
+                .try_for_each(|(i, (ty, v))| {
+                    flatten(&format!("{name}.{i}"), ty, v, scalar_input_values)
+                })
+        }
+        _ => Err(format!("Expected a {ty} for {name}, got {value}")),
+    }
+}
+
+/// Given
+/// * a variable name,
+/// * a variable type, and
+/// * a JSON value of that type,
+///
+/// adds the scalar values in the JSON value to a map from delimited names (e.g., "x", "x.0",
+/// "x.field_name") to values. This is the inverse of [extract].
+///
+/// Arrays and tuples are JSON arrays, structures are JSON objects, and booleans are JSON booleans.
+/// Field elements and integers are JSON numbers, or strings holding decimal or `0x`-prefixed hex
+/// numbers.
+pub fn flatten_json(
+    name: &str,
+    ty: &Ty,
+    json: &Json,
+    scalar_input_values: &mut HashMap<String, Value>,
+) -> Result<(), String> {
+    let value = match ty {
+        Ty::Bool => Value::Bool(
+            json.as_bool()
+                .ok_or_else(|| format!("Expected a boolean for {name}, got {json}"))?,
+        ),
+        Ty::Field => Value::Field(cfg().field().new_v(json_integer(name, json)?)),
+        Ty::Uint(w) => {
+            let i = json_integer(name, json)?;
+            if i < 0 || i.significant_bits() as usize > *w {
+                return Err(format!("Value {i} for {name} does not fit in a u{w}"));
+            }
+            Value::BitVector(BitVector::new(i, *w))
+        }
+        Ty::Int(w) => {
+            let i = json_integer(name, json)?;
+            let half = Integer::from(1) << (*w as u32 - 1);
+            if i >= half || i < Integer::from(-&half) {
+                return Err(format!("Value {i} for {name} does not fit in an i{w}"));
+            }
+            Value::BitVector(BitVector::new(i.keep_bits(*w as u32), *w))
+        }
+        Ty::Array(elem_count, elem_ty) => {
+            return json_elements(name, json, *elem_count)?
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, j)| {
+                    flatten_json(&format!("{name}.{i}"), elem_ty, j, scalar_input_values)
+                });
+        }
+        Ty::MutArray(elem_count) => {
+            return json_elements(name, json, *elem_count)?
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, j)| {
+                    flatten_json(&format!("{name}.{i}"), &Ty::Field, j, scalar_input_values)
+                });
+        }
+        Ty::Tuple(tys) => {
+            return json_elements(name, json, tys.len())?
+                .iter()
+                .zip(tys)
+                .enumerate()
+                .try_for_each(|(i, (j, ty))| {
+                    flatten_json(&format!("{name}.{i}"), ty, j, scalar_input_values)
+                });
+        }
+        Ty::Struct(s_name, fields) => {
+            let obj = json
+                .as_object()
+                .ok_or_else(|| format!("Expected a {s_name} object for {name}, got {json}"))?;
+            if let Some(f_name) = obj.keys().find(|f| fields.search(f).is_none()) {
+                return Err(format!("Struct {s_name} has no field {f_name} (in {name})"));
+            }
+            return fields.fields().try_for_each(|(f_name, f_ty)| {
+                let j = obj
+                    .get(f_name)
+                    .ok_or_else(|| format!("Missing field {f_name} of {name}"))?;
+                flatten_json(&format!("{name}.{f_name}"), f_ty, j, scalar_input_values)
+            });
+        }
+    };
+    scalar_input_values.insert(name.to_string(), value);
+    Ok(())
+}
+
+fn json_elements<'a>(name: &str, json: &'a Json, len: usize) -> Result<&'a [Json], String> {
+    match json.as_array() {
+        Some(a) if a.len() == len => Ok(a),
+        Some(a) => Err(format!(
+            "Expected {len} elements for {name}, got {}",
+            a.len()
+        )),
+        None => Err(format!("Expected an array for {name}, got {json}")),
+    }
+}
+
+fn json_integer(name: &str, json: &Json) -> Result<Integer, String> {
+    match json {
+        Json::Number(n) => n
+            .as_i64()
+            .map(Integer::from)
+            .or_else(|| n.as_u64().map(Integer::from))
+            .ok_or_else(|| format!("Expected an integer for {name}, got {n}")),
+        Json::String(s) => {
+            let (neg, digits) = match s.strip_prefix('-') {
+                Some(d) => (true, d),
+                None => (false, s.as_str()),
+            };
+            let i = match digits.strip_prefix("0x") {
+                Some(h) => Integer::from_str_radix(h, 16),
+                None => Integer::from_str_radix(digits, 10),
+            }
+            .map_err(|e| format!("Bad number {s:?} for {name}: {e}"))?;
+            Ok(if neg { -i } else { i })
+        }
+        _ => Err(format!("Expected a number for {name}, got {json}")),
     }
 }
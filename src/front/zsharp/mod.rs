@@ -1,5 +1,7 @@
 //! The ZoKrates/Z# front-end
 
+pub mod check;
+pub mod debug;
 mod interp;
 mod parser;
 mod term;
@@ -18,7 +20,7 @@ use rug::Integer;
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fmt::Display;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use zokrates_pest_ast as ast;
 
@@ -36,6 +38,42 @@ pub struct Inputs {
     pub mode: Mode,
 }
 
+/// A `main` that is a bounded loop over a fixed step function:
+///
+/// ```text
+/// def main(State s) -> State:
+///     for u32 i in 0..STEPS do
+///         s = step(s)
+///     endfor
+///     return s
+/// ```
+///
+/// Such programs are uniform: every iteration applies the same transition to the state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformLoop {
+    /// The step function
+    pub step: String,
+    /// The name of the step function's parameter
+    pub param: String,
+    /// The name of the loop state in `main`
+    pub state: String,
+    /// The number of iterations
+    pub steps: usize,
+}
+
+/// The outcome of a `#[test]` function. See [ZSharpFE::test].
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    /// The file the test is in
+    pub file: PathBuf,
+    /// The test function
+    pub name: String,
+    /// The line the test function starts on
+    pub line: usize,
+    /// Why the test failed, if it did
+    pub result: Result<(), String>,
+}
+
 /// The Z# front-end. Implements [FrontEnd].
 pub struct ZSharpFE;
 
@@ -46,18 +84,16 @@ impl FrontEnd for ZSharpFE {
             "Starting Z# front-end, field: {}",
             Sort::Field(cfg().field().clone())
         );
-        let loader = parser::ZLoad::new();
-        let asts = loader.load(&i.file);
-        let mut g = ZGen::new(asts, i.mode, loader.stdlib(), cfg().zsharp.isolate_asserts);
-        g.visit_files();
-        g.file_stack_push(i.file);
-        g.generics_stack_push(HashMap::new());
-        g.entry_fn("main");
-        g.generics_stack_pop();
-        g.file_stack_pop();
+        if !cfg().r1cs.source_profile.is_empty() {
+            src_loc::enable();
+        }
+        let circ = Self::with_gen(&i, |g, _| {
+            g.entry_fn("main");
+            g.take_circify()
+        });
 
         let mut cs = Computations::new();
-        let main_comp = std::rc::Rc::try_unwrap(g.into_circify().consume())
+        let main_comp = std::rc::Rc::try_unwrap(circ.consume())
             .unwrap_or_else(|rc| (*rc).clone())
             .into_inner();
         cs.comps.insert("main".to_string(), main_comp);
@@ -68,13 +104,162 @@ impl FrontEnd for ZSharpFE {
 impl ZSharpFE {
     /// Execute the Z# front-end interpreter on the supplied file with the supplied inputs
     pub fn interpret(i: Inputs, input_scalar_values: FxHashMap<String, Value>) -> T {
+        Self::with_gen(&i, |g, _| g.const_entry_fn("main", input_scalar_values))
+    }
+
+    /// Convert JSON inputs to the map from scalar input names to values that [ZSharpFE::interpret]
+    /// and the proof systems take.
+    ///
+    /// The JSON is an object keyed by the names of `main`'s parameters (and `return`, for its
+    /// return value). Keys may be omitted, e.g., to give only the public inputs.
+    pub fn json_inputs(i: &Inputs, src: &[u8]) -> Result<FxHashMap<String, Value>, String> {
+        Self::witness_json_inputs(i, &[], src)
+    }
+
+    /// Compute complete prover and verifier inputs for `main` by running the witness function
+    /// `witness_fn` on the "true" inputs.
+    ///
+    /// The inputs are keyed by the parameters of `witness_fn` and `main`. `witness_fn` runs on its
+    /// own inputs and returns a struct; each of its fields supplies the `main` parameter of the
+    /// same name. The remaining `main` parameters are taken from the inputs. Then `main` runs,
+    /// which checks its assertions and computes `return`.
+    ///
+    /// Returns the prover inputs (all parameters of `main`, for a `.pin` file) and the verifier
+    /// inputs (its public parameters and `return`, for a `.vin` file).
+    pub fn witness(
+        i: Inputs,
+        witness_fn: &str,
+        input_scalar_values: FxHashMap<String, Value>,
+    ) -> Result<(FxHashMap<String, Value>, FxHashMap<String, Value>), String> {
+        Self::with_gen(&i, |g, _| g.witness(witness_fn, input_scalar_values))
+    }
+
+    /// Like [ZSharpFE::json_inputs], but the JSON object may also have keys for the parameters
+    /// of the witness functions `witness_fns`. See [ZSharpFE::witness].
+    pub fn witness_json_inputs(
+        i: &Inputs,
+        witness_fns: &[&str],
+        src: &[u8],
+    ) -> Result<FxHashMap<String, Value>, String> {
+        let json: serde_json::Value =
+            serde_json::from_slice(src).map_err(|e| format!("Bad JSON inputs: {e}"))?;
+        let mut ns = vec!["main"];
+        ns.extend_from_slice(witness_fns);
+        Self::with_gen(i, |g, _| g.json_inputs(&ns, &json))
+    }
+
+    /// Execute the Z# front-end interpreter under a [debug::Debugger].
+    ///
+    /// Unlike [ZSharpFE::interpret], evaluation errors (e.g., failed assertions) are returned.
+    pub fn debug(
+        i: Inputs,
+        input_scalar_values: FxHashMap<String, Value>,
+        debugger: debug::Debugger,
+    ) -> Result<T, String> {
+        Self::with_gen(&i, |g, _| {
+            g.debugger.replace(Some(debugger));
+            g.try_const_entry_fn("main", input_scalar_values)
+        })
+    }
+
+    /// Recognize a `main` that is a bounded loop over a fixed step function. See [UniformLoop].
+    pub fn uniform_loop(i: &Inputs) -> Result<UniformLoop, String> {
+        Self::with_gen(i, |g, _| g.uniform_loop("main"))
+    }
+
+    /// Compile the step function of a [UniformLoop] as the entry function.
+    ///
+    /// The resulting computation relates the step function's parameter (the current state) to its
+    /// public `return` (the next state).
+    pub fn gen_step(i: Inputs, l: &UniformLoop) -> Computations {
+        let circ = Self::with_gen(&i, |g, _| {
+            g.entry_fn(&l.step);
+            g.take_circify()
+        });
+
+        let mut cs = Computations::new();
+        let step_comp = std::rc::Rc::try_unwrap(circ.consume())
+            .unwrap_or_else(|rc| (*rc).clone())
+            .into_inner();
+        cs.comps.insert("main".to_string(), step_comp);
+        cs
+    }
+
+    /// The transitive import closure of the supplied file (including the file itself), in
+    /// dependency order.
+    pub fn imports(i: &Inputs) -> Vec<PathBuf> {
+        let loader = parser::ZLoad::new();
+        let asts = loader.load(&i.file);
+        let mut g = ZGen::new(asts, i.mode, loader.stdlib(), cfg().zsharp.isolate_asserts);
+        g.visit_imports()
+    }
+
+    /// Run the `#[test]` functions in the supplied file and its imports, in dependency order.
+    ///
+    /// Only tests whose names contain `filter` (if given) run. Each test is evaluated like
+    /// [ZSharpFE::interpret] evaluates `main`. It passes if all of its assertions hold, and (if it
+    /// returns a `bool`) it returns `true`.
+    ///
+    /// Failing tests are reported in the results, but the panic hook still runs for them; callers
+    /// that don't want them printed should install their own hook.
+    pub fn test(i: &Inputs, filter: Option<&str>) -> Vec<TestResult> {
+        Self::with_gen(i, |g, files| g.run_tests(files, filter))
+    }
+
+    /// Parse and type-check the supplied file (and its imports), without generating constraints.
+    /// See [check::Check].
+    ///
+    /// `unsaved` maps (canonical) paths to contents that replace those on disk.
+    ///
+    /// Internal errors (panics) are caught and reported as diagnostics, but the panic hook still
+    /// runs for them; callers that don't want them printed should install their own hook.
+    pub fn check(i: &Inputs, unsaved: HashMap<PathBuf, String>) -> check::Check {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        let file = i.file.canonicalize().unwrap_or_else(|_| i.file.clone());
+        let loader = parser::ZLoad::with_overlay(unsaved);
+        let asts = match loader.try_load(&file) {
+            Ok(asts) => asts,
+            Err(diagnostics) => {
+                return check::Check {
+                    diagnostics,
+                    types: Vec::new(),
+                }
+                .canonical()
+            }
+        };
+        let mut g = ZGen::new(asts, i.mode, loader.stdlib(), cfg().zsharp.isolate_asserts);
+        g.check.replace(Some(Default::default()));
+        let r = catch_unwind(AssertUnwindSafe(|| g.visit_files()));
+        let mut c = g.check.take().unwrap();
+        if let Err(payload) = r {
+            if !payload.is::<check::Abort>() {
+                c.diagnostics.push(check::Diagnostic {
+                    range: check::SrcRange {
+                        file,
+                        start: (1, 1),
+                        end: (1, 1),
+                    },
+                    message: check::panic_message(&*payload),
+                });
+            }
+        }
+        c.canonical()
+    }
+
+    /// Load the supplied file and its imports, and run `f` with the file as the current one.
+    ///
+    /// `f` also gets the loaded files, in dependency order.
+    fn with_gen<R>(i: &Inputs, f: impl FnOnce(&mut ZGen, &[PathBuf]) -> R) -> R {
         let loader = parser::ZLoad::new();
         let asts = loader.load(&i.file);
         let mut g = ZGen::new(asts, i.mode, loader.stdlib(), cfg().zsharp.isolate_asserts);
-        g.visit_files();
-        g.file_stack_push(i.file);
+        let files = g.visit_files();
+        g.file_stack_push(i.file.clone());
         g.generics_stack_push(HashMap::new());
-        g.const_entry_fn("main", input_scalar_values)
+        let r = f(&mut g, &files);
+        g.generics_stack_pop();
+        g.file_stack_pop();
+        r
     }
 }
 
@@ -102,6 +287,12 @@ struct ZGen<'ast> {
     challenge_count: Cell<usize>,
     isolate_asserts: bool,
     in_witness_gen: Cell<bool>,
+    // active (non-builtin) calls, innermost last: (file, function)
+    fn_stack: RefCell<Vec<(PathBuf, String)>>,
+    debugger: RefCell<Option<debug::Debugger>>,
+    check: RefCell<Option<check::Check>>,
+    // conditions of the enclosing non-constant branches (if/else and ternaries); see rec_bound
+    branch_guards: RefCell<Vec<Term>>,
 }
 
 impl<'ast> Drop for ZGen<'ast> {
@@ -124,6 +315,7 @@ impl<'ast> Drop for ZGen<'ast> {
 enum ZAccess {
     Member(String),
     Idx(T),
+    Elem(usize),
 }
 
 fn loc_store(struct_: T, loc: &[ZAccess], val: T) -> Result<T, String> {
@@ -139,6 +331,11 @@ fn loc_store(struct_: T, loc: &[ZAccess], val: T) -> Result<T, String> {
             let new_inner = loc_store(old_inner, &loc[1..], val)?;
             array_store(struct_, idx.clone(), new_inner)
         }
+        Some(ZAccess::Elem(idx)) => {
+            let inner = tuple_select(&struct_, *idx)?;
+            let new_inner = loc_store(inner, &loc[1..], val)?;
+            tuple_store(struct_, *idx, new_inner)
+        }
     }
 }
 
@@ -179,6 +376,10 @@ impl<'ast> ZGen<'ast> {
             challenge_count: Cell::new(0),
             isolate_asserts,
             in_witness_gen: Cell::new(false),
+            fn_stack: Default::default(),
+            debugger: Default::default(),
+            check: Default::default(),
+            branch_guards: Default::default(),
         };
         this.circ
             .borrow()
@@ -190,12 +391,22 @@ impl<'ast> ZGen<'ast> {
         this
     }
 
-    fn into_circify(self) -> Circify<ZSharp> {
+    fn take_circify(&self) -> Circify<ZSharp> {
         self.circ.replace(Circify::new(ZSharp::new()))
     }
 
     /// Unwrap a result with a span-dependent error
+    ///
+    /// When checking (see [ZSharpFE::check]), records the error and abandons the current
+    /// declaration instead.
     fn err<E: Display>(&self, e: E, s: &ast::Span) -> ! {
+        if let Some(c) = self.check.borrow_mut().as_mut() {
+            c.diagnostics.push(check::Diagnostic {
+                range: check::SrcRange::from_span(&self.cur_path(), s),
+                message: e.to_string(),
+            });
+            std::panic::resume_unwind(Box::new(check::Abort))
+        }
         println!("Error: {e}");
         println!("In: {}", self.cur_path().canonicalize().unwrap().display());
         s.lines().for_each(|l| print!("  {l}"));
@@ -214,7 +425,8 @@ impl<'ast> ZGen<'ast> {
     ) -> Result<T, String> {
         debug!("Builtin Call: {}", f_name);
         match f_name {
-            "u8_to_bits" | "u16_to_bits" | "u32_to_bits" | "u64_to_bits" => {
+            "u8_to_bits" | "u16_to_bits" | "u32_to_bits" | "u64_to_bits" | "u128_to_bits"
+            | "u256_to_bits" => {
                 if args.len() != 1 {
                     Err(format!(
                         "Got {} args to EMBED/{}, expected 1",
@@ -231,7 +443,8 @@ impl<'ast> ZGen<'ast> {
                     uint_to_bits(args.pop().unwrap())
                 }
             }
-            "u8_from_bits" | "u16_from_bits" | "u32_from_bits" | "u64_from_bits" => {
+            "u8_from_bits" | "u16_from_bits" | "u32_from_bits" | "u64_from_bits"
+            | "u128_from_bits" | "u256_from_bits" => {
                 if args.len() != 1 {
                     Err(format!(
                         "Got {} args to EMBED/{}, expected 1",
@@ -248,7 +461,8 @@ impl<'ast> ZGen<'ast> {
                     uint_from_bits(args.pop().unwrap())
                 }
             }
-            "u8_to_field" | "u16_to_field" | "u32_to_field" | "u64_to_field" => {
+            "u8_to_field" | "u16_to_field" | "u32_to_field" | "u64_to_field" | "u128_to_field"
+            | "u256_to_field" => {
                 if args.len() != 1 {
                     Err(format!(
                         "Got {} args to EMBED/{}, expected 1",
@@ -265,8 +479,9 @@ impl<'ast> ZGen<'ast> {
                     uint_to_field(args.pop().unwrap())
                 }
             }
-            "u8_to_u64" | "u16_to_u64" | "u32_to_u64" | "u8_to_u32" | "u16_to_u32"
-            | "u8_to_u16" => {
+            "u8_to_u256" | "u16_to_u256" | "u32_to_u256" | "u64_to_u256" | "u128_to_u256"
+            | "u8_to_u128" | "u16_to_u128" | "u32_to_u128" | "u64_to_u128" | "u8_to_u64"
+            | "u16_to_u64" | "u32_to_u64" | "u8_to_u32" | "u16_to_u32" | "u8_to_u16" => {
                 if args.len() != 1 {
                     Err(format!(
                         "Got {} args to EMBED/{}, expected 1",
@@ -280,12 +495,41 @@ impl<'ast> ZGen<'ast> {
                         f_name
                     ))
                 } else {
-                    let len = f_name.len();
-                    match &f_name[len - 2..] {
-                        "64" => uint_to_uint(args.pop().unwrap(), 64),
-                        "32" => uint_to_uint(args.pop().unwrap(), 32),
-                        "16" => uint_to_uint(args.pop().unwrap(), 16),
-                        _ => unreachable!(),
+                    let (_, to) = f_name.split_once("_to_u").unwrap();
+                    uint_to_uint(args.pop().unwrap(), to.parse().unwrap())
+                }
+            }
+            "i8_to_bits" | "i16_to_bits" | "i32_to_bits" | "i64_to_bits" | "i8_from_bits"
+            | "i16_from_bits" | "i32_from_bits" | "i64_from_bits" | "i8_to_field"
+            | "i16_to_field" | "i32_to_field" | "i64_to_field" | "field_to_i8" | "field_to_i16"
+            | "field_to_i32" | "field_to_i64" | "i8_to_u8" | "i16_to_u16" | "i32_to_u32"
+            | "i64_to_u64" | "u8_to_i8" | "u16_to_i16" | "u32_to_i32" | "u64_to_i64"
+            | "i8_to_i16" | "i8_to_i32" | "i8_to_i64" | "i16_to_i32" | "i16_to_i64"
+            | "i32_to_i64" => {
+                if args.len() != 1 {
+                    Err(format!(
+                        "Got {} args to EMBED/{}, expected 1",
+                        args.len(),
+                        f_name
+                    ))
+                } else if !generics.is_empty() {
+                    Err(format!(
+                        "Got {} generic args to EMBED/{}, expected 0",
+                        generics.len(),
+                        f_name
+                    ))
+                } else {
+                    let arg = args.pop().unwrap();
+                    let (from, to) = f_name.split_once("_to_").unwrap_or((f_name, ""));
+                    let to_width = to.get(1..).and_then(|w| w.parse::<usize>().ok());
+                    match (from, to) {
+                        (_, "bits") => int_to_bits(arg),
+                        (_, "") => int_from_bits(arg),
+                        (_, "field") => int_to_field(arg),
+                        ("field", _) => field_to_int(arg, to_width.unwrap()),
+                        (_, t) if t.starts_with('u') => int_to_uint(arg),
+                        (f, _) if f.starts_with('u') => uint_to_int(arg),
+                        _ => int_to_int(arg, to_width.unwrap()),
                     }
                 }
             }
@@ -453,6 +697,7 @@ impl<'ast> ZGen<'ast> {
         accs.iter()
             .map(|acc| match acc {
                 ast::AssigneeAccess::Member(m) => Ok(ZAccess::Member(m.id.value.clone())),
+                ast::AssigneeAccess::Tuple(t) => Ok(ZAccess::Elem(t.index.value)),
                 ast::AssigneeAccess::Select(m) => match &m.expression {
                     ast::RangeOrExpression::Expression(e) => {
                         self.expr_impl_::<IS_CNST>(e).map(ZAccess::Idx)
@@ -481,6 +726,29 @@ impl<'ast> ZGen<'ast> {
                     Some(ast::DecimalSuffix::U64(_)) => {
                         Ok(uint_lit(vstr.parse::<u64>().unwrap(), 64))
                     }
+                    Some(ast::DecimalSuffix::U128(_)) => {
+                        Ok(uint_lit(vstr.parse::<u128>().unwrap(), 128))
+                    }
+                    Some(ast::DecimalSuffix::U256(_)) => {
+                        let v = Integer::from_str_radix(vstr, 10).unwrap();
+                        if v.significant_bits() > 256 {
+                            Err(format!("{v} does not fit in u256"))
+                        } else {
+                            Ok(uint_lit(v, 256))
+                        }
+                    }
+                    Some(ast::DecimalSuffix::I8(_)) => {
+                        int_lit(Integer::from_str_radix(vstr, 10).unwrap(), 8)
+                    }
+                    Some(ast::DecimalSuffix::I16(_)) => {
+                        int_lit(Integer::from_str_radix(vstr, 10).unwrap(), 16)
+                    }
+                    Some(ast::DecimalSuffix::I32(_)) => {
+                        int_lit(Integer::from_str_radix(vstr, 10).unwrap(), 32)
+                    }
+                    Some(ast::DecimalSuffix::I64(_)) => {
+                        int_lit(Integer::from_str_radix(vstr, 10).unwrap(), 64)
+                    }
                     Some(ast::DecimalSuffix::Field(_)) => {
                         Ok(field_lit(Integer::from_str_radix(vstr, 10).unwrap()))
                     }
@@ -503,11 +771,31 @@ impl<'ast> ZGen<'ast> {
                 ast::HexNumberExpression::U64(h) => {
                     Ok(uint_lit(u64::from_str_radix(&h.value, 16).unwrap(), 64))
                 }
+                ast::HexNumberExpression::U128(h) => {
+                    Ok(uint_lit(u128::from_str_radix(&h.value, 16).unwrap(), 128))
+                }
+                ast::HexNumberExpression::U256(h) => Ok(uint_lit(
+                    Integer::from_str_radix(&h.value, 16).unwrap(),
+                    256,
+                )),
             },
         }
         .map_err(|err| format!("{}; context:\n{}", err, span_to_string(e.span())))
     }
 
+    /// The literal `-e`. A signed literal is negated before its range is checked, so that, e.g.,
+    /// `-128i8` is allowed while `128i8` is not.
+    fn negated_literal_(&self, e: &ast::LiteralExpression<'ast>) -> Result<T, String> {
+        if let ast::LiteralExpression::DecimalLiteral(d) = e {
+            if let Some(bits) = d.suffix.as_ref().and_then(signed_suffix_bits) {
+                let v = Integer::from_str_radix(d.value.span.as_str(), 10).unwrap();
+                return int_lit(-v, bits)
+                    .map_err(|err| format!("{}; context:\n{}", err, span_to_string(e.span())));
+            }
+        }
+        neg(self.literal_(e)?)
+    }
+
     fn unary_op(&self, o: &ast::UnaryOperator) -> fn(T) -> Result<T, String> {
         match o {
             ast::UnaryOperator::Pos(_) => Ok,
@@ -620,7 +908,7 @@ impl<'ast> ZGen<'ast> {
                 .collect::<Result<Vec<_>, _>>()?;
             self.builtin_call(&f_name, args, generics)
         } else {
-            // XXX(unimpl) multi-return unimplemented
+            // multiple returns were collapsed into a tuple in visit_declarations
             assert!(f.returns.len() <= 1);
             if f.generics.len() != generics.len() {
                 return Err(format!(
@@ -639,7 +927,22 @@ impl<'ast> ZGen<'ast> {
                 ));
             }
 
+            if let Some(bound) = self.rec_bound(f) {
+                let depth = self
+                    .fn_stack
+                    .borrow()
+                    .iter()
+                    .filter(|(p, n)| *p == f_path && *n == f_name)
+                    .count();
+                if depth >= bound {
+                    return self.rec_bound_exceeded::<IS_CNST>(f, bound, f_path, generics);
+                }
+            }
+
             let f = f.clone();
+            self.fn_stack
+                .borrow_mut()
+                .push((f_path.clone(), f_name.clone()));
             self.file_stack_push(f_path);
             self.generics_stack_push(generics);
             self.ret_ty_stack_push::<IS_CNST>(&f)?;
@@ -684,6 +987,7 @@ impl<'ast> ZGen<'ast> {
             self.ret_ty_stack_pop();
             self.generics_stack_pop();
             self.file_stack_pop();
+            self.fn_stack.borrow_mut().pop();
 
             if IS_CNST {
                 let ret_ty = ret_ty.unwrap_or(Ty::Bool);
@@ -701,6 +1005,57 @@ impl<'ast> ZGen<'ast> {
         }
     }
 
+    /// The bound on how many calls to `f` may be active at once: its `#[unroll(N)]`, or else the
+    /// global `--zsharp-rec-limit` (if any).
+    fn rec_bound(&self, f: &ast::FunctionDefinition<'ast>) -> Option<usize> {
+        f.attributes
+            .iter()
+            .find(|a| a.id.value == "unroll")
+            .and_then(|a| a.argument.as_ref())
+            .and_then(|n| n.span.as_str().parse().ok())
+            .or_else(|| Some(cfg().zsharp.rec_limit).filter(|l| *l > 0))
+    }
+
+    /// Instead of calling `f` beyond its recursion bound, assert that the call is unreachable (the
+    /// enclosing branch conditions do not all hold), and return a default value. The enclosing
+    /// branches mux that value away.
+    ///
+    /// When evaluating constants, or when the call is unconditional, this is an error.
+    fn rec_bound_exceeded<const IS_CNST: bool>(
+        &self,
+        f: &ast::FunctionDefinition<'ast>,
+        bound: usize,
+        f_path: PathBuf,
+        generics: HashMap<String, T>,
+    ) -> Result<T, String> {
+        let msg = format!(
+            "Recursion bound of {} exceeded calling {}",
+            bound, &f.id.value
+        );
+        let mut guards = self.branch_guards.borrow().clone();
+        if IS_CNST || guards.is_empty() {
+            return Err(msg);
+        }
+        let reached = if guards.len() == 1 {
+            guards.pop().unwrap()
+        } else {
+            term(AND, guards)
+        };
+        debug!("{}; asserting that the call is unreachable", msg);
+        self.assert(term![NOT; reached])?;
+
+        self.file_stack_push(f_path);
+        self.generics_stack_push(generics);
+        let ret_ty = f
+            .returns
+            .first()
+            .map(|r| self.type_impl_::<false>(r))
+            .transpose();
+        self.generics_stack_pop();
+        self.file_stack_pop();
+        Ok(ret_ty?.unwrap_or(Ty::Bool).default())
+    }
+
     fn maybe_garbage_collect(&self) {
         let est = self.gc_depth_estimate.get();
         let cur = self.file_stack_depth();
@@ -719,7 +1074,16 @@ impl<'ast> ZGen<'ast> {
         }
     }
 
-    fn const_entry_fn(&self, n: &str, mut input_scalar_values: FxHashMap<String, Value>) -> T {
+    fn const_entry_fn(&self, n: &str, input_scalar_values: FxHashMap<String, Value>) -> T {
+        self.try_const_entry_fn(n, input_scalar_values)
+            .unwrap_or_else(|e| panic!("const_entry_fn failed: {}", e))
+    }
+
+    fn try_const_entry_fn(
+        &self,
+        n: &str,
+        mut input_scalar_values: FxHashMap<String, Value>,
+    ) -> Result<T, String> {
         debug!("Const entry: {}", n);
         let (f_file, f_name) = self.deref_import(n);
         if let Some(f) = self.functions.get(&f_file).and_then(|m| m.get(&f_name)) {
@@ -743,11 +1107,10 @@ impl<'ast> ZGen<'ast> {
                     .collect::<Vec<_>>()
                     .as_slice()
                     .join(", ");
-                self.err(format!("Ununused inputs {unused_input_list}"), &f.span);
+                self.err(format!("Unused inputs {unused_input_list}"), &f.span);
             }
 
             self.function_call_impl_::<true>(args, &[][..], None, f_file, f_name)
-                .unwrap_or_else(|e| panic!("const_entry_fn failed: {}", e))
         } else {
             panic!(
                 "No function '{:?}//{}' attempting const_entry_fn",
@@ -756,6 +1119,153 @@ impl<'ast> ZGen<'ast> {
         }
     }
 
+    /// Type the JSON inputs by the parameters of the functions `ns` (the first function with a
+    /// parameter of that name wins), and `return` by the return type of `ns[0]`.
+    fn json_inputs(
+        &self,
+        ns: &[&str],
+        json: &serde_json::Value,
+    ) -> Result<FxHashMap<String, Value>, String> {
+        let mut tys = HashMap::new();
+        for (i, n) in ns.iter().enumerate() {
+            let (f_file, f_name) = self.deref_import(n);
+            let f = self.const_entry_fn_def(&f_file, &f_name)?;
+            for p in &f.parameters {
+                if !tys.contains_key(p.id.value.as_str()) {
+                    tys.insert(p.id.value.as_str(), self.type_impl_::<false>(&p.ty)?);
+                }
+            }
+            if let (0, Some(r)) = (i, f.returns.first()) {
+                tys.insert("return", self.type_impl_::<false>(r)?);
+            }
+        }
+
+        let inputs = json
+            .as_object()
+            .ok_or_else(|| "JSON inputs must be an object keyed by parameter name".to_string())?;
+        let mut input_scalar_values = FxHashMap::default();
+        for (name, value) in inputs {
+            let ty = tys
+                .get(name.as_str())
+                .ok_or_else(|| format!("'{}' has no parameter {name}", ns.join("' or '")))?;
+            interp::flatten_json(name, ty, value, &mut input_scalar_values)?;
+        }
+        Ok(input_scalar_values)
+    }
+
+    /// Look up a function to run on constant inputs, which must not be generic.
+    fn const_entry_fn_def(
+        &self,
+        f_file: &Path,
+        f_name: &str,
+    ) -> Result<&ast::FunctionDefinition<'ast>, String> {
+        let f = self
+            .functions
+            .get(f_file)
+            .and_then(|m| m.get(f_name))
+            .ok_or_else(|| format!("No function '{f_name}' in {}", f_file.display()))?;
+        if !f.generics.is_empty() {
+            return Err(format!(
+                "Function '{f_name}' is generic; cannot type its inputs"
+            ));
+        }
+        Ok(f)
+    }
+
+    /// Compute the prover and verifier inputs of `main`, using the witness function `n`. See
+    /// [ZSharpFE::witness].
+    fn witness(
+        &self,
+        n: &str,
+        mut inputs: FxHashMap<String, Value>,
+    ) -> Result<(FxHashMap<String, Value>, FxHashMap<String, Value>), String> {
+        let (w_file, w_name) = self.deref_import(n);
+        let w = self.const_entry_fn_def(&w_file, &w_name)?;
+        let (m_file, m_name) = self.deref_import("main");
+        let m = self.const_entry_fn_def(&m_file, &m_name)?;
+
+        // the scalars of n's parameters go to n; main may share them
+        let is_w_param = |k: &str| {
+            w.parameters.iter().any(|p| {
+                let p = p.id.value.as_str();
+                k == p || k.strip_prefix(p).map_or(false, |r| r.starts_with('.'))
+            })
+        };
+        let w_inputs: FxHashMap<String, Value> = inputs
+            .iter()
+            .filter(|(k, _)| is_w_param(k))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        // try_const_entry_fn exits on missing or unused inputs, so check them here
+        let mut w_rest = w_inputs.clone();
+        for p in &w.parameters {
+            let ty = self.type_impl_::<false>(&p.ty)?;
+            interp::extract(&p.id.value, &ty, &mut w_rest)?;
+        }
+        if !w_rest.is_empty() {
+            let mut unused = w_rest.keys().map(|s| s.as_str()).collect::<Vec<_>>();
+            unused.sort_unstable();
+            return Err(format!("Unused inputs {}", unused.join(", ")));
+        }
+        let w_out = self.try_const_entry_fn(n, w_inputs)?;
+
+        let mut w_tys = HashMap::new();
+        let mut w_values = FxHashMap::default();
+        match (&w_out.ty, w_out.term.as_value_opt()) {
+            (Ty::Struct(_, fields), Some(Value::Tuple(vs))) => {
+                for ((f_name, f_ty), v) in fields.fields().zip(vs.iter()) {
+                    if !m.parameters.iter().any(|p| &p.id.value == f_name) {
+                        return Err(format!(
+                            "'{w_name}' returns {f_name}, which is not a parameter of main"
+                        ));
+                    }
+                    w_tys.insert(f_name.as_str(), f_ty);
+                    interp::flatten(f_name, f_ty, v, &mut w_values)?;
+                }
+            }
+            (ty, _) => {
+                return Err(format!(
+                    "'{w_name}' must return a struct with fields named after main's parameters, not {ty}"
+                ))
+            }
+        }
+
+        let mut prover_inputs = FxHashMap::default();
+        let mut verifier_inputs = FxHashMap::default();
+        for p in &m.parameters {
+            let name = &p.id.value;
+            let ty = self.type_impl_::<false>(&p.ty)?;
+            let value = match w_tys.get(name.as_str()) {
+                Some(w_ty) if **w_ty != ty => {
+                    return Err(format!(
+                        "'{w_name}' returns {name} as a {w_ty}, but main takes a {ty}"
+                    ))
+                }
+                Some(_) => interp::extract(name, &ty, &mut w_values)?,
+                None => interp::extract(name, &ty, &mut inputs)?,
+            };
+            let value = value.term.as_value_opt().unwrap();
+            interp::flatten(name, &ty, value, &mut prover_inputs)?;
+            if let ZVis::Public = self.interpret_visibility(&p.visibility) {
+                interp::flatten(name, &ty, value, &mut verifier_inputs)?;
+            }
+        }
+        inputs.retain(|k, _| !is_w_param(k));
+        if !inputs.is_empty() {
+            let mut unused = inputs.keys().map(|s| s.as_str()).collect::<Vec<_>>();
+            unused.sort_unstable();
+            return Err(format!("Unused inputs {}", unused.join(", ")));
+        }
+
+        let ret = self.try_const_entry_fn("main", prover_inputs.clone())?;
+        let value = ret
+            .term
+            .as_value_opt()
+            .ok_or_else(|| format!("main returned a non-constant: {ret}"))?;
+        interp::flatten("return", &ret.ty, value, &mut verifier_inputs)?;
+        Ok((prover_inputs, verifier_inputs))
+    }
+
     fn entry_fn(&self, n: &str) {
         debug!("Entry: {}", n);
         // find the entry function
@@ -767,7 +1277,7 @@ impl<'ast> ZGen<'ast> {
             .get(&f_name)
             .unwrap_or_else(|| panic!("No function '{}'", &f_name))
             .clone();
-        // XXX(unimpl) tuple returns not supported
+        // multiple returns were collapsed into a tuple in visit_declarations
         assert!(f.returns.len() <= 1);
         if !f.generics.is_empty() {
             self.err("Entry function cannot be generic. Try adding a wrapper function that supplies an explicit generic argument.", &f.span);
@@ -776,6 +1286,17 @@ impl<'ast> ZGen<'ast> {
         let ret_ty = f.returns.first().map(|r| self.type_(r));
         // set up stack frame for entry function
         self.circ_enter_fn(n.to_owned(), ret_ty.clone());
+        self.fn_stack
+            .borrow_mut()
+            .push((f_file.clone(), f_name.clone()));
+        // attribute the inputs to the signature
+        let loc = src_loc::is_enabled().then(|| {
+            src_loc::enter(src_loc::SrcLoc {
+                file: self.cur_path(),
+                line: f.span.start_pos().line_col().0,
+                stack: self.fn_names(),
+            })
+        });
         let mut persistent_arrays: Vec<String> = Vec::new();
         for p in f.parameters.iter() {
             let ty = self.type_(&p.ty);
@@ -800,9 +1321,11 @@ impl<'ast> ZGen<'ast> {
                 }
             }
         }
+        std::mem::drop(loc);
         for s in &f.statements {
             self.unwrap(self.stmt_impl_::<false>(s), s.span());
         }
+        self.fn_stack.borrow_mut().pop();
         for a in persistent_arrays {
             let term = self
                 .circ_get_value(Loc::local(a.clone()))
@@ -887,6 +1410,92 @@ impl<'ast> ZGen<'ast> {
             }
         }
     }
+    fn uniform_loop(&self, n: &str) -> Result<UniformLoop, String> {
+        let (f_file, f_name) = self.deref_import(n);
+        let f = self
+            .functions
+            .get(&f_file)
+            .and_then(|m| m.get(&f_name))
+            .ok_or_else(|| format!("No function '{f_name}'"))?;
+        let shape_err = || {
+            format!("'{n}' must have the form `for ... do s = step(s) endfor return s`, where s is its only parameter")
+        };
+        let (l, r) = match &f.statements[..] {
+            [ast::Statement::Iteration(l), ast::Statement::Return(r)] => (l, r),
+            _ => return Err(shape_err()),
+        };
+        let (state, call) = match &l.statements[..] {
+            [ast::Statement::Definition(d)] => match (&d.lhs[..], &d.expression) {
+                ([ast::TypedIdentifierOrAssignee::Assignee(a)], ast::Expression::Postfix(p))
+                    if a.accesses.is_empty() =>
+                {
+                    (&a.id.value, p)
+                }
+                _ => return Err(shape_err()),
+            },
+            _ => return Err(shape_err()),
+        };
+        let arg = match &call.accesses[..] {
+            [ast::Access::Call(c)] if c.explicit_generics.is_none() => {
+                match &c.arguments.expressions[..] {
+                    [ast::Expression::Identifier(i)] => &i.value,
+                    _ => return Err(shape_err()),
+                }
+            }
+            _ => return Err(shape_err()),
+        };
+        let ret = match &r.expressions[..] {
+            [ast::Expression::Identifier(i)] => &i.value,
+            _ => return Err(shape_err()),
+        };
+        let param = match &f.parameters[..] {
+            [p] if &p.id.value == state && arg == state && ret == state => p,
+            _ => return Err(shape_err()),
+        };
+        let state_ty = self.type_impl_::<false>(&param.ty)?;
+
+        let (s_file, s_name) = self.deref_import(&call.id.value);
+        if s_file != self.cur_path() {
+            return Err(format!(
+                "Step function '{s_name}' must be defined in {}",
+                self.cur_path().display()
+            ));
+        }
+        let step = self
+            .functions
+            .get(&s_file)
+            .and_then(|m| m.get(&s_name))
+            .ok_or_else(|| format!("No function '{s_name}'"))?;
+        if !step.generics.is_empty() {
+            return Err(format!("Step function '{s_name}' cannot be generic"));
+        }
+        let step_param = match (&step.parameters[..], &step.returns[..]) {
+            ([p], [ret_ty])
+                if self.type_impl_::<false>(&p.ty)? == state_ty
+                    && self.type_impl_::<false>(ret_ty)? == state_ty =>
+            {
+                p
+            }
+            _ => {
+                return Err(format!(
+                    "Step function '{s_name}' must map {state_ty} to {state_ty}"
+                ))
+            }
+        };
+
+        let from = self.const_isize_impl_::<true>(&l.from)?;
+        let to = self.const_isize_impl_::<true>(&l.to)?;
+        if to <= from {
+            return Err(format!("The loop in '{n}' must run at least once"));
+        }
+        Ok(UniformLoop {
+            step: s_name,
+            param: step_param.id.value.clone(),
+            state: state.clone(),
+            steps: (to - from) as usize,
+        })
+    }
+
     fn interpret_array_md(
         &self,
         md: &Option<ast::ArrayParamMetadata<'ast>>,
@@ -943,6 +1552,12 @@ impl<'ast> ZGen<'ast> {
         self.file_stack.borrow().last().unwrap().to_path_buf()
     }
 
+    /// `FILE:LINE:COL` of the start of a span in the current file.
+    fn span_location(&self, span: &ast::Span) -> String {
+        let (line, col) = span.start_pos().line_col();
+        format!("{}:{}:{}", self.cur_path().display(), line, col)
+    }
+
     fn cur_dir(&self) -> PathBuf {
         let mut p = self.cur_path();
         p.pop();
@@ -1085,10 +1700,15 @@ impl<'ast> ZGen<'ast> {
                         let c = self.expr_impl_::<false>(&u.first)?;
                         let cbool = bool(c.clone())?;
                         self.circ_enter_condition(cbool.clone());
+                        self.branch_guards.borrow_mut().push(cbool.clone());
                         let a = self.expr_impl_::<false>(&u.second)?;
+                        self.branch_guards.borrow_mut().pop();
                         self.circ_exit_condition();
-                        self.circ_enter_condition(term![NOT; cbool]);
+                        let not_cbool = term![NOT; cbool];
+                        self.circ_enter_condition(not_cbool.clone());
+                        self.branch_guards.borrow_mut().push(not_cbool);
                         let b = self.expr_impl_::<false>(&u.third)?;
+                        self.branch_guards.borrow_mut().pop();
                         self.circ_exit_condition();
                         cond(c, a, b)
                     }
@@ -1101,6 +1721,11 @@ impl<'ast> ZGen<'ast> {
                 op(left, right)
             }
             ast::Expression::Unary(u) => {
+                if let (ast::UnaryOperator::Neg(_), ast::Expression::Literal(l)) =
+                    (&u.op, &*u.expression)
+                {
+                    return self.negated_literal_(l);
+                }
                 let arg = self.expr_impl_::<IS_CNST>(&u.expression)?;
                 let op = self.unary_op(&u.op);
                 op(arg)
@@ -1164,6 +1789,7 @@ impl<'ast> ZGen<'ast> {
                         Err("Function call in non-first-access position in expr".to_string())
                     }
                     ast::Access::Member(a) => field_select(&v, &a.id.value),
+                    ast::Access::Tuple(t) => tuple_select(&v, t.index.value),
                     ast::Access::Select(s) => self.array_access_impl_::<IS_CNST>(s, v),
                 })
             }
@@ -1176,6 +1802,12 @@ impl<'ast> ZGen<'ast> {
                 })
                 .collect::<Result<Vec<_>, String>>()
                 .and_then(|members| Ok(T::new_struct(self.canon_struct(&u.ty.value)?, members))),
+            ast::Expression::InlineTuple(u) => u
+                .expressions
+                .iter()
+                .map(|e| self.expr_impl_::<IS_CNST>(e))
+                .collect::<Result<Vec<_>, String>>()
+                .map(T::new_tuple),
         }
         .and_then(|res| if IS_CNST { const_val(res) } else { Ok(res) })
         .map_err(|err| format!("{}; context:\n{}", err, span_to_string(e.span())))
@@ -1233,13 +1865,21 @@ impl<'ast> ZGen<'ast> {
     fn stmt_impl_<const IS_CNST: bool>(&self, s: &ast::Statement<'ast>) -> Result<(), String> {
         if IS_CNST {
             debug!("Const stmt: {}", s.span().as_str());
+            self.debug_hook(s);
         } else {
             debug!("Stmt: {}", s.span().as_str());
         }
+        let _loc = (!IS_CNST && src_loc::is_enabled()).then(|| {
+            src_loc::enter(src_loc::SrcLoc {
+                file: self.cur_path(),
+                line: s.span().start_pos().line_col().0,
+                stack: self.fn_names(),
+            })
+        });
 
         match s {
             ast::Statement::Return(r) => {
-                // XXX(unimpl) multi-return unimplemented
+                // multi-returns were rewritten to tuples by ZStatementWalker
                 assert!(r.expressions.len() <= 1);
                 if let Some(e) = r.expressions.first() {
                     self.set_lhs_ty_ret(r);
@@ -1257,16 +1897,18 @@ impl<'ast> ZGen<'ast> {
                 }) {
                     Ok(true) => Ok(()),
                     Ok(false) => Err(format!(
-                        "Const assert failed: {} at\n{}",
+                        "Const assert failed: {} at {}\n{}",
                         e.message
                             .as_ref()
                             .map(|m| m.value.as_ref())
                             .unwrap_or("(no error message given)"),
+                        self.span_location(e.expression.span()),
                         span_to_string(e.expression.span()),
                     )),
                     Err(err) if IS_CNST => Err(format!(
-                        "Const assert expression eval failed {} at\n{}",
+                        "Const assert expression eval failed {} at {}\n{}",
                         err,
+                        self.span_location(e.expression.span()),
                         span_to_string(e.expression.span()),
                     )),
                     _ => {
@@ -1322,51 +1964,80 @@ impl<'ast> ZGen<'ast> {
                 self.exit_scope_impl_::<IS_CNST>();
                 Ok(())
             }
+            ast::Statement::IfElse(i) => {
+                let alternative = i
+                    .alternative
+                    .as_ref()
+                    .map(|e| &e.statements[..])
+                    .unwrap_or(&[]);
+                match self
+                    .expr_impl_::<true>(&i.condition)
+                    .ok()
+                    .and_then(const_bool)
+                {
+                    Some(c) => {
+                        let branch = if c { &i.consequence[..] } else { alternative };
+                        self.enter_scope_impl_::<IS_CNST>();
+                        for s in branch {
+                            self.stmt_impl_::<IS_CNST>(s)?;
+                        }
+                        self.exit_scope_impl_::<IS_CNST>();
+                        Ok(())
+                    }
+                    None if IS_CNST => Err("if condition not const bool".to_string()),
+                    _ => {
+                        let cbool = bool(self.expr_impl_::<false>(&i.condition)?)?;
+                        // unlike a ternary, the branches assign, so they are always guarded;
+                        // assertions are guarded only if isolate_asserts is set.
+                        for (guard, branch) in [
+                            (cbool.clone(), &i.consequence[..]),
+                            (term![NOT; cbool], alternative),
+                        ] {
+                            self.circ
+                                .borrow_mut()
+                                .enter_condition(guard.clone())
+                                .map_err(|e| format!("{e}"))?;
+                            self.branch_guards.borrow_mut().push(guard);
+                            self.circ_enter_scope();
+                            for s in branch {
+                                self.stmt_impl_::<false>(s)?;
+                            }
+                            self.circ_exit_scope();
+                            self.branch_guards.borrow_mut().pop();
+                            self.circ.borrow_mut().exit_condition();
+                        }
+                        Ok(())
+                    }
+                }
+            }
             ast::Statement::Definition(d) => {
-                // XXX(unimpl) multi-assignment unimplemented
-                assert!(d.lhs.len() <= 1);
-
                 self.set_lhs_ty_defn::<IS_CNST>(d)?;
                 let e = self.expr_impl_::<IS_CNST>(&d.expression)?;
+                let strict = match &d.expression {
+                    ast::Expression::Unary(u) => matches!(&u.op, ast::UnaryOperator::Strict(_)),
+                    _ => false,
+                };
 
-                if let Some(l) = d.lhs.first() {
-                    match l {
-                        ast::TypedIdentifierOrAssignee::Assignee(l) => {
-                            let strict = match &d.expression {
-                                ast::Expression::Unary(u) => {
-                                    matches!(&u.op, ast::UnaryOperator::Strict(_))
-                                }
-                                _ => false,
-                            };
-                            self.assign_impl_::<IS_CNST>(&l.id.value, &l.accesses[..], e, strict)
-                        }
-                        ast::TypedIdentifierOrAssignee::TypedIdentifier(l) => {
-                            let decl_ty = self.type_impl_::<IS_CNST>(&l.ty)?;
-                            let ty = e.type_();
-                            if &decl_ty != ty {
-                                return Err(format!(
-                                    "Assignment type mismatch: {decl_ty} annotated vs {ty} actual",
-                                ));
-                            }
-                            self.declare_init_impl_::<IS_CNST>(
-                                l.identifier.value.clone(),
-                                decl_ty,
-                                e,
-                            )?;
-                            let md = self.interpret_array_md(&l.array_metadata);
-                            if let Some(ArrayParamMetadata::Transcript) = md {
-                                let value = self
-                                    .circ_get_value(Loc::local(l.identifier.value.clone()))
-                                    .map_err(|e| format!("{e}"))?
-                                    .unwrap_term();
-                                self.mark_array_as_transcript(&l.identifier.value, value);
-                            }
-                            Ok(())
+                match &d.lhs[..] {
+                    [] => {
+                        warn!("Statement with no LHS!");
+                        Ok(())
+                    }
+                    [l] => self.define_impl_::<IS_CNST>(l, e, strict),
+                    ls => {
+                        // multiple LHSs destructure a tuple
+                        let es = e.unwrap_tuple()?;
+                        if es.len() != ls.len() {
+                            return Err(format!(
+                                "Cannot destructure {}-tuple into {} variables",
+                                es.len(),
+                                ls.len(),
+                            ));
                         }
+                        ls.iter()
+                            .zip(es)
+                            .try_for_each(|(l, e)| self.define_impl_::<IS_CNST>(l, e, strict))
                     }
-                } else {
-                    warn!("Statement with no LHS!");
-                    Ok(())
                 }
             }
             ast::Statement::Witness(d) => {
@@ -1388,10 +2059,88 @@ impl<'ast> ZGen<'ast> {
                 self.declare_init_impl_::<IS_CNST>(d.id.value.clone(), decl_ty, e)?;
                 Ok(())
             }
+            ast::Statement::Log(l) => {
+                let format = &l.message.value;
+                let n_holes = format.matches("{}").count();
+                if n_holes != l.expressions.len() {
+                    return Err(format!(
+                        "log! message has {} placeholders, but {} arguments",
+                        n_holes,
+                        l.expressions.len()
+                    ));
+                }
+                if IS_CNST {
+                    let args = l
+                        .expressions
+                        .iter()
+                        .map(|e| {
+                            let mut buf = Vec::new();
+                            self.expr_impl_::<true>(e)?
+                                .pretty(&mut buf)
+                                .map_err(|e| format!("{e}"))?;
+                            Ok(vec![String::from_utf8_lossy(&buf).into_owned()])
+                        })
+                        .collect::<Result<Vec<_>, String>>()?;
+                    println!("log: {}", precomp::Log::render(format, &args));
+                } else {
+                    // the arguments become precomputation outputs, which are never constrained
+                    let args = l
+                        .expressions
+                        .iter()
+                        .map(|e| self.expr_impl_::<false>(e).map(|v| v.terms()))
+                        .collect::<Result<Vec<_>, String>>()?;
+                    self.circ
+                        .borrow()
+                        .cir_ctx()
+                        .cs
+                        .borrow_mut()
+                        .precomputes
+                        .add_log(format.clone(), args);
+                }
+                Ok(())
+            }
         }
         .map_err(|err| format!("{}; context:\n{}", err, span_to_string(s.span())))
     }
 
+    fn define_impl_<const IS_CNST: bool>(
+        &self,
+        l: &ast::TypedIdentifierOrAssignee<'ast>,
+        e: T,
+        strict: bool,
+    ) -> Result<(), String> {
+        match l {
+            ast::TypedIdentifierOrAssignee::Assignee(l) => {
+                self.assign_impl_::<IS_CNST>(&l.id.value, &l.accesses[..], e, strict)
+            }
+            ast::TypedIdentifierOrAssignee::TypedIdentifier(l) => {
+                let decl_ty = self.type_impl_::<IS_CNST>(&l.ty)?;
+                let ty = e.type_();
+                if &decl_ty != ty {
+                    return Err(format!(
+                        "Assignment type mismatch: {decl_ty} annotated vs {ty} actual",
+                    ));
+                }
+                self.declare_init_impl_::<IS_CNST>(l.identifier.value.clone(), decl_ty, e)?;
+                let md = self.interpret_array_md(&l.array_metadata);
+                if let Some(ArrayParamMetadata::Transcript) = md {
+                    let value = self
+                        .circ_get_value(Loc::local(l.identifier.value.clone()))
+                        .map_err(|e| format!("{e}"))?
+                        .unwrap_term();
+                    self.mark_array_as_transcript(&l.identifier.value, value);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn debug_hook(&self, s: &ast::Statement<'ast>) {
+        if let Some(d) = self.debugger.borrow_mut().as_mut() {
+            d.before_stmt(&debug::DebugState::new(self, s));
+        }
+    }
+
     fn set_lhs_ty_defn<const IS_CNST: bool>(
         &self,
         d: &ast::DefinitionStatement<'ast>,
@@ -1399,11 +2148,16 @@ impl<'ast> ZGen<'ast> {
         assert!(self.lhs_ty.borrow().is_none()); // starting from nothing...
         if let ast::Expression::Postfix(pfe) = &d.expression {
             if matches!(pfe.accesses.first(), Some(ast::Access::Call(_))) {
-                let ty = d
+                let mut tys = d
                     .lhs
-                    .first()
+                    .iter()
                     .map(|ty| self.lhs_type::<IS_CNST>(ty))
-                    .transpose()?;
+                    .collect::<Result<Vec<_>, _>>()?;
+                let ty = if tys.len() > 1 {
+                    Some(Ty::Tuple(tys))
+                } else {
+                    tys.pop()
+                };
                 self.lhs_ty_put(ty);
             }
         }
@@ -1445,6 +2199,15 @@ impl<'ast> ZGen<'ast> {
                             }),
                         ty => Err(format!("Attempted member access on non-Struct type {ty}")),
                     },
+                    ast::AssigneeAccess::Tuple(ta) => match ty {
+                        Ty::Tuple(tys) => {
+                            let n = tys.len();
+                            tys.into_iter().nth(ta.index.value).ok_or_else(|| {
+                                format!("No such element {} of {n}-tuple", ta.index.value)
+                            })
+                        }
+                        ty => Err(format!("Attempted tuple access on non-Tuple type {ty}")),
+                    },
                 })
             }
             TypedIdentifier(t) => self.type_impl_::<IS_CNST>(&t.ty),
@@ -1593,6 +2356,7 @@ impl<'ast> ZGen<'ast> {
         let mut v = ZConstLiteralRewriter::new(None);
         v.visit_type(&mut c.ty)
             .unwrap_or_else(|e| self.err(e.0, &c.span));
+        self.record_type(&c.id.value, &c.id.span, &c.ty);
         let ctype = self.unwrap(self.type_impl_::<true>(&c.ty), type_span(&c.ty));
         // handle literal type inference using declared type
         v.replace(Some(ctype));
@@ -1655,6 +2419,12 @@ impl<'ast> ZGen<'ast> {
             ast::Type::Basic(ast::BasicType::U16(_)) => Ok(Ty::Uint(16)),
             ast::Type::Basic(ast::BasicType::U32(_)) => Ok(Ty::Uint(32)),
             ast::Type::Basic(ast::BasicType::U64(_)) => Ok(Ty::Uint(64)),
+            ast::Type::Basic(ast::BasicType::U128(_)) => Ok(Ty::Uint(128)),
+            ast::Type::Basic(ast::BasicType::U256(_)) => Ok(Ty::Uint(256)),
+            ast::Type::Basic(ast::BasicType::I8(_)) => Ok(Ty::Int(8)),
+            ast::Type::Basic(ast::BasicType::I16(_)) => Ok(Ty::Int(16)),
+            ast::Type::Basic(ast::BasicType::I32(_)) => Ok(Ty::Int(32)),
+            ast::Type::Basic(ast::BasicType::I64(_)) => Ok(Ty::Int(64)),
             ast::Type::Basic(ast::BasicType::Boolean(_)) => Ok(Ty::Bool),
             ast::Type::Basic(ast::BasicType::Field(_)) => Ok(Ty::Field),
             ast::Type::Array(a) => {
@@ -1665,6 +2435,12 @@ impl<'ast> ZGen<'ast> {
                     .map(|d| self.const_usize_impl_::<IS_CNST>(d))
                     .fold(b, |b, d| Ok(Ty::Array(d?, Box::new(b?))))
             }
+            ast::Type::Tuple(t) => t
+                .elements
+                .iter()
+                .map(|ty| self.type_impl_::<IS_CNST>(ty))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Ty::Tuple),
             ast::Type::Struct(s) => {
                 let (def, path) = self.get_struct_or_type(&s.id.value).ok_or_else(|| {
                     format!(
@@ -1710,12 +2486,14 @@ impl<'ast> ZGen<'ast> {
         }
     }
 
-    fn visit_files(&mut self) {
+    /// Returns the files, in dependency order.
+    fn visit_files(&mut self) -> Vec<PathBuf> {
         // 1. go through includes and return a toposorted visit order for remaining processing
         let files = self.visit_imports();
 
         // 2. visit constant, struct, and function defs ; infer types and generics
-        self.visit_declarations(files);
+        self.visit_declarations(files.clone());
+        files
     }
 
     fn visit_imports(&mut self) -> Vec<PathBuf> {
@@ -1798,22 +2576,51 @@ impl<'ast> ZGen<'ast> {
         }
         self.asts = asts;
 
-        // flatten the import map, i.e., a -> b -> c becomes a -> c
-        self.flatten_import_map();
-
-        toposort(&ig, None)
-            .unwrap_or_else(|e| {
-                use petgraph::dot::{Config, Dot};
+        let order = toposort(&ig, None)
+            .unwrap_or_else(|c| {
+                // edges go from imported to importer: follow them from a file on the cycle back
+                // to that file
+                let start = c.node_id();
+                let mut importee = HashMap::new();
+                let mut queue = std::collections::VecDeque::from(vec![start]);
+                let mut last = start;
+                while let Some(n) = queue.pop_front() {
+                    if ig.contains_edge(n, start) {
+                        last = n;
+                        break;
+                    }
+                    for m in ig.neighbors(n) {
+                        if m != start && !importee.contains_key(&m) {
+                            importee.insert(m, n);
+                            queue.push_back(m);
+                        }
+                    }
+                }
+                let mut chain = vec![start];
+                let mut n = last;
+                while n != start {
+                    chain.push(n);
+                    n = importee[&n];
+                }
+                chain.push(start);
                 panic!(
-                    "Import graph is cyclic!: {:?}\n{:?}\n",
-                    e,
-                    Dot::with_config(&ig, &[Config::EdgeNoLabel])
+                    "Cyclic imports: {}",
+                    chain
+                        .iter()
+                        .map(|n| ig[*n].display().to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n  imports ")
                 )
             })
             .iter()
             .map(|idx| std::mem::take(ig.node_weight_mut(*idx).unwrap()))
             .filter(|p| self.asts.contains_key(p))
-            .collect()
+            .collect();
+
+        // flatten the import map, i.e., a -> b -> c becomes a -> c
+        // (after the toposort, since a cyclic import could make this loop)
+        self.flatten_import_map();
+        order
     }
 
     fn flatten_import_map(&mut self) {
@@ -1865,114 +2672,306 @@ impl<'ast> ZGen<'ast> {
             self.functions.insert(p.clone(), HashMap::new());
             self.file_stack_push(p.clone());
             for d in t.get_mut(&p).unwrap().declarations.iter_mut() {
-                match d {
-                    ast::SymbolDeclaration::Constant(c) => {
-                        debug!("processing decl: const {} in {}", c.id.value, p.display());
-                        self.const_decl_(c);
-                    }
-                    ast::SymbolDeclaration::Struct(s) => {
-                        debug!("processing decl: struct {} in {}", s.id.value, p.display());
-                        let mut s_ast = s.clone();
-
-                        // rewrite literals in ArrayTypes
-                        clr.visit_struct_definition(&mut s_ast)
-                            .unwrap_or_else(|e| self.err(e.0, &s.span));
-
-                        if self
-                            .structs_and_tys
-                            .get_mut(self.file_stack.borrow().last().unwrap())
-                            .unwrap()
-                            .insert(s.id.value.clone(), Ok(s_ast))
-                            .is_some()
-                        {
-                            self.err(
-                                format!("Struct {} defined over existing name", &s.id.value),
-                                &s.span,
-                            );
-                        }
-                    }
-                    ast::SymbolDeclaration::Type(t) => {
-                        debug!(
-                            "processing decl: type definition {} in {}",
-                            t.id.value,
-                            p.display()
-                        );
-                        let mut t_ast = t.clone();
-
-                        // rewrite literals in ArrayTypes
-                        clr.visit_type_definition(&mut t_ast)
-                            .unwrap_or_else(|e| self.err(e.0, &t.span));
-
-                        if self
-                            .structs_and_tys
-                            .get_mut(self.file_stack.borrow().last().unwrap())
-                            .unwrap()
-                            .insert(t.id.value.clone(), Err(t_ast))
-                            .is_some()
-                        {
-                            self.err(
-                                format!("Type {} defined over existing name", &t.id.value),
-                                &t.span,
-                            );
-                        }
-                    }
-                    ast::SymbolDeclaration::Function(f) => {
-                        debug!("processing decl: fn {} in {}", f.id.value, p.display());
-                        let mut f_ast = f.clone();
-
-                        // rewrite literals in params and returns
-                        let mut v = ZConstLiteralRewriter::new(None);
-                        f_ast
-                            .parameters
-                            .iter_mut()
-                            .try_for_each(|p| v.visit_parameter(p))
-                            .unwrap_or_else(|e| self.err(e.0, &f.span));
-                        if f_ast.returns.len() != 1 {
-                            // XXX(unimpl) functions MUST return exactly 1 value
+                if self.check.borrow().is_some() {
+                    self.check_declaration(d, &mut clr, &p);
+                } else {
+                    self.visit_declaration(d, &mut clr, &p);
+                }
+            }
+            self.file_stack_pop();
+        }
+        self.asts = t;
+    }
+
+    fn visit_declaration(
+        &mut self,
+        d: &mut ast::SymbolDeclaration<'ast>,
+        clr: &mut ZConstLiteralRewriter,
+        p: &Path,
+    ) {
+        match d {
+            ast::SymbolDeclaration::Constant(c) => {
+                debug!("processing decl: const {} in {}", c.id.value, p.display());
+                self.const_decl_(c);
+            }
+            ast::SymbolDeclaration::Struct(s) => {
+                debug!("processing decl: struct {} in {}", s.id.value, p.display());
+                let mut s_ast = s.clone();
+
+                // rewrite literals in ArrayTypes
+                clr.visit_struct_definition(&mut s_ast)
+                    .unwrap_or_else(|e| self.err(e.0, &s.span));
+
+                if self
+                    .structs_and_tys
+                    .get_mut(self.file_stack.borrow().last().unwrap())
+                    .unwrap()
+                    .insert(s.id.value.clone(), Ok(s_ast))
+                    .is_some()
+                {
+                    self.err(
+                        format!("Struct {} defined over existing name", &s.id.value),
+                        &s.span,
+                    );
+                }
+            }
+            ast::SymbolDeclaration::Type(t) => {
+                debug!(
+                    "processing decl: type definition {} in {}",
+                    t.id.value,
+                    p.display()
+                );
+                let mut t_ast = t.clone();
+
+                // rewrite literals in ArrayTypes
+                clr.visit_type_definition(&mut t_ast)
+                    .unwrap_or_else(|e| self.err(e.0, &t.span));
+
+                if self
+                    .structs_and_tys
+                    .get_mut(self.file_stack.borrow().last().unwrap())
+                    .unwrap()
+                    .insert(t.id.value.clone(), Err(t_ast))
+                    .is_some()
+                {
+                    self.err(
+                        format!("Type {} defined over existing name", &t.id.value),
+                        &t.span,
+                    );
+                }
+            }
+            ast::SymbolDeclaration::Function(f) => {
+                debug!("processing decl: fn {} in {}", f.id.value, p.display());
+                for a in &f.attributes {
+                    let bound = a
+                        .argument
+                        .as_ref()
+                        .map(|n| n.span.as_str().parse::<usize>());
+                    match (a.id.value.as_str(), bound) {
+                        ("test", None) if !f.parameters.is_empty() || !f.generics.is_empty() => {
                             self.err(
                                 format!(
-                                    "Functions must return exactly 1 value; {} returns {}",
-                                    &f_ast.id.value,
-                                    f_ast.returns.len(),
+                                    "Test function {} cannot take parameters or generics",
+                                    &f.id.value
                                 ),
-                                &f.span,
-                            );
-                        }
-                        f_ast
-                            .returns
-                            .iter_mut()
-                            .try_for_each(|r| v.visit_type(r))
-                            .unwrap_or_else(|e| self.err(e.0, &f.span));
-
-                        // go through stmts typechecking and rewriting literals
-                        let mut sw = ZStatementWalker::new(
-                            f_ast.parameters.as_ref(),
-                            f_ast.returns.as_ref(),
-                            f_ast.generics.as_ref(),
-                            self,
-                        );
-                        f_ast
-                            .statements
-                            .iter_mut()
-                            .try_for_each(|s| sw.visit_statement(s))
-                            .unwrap_or_else(|e| self.err(e.0, &f.span));
-
-                        if self
-                            .functions
-                            .get_mut(self.file_stack.borrow().last().unwrap())
-                            .unwrap()
-                            .insert(f.id.value.clone(), f_ast)
-                            .is_some()
-                        {
-                            self.err(format!("Function {} redefined", &f.id.value), &f.span);
+                                &a.span,
+                            )
                         }
+                        ("test", None) => (),
+                        ("unroll", Some(Ok(n))) if n > 0 => (),
+                        ("test", _) | ("unroll", _) => self.err(
+                            format!(
+                                "Bad attribute {}: use #[test] or #[unroll(N)] with N > 0",
+                                a.span.as_str().trim()
+                            ),
+                            &a.span,
+                        ),
+                        _ => self.err(format!("Unknown attribute #[{}]", &a.id.value), &a.span),
                     }
-                    ast::SymbolDeclaration::Import(_) => (), // already handled in visit_imports
+                }
+                let mut f_ast = f.clone();
+
+                // rewrite literals in params and returns
+                let mut v = ZConstLiteralRewriter::new(None);
+                f_ast
+                    .parameters
+                    .iter_mut()
+                    .try_for_each(|p| v.visit_parameter(p))
+                    .unwrap_or_else(|e| self.err(e.0, &f.span));
+                if f_ast.returns.is_empty() {
+                    // XXX(unimpl) functions MUST return a value
+                    self.err(
+                        format!(
+                            "Functions must return a value; {} returns nothing",
+                            &f_ast.id.value,
+                        ),
+                        &f.span,
+                    );
+                } else if f_ast.returns.len() > 1 {
+                    // multiple returns are a single tuple return
+                    let elements = std::mem::take(&mut f_ast.returns);
+                    f_ast.returns.push(ast::Type::Tuple(ast::TupleType {
+                        elements,
+                        span: f.span,
+                    }));
+                }
+                f_ast
+                    .returns
+                    .iter_mut()
+                    .try_for_each(|r| v.visit_type(r))
+                    .unwrap_or_else(|e| self.err(e.0, &f.span));
+
+                // go through stmts typechecking and rewriting literals
+                let mut sw = ZStatementWalker::new(
+                    f_ast.parameters.as_ref(),
+                    f_ast.returns.as_ref(),
+                    f_ast.generics.as_ref(),
+                    self,
+                );
+                f_ast
+                    .statements
+                    .iter_mut()
+                    .try_for_each(|s| sw.visit_statement(s))
+                    .unwrap_or_else(|e| self.err(e.0, sw.stmt_span().unwrap_or(&f.span)));
+
+                if self
+                    .functions
+                    .get_mut(self.file_stack.borrow().last().unwrap())
+                    .unwrap()
+                    .insert(f.id.value.clone(), f_ast)
+                    .is_some()
+                {
+                    self.err(format!("Function {} redefined", &f.id.value), &f.span);
                 }
             }
-            self.file_stack_pop();
+            ast::SymbolDeclaration::Import(_) => (), // already handled in visit_imports
+        }
+    }
+
+    /// Like [ZGen::visit_declaration], but records an error (and skips the rest of the
+    /// declaration) instead of exiting.
+    fn check_declaration(
+        &mut self,
+        d: &mut ast::SymbolDeclaration<'ast>,
+        clr: &mut ZConstLiteralRewriter,
+        p: &Path,
+    ) {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        let span = match d {
+            ast::SymbolDeclaration::Constant(c) => c.span,
+            ast::SymbolDeclaration::Struct(s) => s.span,
+            ast::SymbolDeclaration::Type(t) => t.span,
+            ast::SymbolDeclaration::Function(f) => f.span,
+            ast::SymbolDeclaration::Import(_) => return,
+        };
+        let depths = self.stack_depths();
+        let r = catch_unwind(AssertUnwindSafe(|| self.visit_declaration(d, clr, p)));
+        if let Err(payload) = r {
+            // drop whatever the abandoned declaration left on the stacks
+            self.unwind_stacks(depths);
+            if !payload.is::<check::Abort>() {
+                let diagnostic = check::Diagnostic {
+                    range: check::SrcRange::from_span(p, &span),
+                    message: check::panic_message(&*payload),
+                };
+                if let Some(c) = self.check.borrow_mut().as_mut() {
+                    c.diagnostics.push(diagnostic);
+                }
+            }
+        }
+    }
+
+    fn run_tests(&self, files: &[PathBuf], filter: Option<&str>) -> Vec<TestResult> {
+        let mut results = Vec::new();
+        for p in files {
+            let mut tests: Vec<&ast::FunctionDefinition<'ast>> = self
+                .functions
+                .get(p)
+                .into_iter()
+                .flat_map(|fs| fs.values())
+                .filter(|f| f.attributes.iter().any(|a| a.id.value == "test"))
+                .filter(|f| filter.map_or(true, |s| f.id.value.contains(s)))
+                .collect();
+            tests.sort_by_key(|f| f.span.start());
+            for f in tests {
+                debug!("Test: {} in {}", f.id.value, p.display());
+                results.push(TestResult {
+                    file: p.clone(),
+                    name: f.id.value.clone(),
+                    line: f.span.start_pos().line_col().0,
+                    result: self.run_test(p, &f.id.value),
+                });
+            }
+        }
+        results
+    }
+
+    fn run_test(&self, f_path: &Path, f_name: &str) -> Result<(), String> {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        let depths = self.stack_depths();
+        // errors that would exit are recorded instead, as when checking
+        self.check.replace(Some(Default::default()));
+        let r = catch_unwind(AssertUnwindSafe(|| {
+            self.function_call_impl_::<true>(
+                Vec::new(),
+                &[][..],
+                None,
+                f_path.to_path_buf(),
+                f_name.to_string(),
+            )
+        }));
+        let c = self.check.replace(None).unwrap();
+        let r = match r {
+            Ok(r) => r,
+            Err(payload) if payload.is::<check::Abort>() => {
+                let d = c.diagnostics.last().unwrap();
+                let (line, col) = d.range.start;
+                Err(format!(
+                    "{} at {}:{}:{}",
+                    d.message,
+                    d.range.file.display(),
+                    line,
+                    col
+                ))
+            }
+            Err(payload) => Err(check::panic_message(&*payload)),
+        };
+        match r {
+            Ok(ret) if const_bool(ret.clone()) == Some(false) => {
+                Err(format!("{} returned false", f_name))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                self.unwind_stacks(depths);
+                Err(e)
+            }
+        }
+    }
+
+    /// The names of the active functions, innermost last
+    fn fn_names(&self) -> Vec<String> {
+        self.fn_stack
+            .borrow()
+            .iter()
+            .map(|(_, n)| n.clone())
+            .collect()
+    }
+
+    /// The depths of the stacks that function calls push to. See [ZGen::unwind_stacks].
+    fn stack_depths(&self) -> [usize; 7] {
+        [
+            self.branch_guards.borrow().len(),
+            self.file_stack.borrow().len(),
+            self.generics_stack.borrow().len(),
+            self.fn_stack.borrow().len(),
+            self.cvars_stack.borrow().len(),
+            self.crets_stack.borrow().len(),
+            self.ret_ty_stack.borrow().len(),
+        ]
+    }
+
+    /// Pop the stacks back to `depths`, after an error abandoned a computation part-way through.
+    fn unwind_stacks(&self, depths: [usize; 7]) {
+        let [guards, files, generics, fns, cvars, crets, ret_tys] = depths;
+        self.branch_guards.borrow_mut().truncate(guards);
+        self.file_stack.borrow_mut().truncate(files);
+        self.generics_stack.borrow_mut().truncate(generics);
+        self.fn_stack.borrow_mut().truncate(fns);
+        self.cvars_stack.borrow_mut().truncate(cvars);
+        self.crets_stack.borrow_mut().truncate(crets);
+        self.ret_ty_stack.borrow_mut().truncate(ret_tys);
+        self.lhs_ty.replace(None);
+        self.in_witness_gen.set(false);
+    }
+
+    fn record_type(&self, name: &str, span: &ast::Span, ty: &ast::Type) {
+        if let Some(c) = self.check.borrow_mut().as_mut() {
+            c.types.push(check::TypeInfo {
+                range: check::SrcRange::from_span(&self.cur_path(), span),
+                name: name.to_string(),
+                ty: check::type_string(ty),
+            });
         }
-        self.asts = t;
     }
 
     fn get_function(&self, fn_id: &str) -> Option<&ast::FunctionDefinition<'ast>> {
@@ -2111,6 +3110,17 @@ impl<'ast> ZGen<'ast> {
     }
 }
 
+/// The width of the signed integer type named by `suffix`, if it names one.
+fn signed_suffix_bits(suffix: &ast::DecimalSuffix) -> Option<usize> {
+    match suffix {
+        ast::DecimalSuffix::I8(_) => Some(8),
+        ast::DecimalSuffix::I16(_) => Some(16),
+        ast::DecimalSuffix::I32(_) => Some(32),
+        ast::DecimalSuffix::I64(_) => Some(64),
+        _ => None,
+    }
+}
+
 fn span_to_string(span: &ast::Span) -> String {
     span.lines().collect::<String>()
 }
@@ -2121,6 +3131,7 @@ fn type_span<'ast, 'a>(ty: &'a ast::Type<'ast>) -> &'a ast::Span<'ast> {
     match ty {
         Array(a) => &a.span,
         Struct(s) => &s.span,
+        Tuple(t) => &t.span,
         Basic(b) => match b {
             Field(f) => &f.span,
             Boolean(b) => &b.span,
@@ -2128,6 +3139,12 @@ fn type_span<'ast, 'a>(ty: &'a ast::Type<'ast>) -> &'a ast::Span<'ast> {
             U16(u) => &u.span,
             U32(u) => &u.span,
             U64(u) => &u.span,
+            U128(u) => &u.span,
+            U256(u) => &u.span,
+            I8(i) => &i.span,
+            I16(i) => &i.span,
+            I32(i) => &i.span,
+            I64(i) => &i.span,
         },
     }
 }
@@ -5,30 +5,55 @@
 
 use zokrates_pest_ast as ast;
 
+use super::check::{Diagnostic, SrcRange};
 use log::debug;
-use std::collections::HashMap;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::env::var_os;
+use std::rc::Rc;
 
+use crate::cfg::{cfg, is_cfg_set};
 use crate::circify::includer::Loader;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use typed_arena::Arena;
 
-/// A representation of the standard libary's location.
+/// The file name of a Z# package manifest.
+///
+/// A manifest is a JSON object like `{"name": "alpaca", "dependencies": {"curves": "../curves"}}`.
+/// Files in the manifest's directory (or below it) can import the package's files as
+/// `"alpaca/hash/poseidon"`, and a dependency's files as `"curves/point"`. The roots of
+/// dependencies are relative to the manifest. Both keys are optional.
+pub const MANIFEST: &str = "zsharp.json";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    name: Option<String>,
+    #[serde(default)]
+    dependencies: HashMap<String, PathBuf>,
+}
+
+/// A representation of the standard libary's location, and the other places that imports are
+/// found in.
 #[derive(Default)]
 pub struct ZStdLib {
     path: PathBuf,
+    search_path: Vec<PathBuf>,
+    /// package roots, by the directory of the manifest that declares them
+    packages: RefCell<HashMap<PathBuf, Rc<HashMap<String, PathBuf>>>>,
 }
 
 impl ZStdLib {
     /// Looks for a "ZoKrates/zokrates_stdlib/stdlib" path in some ancestor of the current
-    /// directory.
+    /// directory. The search path is `--zsharp-search-path`.
     pub fn new() -> Self {
         if let Some(p) = var_os("ZSHARP_STDLIB_PATH") {
             let p = PathBuf::from(p);
             if p.exists() {
-                return Self { path: p };
+                return Self::with_path(p);
             } else {
                 panic!(
                     "ZStdLib: ZSHARP_STDLIB_PATH {:?} does not appear to exist",
@@ -48,34 +73,119 @@ impl ZStdLib {
                 let mut q = a.to_path_buf();
                 q.push(subdir);
                 if q.exists() {
-                    return Self { path: q };
+                    return Self::with_path(q);
                 }
             }
         }
         panic!("Could not find ZoKrates/Z# stdlib from {}", p.display())
     }
+    fn with_path(path: PathBuf) -> Self {
+        let search_path = if is_cfg_set() {
+            cfg().zsharp.search_path.clone()
+        } else {
+            Vec::new()
+        };
+        Self {
+            path,
+            search_path,
+            packages: Default::default(),
+        }
+    }
     /// Turn `child`, relative to `parent` (or to the standard libary!), into an absolute path.
+    ///
+    /// Panics if there is no such file, or more than one. See [ZStdLib::resolve].
     pub fn canonicalize(&self, parent: &Path, child: &str) -> PathBuf {
+        self.resolve(parent, child)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+    /// Find the file that `child`, imported from a file in `parent`, refers to.
+    ///
+    /// A file relative to `parent` comes first. Otherwise, `child` starts with the name of a
+    /// package visible from `parent` (see [MANIFEST]), or is relative to a directory in the
+    /// search path or to the standard library. It is an error for more than one of these to
+    /// exist.
+    pub fn resolve(&self, parent: &Path, child: &str) -> Result<PathBuf, String> {
         debug!("Looking for {} from {}", child, parent.display());
-        let paths = [parent.to_path_buf(), self.path.clone()];
-        for mut p in paths {
-            p.push(child);
-            debug!("Checking {}", p.display());
-            if p.exists() {
-                return p;
-            }
-            if p.extension().is_some() {
-                continue;
-            }
-            for ext in ["zok", "zx"] {
-                p.set_extension(ext);
-                debug!("Checking {}", p.display());
-                if p.exists() {
-                    return p;
-                }
+        if let Some(p) = find_file(parent.join(child)) {
+            return Ok(p);
+        }
+        let packages = self.packages(parent)?;
+        let mut candidates = Vec::new();
+        let mut components = Path::new(child).components();
+        if let Some(root) = components
+            .next()
+            .and_then(|c| c.as_os_str().to_str())
+            .and_then(|name| packages.get(name))
+        {
+            candidates.extend(find_file(root.join(components.as_path())));
+        }
+        for dir in self.search_path.iter().chain([&self.path]) {
+            candidates.extend(find_file(dir.join(child)));
+        }
+        let mut seen = HashSet::new();
+        candidates.retain(|c| seen.insert(c.canonicalize().unwrap_or_else(|_| c.clone())));
+        match candidates.len() {
+            0 => Err(format!(
+                "Could not find {} from {}",
+                child,
+                parent.display()
+            )),
+            1 => Ok(candidates.pop().unwrap()),
+            _ => Err(format!(
+                "Ambiguous import {:?} from {}; it could be any of:\n  {}",
+                child,
+                parent.display(),
+                candidates
+                    .iter()
+                    .map(|c| c.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n  ")
+            )),
+        }
+    }
+    /// The packages visible from `dir`: those declared by the nearest [MANIFEST] in `dir` or one of
+    /// its ancestors.
+    fn packages(&self, dir: &Path) -> Result<Rc<HashMap<String, PathBuf>>, String> {
+        let m_dir = match dir.ancestors().find(|a| a.join(MANIFEST).is_file()) {
+            Some(m_dir) => m_dir,
+            None => return Ok(Default::default()),
+        };
+        if let Some(packages) = self.packages.borrow().get(m_dir) {
+            return Ok(packages.clone());
+        }
+        let path = m_dir.join(MANIFEST);
+        debug!("Reading manifest {}", path.display());
+        let src = std::fs::read(&path)
+            .map_err(|e| format!("Could not read manifest {}: {}", path.display(), e))?;
+        let manifest: Manifest = serde_json::from_slice(&src)
+            .map_err(|e| format!("Bad manifest {}: {}", path.display(), e))?;
+        let mut packages: HashMap<String, PathBuf> = manifest
+            .dependencies
+            .into_iter()
+            .map(|(name, root)| (name, m_dir.join(root)))
+            .collect();
+        if let Some(name) = manifest.name {
+            if packages.insert(name.clone(), m_dir.to_path_buf()).is_some() {
+                return Err(format!(
+                    "Manifest {}: package {} depends on a package of the same name",
+                    path.display(),
+                    name
+                ));
             }
         }
-        panic!("Could not find {} from {}", child, parent.display())
+        if let Some((name, root)) = packages.iter().find(|(_, root)| !root.is_dir()) {
+            return Err(format!(
+                "Manifest {}: the root {} of package {} is not a directory",
+                path.display(),
+                root.display(),
+                name
+            ));
+        }
+        let packages = Rc::new(packages);
+        self.packages
+            .borrow_mut()
+            .insert(m_dir.to_path_buf(), packages.clone());
+        Ok(packages)
     }
     /// check if this path is the EMBED prototypes path
     pub fn is_embed<P: AsRef<Path>>(&self, p: P) -> bool {
@@ -84,11 +194,36 @@ impl ZStdLib {
     }
 }
 
+/// `p`, or `p` with a Z# extension, if that file exists.
+fn find_file(mut p: PathBuf) -> Option<PathBuf> {
+    debug!("Checking {}", p.display());
+    if p.is_file() {
+        return Some(p);
+    }
+    if p.extension().is_some() {
+        return None;
+    }
+    for ext in ["zok", "zx"] {
+        p.set_extension(ext);
+        debug!("Checking {}", p.display());
+        if p.is_file() {
+            return Some(p);
+        }
+    }
+    None
+}
+
 /// A recrusive Z# loader
 #[derive(Default)]
 pub struct ZLoad {
     sources: Arena<String>,
     stdlib: ZStdLib,
+    /// the first file found to import each file, for reporting import chains
+    importers: RefCell<HashMap<PathBuf, PathBuf>>,
+    /// contents to use instead of those on disk (e.g., unsaved edits), by canonical path
+    overlay: HashMap<PathBuf, String>,
+    /// imports that could not be resolved (see [ZLoad::try_load])
+    errors: RefCell<Vec<Diagnostic>>,
 }
 
 impl ZLoad {
@@ -98,6 +233,18 @@ impl ZLoad {
         Self {
             sources: Arena::new(),
             stdlib: ZStdLib::new(),
+            importers: Default::default(),
+            overlay: HashMap::new(),
+            errors: Default::default(),
+        }
+    }
+
+    /// Make a new Z# loader that reads the files in `overlay` (keyed by canonical path) from it,
+    /// rather than from disk.
+    pub fn with_overlay(overlay: HashMap<PathBuf, String>) -> Self {
+        Self {
+            overlay,
+            ..Self::new()
         }
     }
 
@@ -107,29 +254,87 @@ impl ZLoad {
     ///
     /// Returns a map from file paths to parsed files.
     pub fn load<P: AsRef<Path>>(&self, p: &P) -> HashMap<PathBuf, ast::File> {
-        self.recursive_load(p).unwrap()
+        self.try_load(p).unwrap_or_else(|errors| {
+            let messages: Vec<String> = errors.into_iter().map(|e| e.message).collect();
+            panic!("{}", messages.join("\n"))
+        })
+    }
+
+    /// Recursively load a Z# file, like [ZLoad::load], but return all parse errors and unresolved
+    /// imports, rather than panicking at the first.
+    pub fn try_load<P: AsRef<Path>>(
+        &self,
+        p: &P,
+    ) -> Result<HashMap<PathBuf, ast::File>, Vec<Diagnostic>> {
+        let mut m = HashMap::default();
+        let mut q = std::collections::VecDeque::new();
+        let mut seen = HashSet::new();
+        q.push_back(p.as_ref().to_path_buf());
+        while let Some(p) = q.pop_front() {
+            if seen.insert(p.clone()) {
+                match self.parse(&p) {
+                    Ok(ast) => {
+                        q.extend(self.includes(&ast, &p));
+                        m.insert(p, ast);
+                    }
+                    Err(e) => self.errors.borrow_mut().push(e),
+                }
+            }
+        }
+        let errors = self.errors.take();
+        if errors.is_empty() {
+            Ok(m)
+        } else {
+            Err(errors)
+        }
     }
 
     /// Get ref to contained ZStdLib
     pub fn stdlib(&self) -> &ZStdLib {
         &self.stdlib
     }
+
+    /// Describe how the entry file (transitively) imports `p`.
+    fn import_chain(&self, p: &Path) -> String {
+        let importers = self.importers.borrow();
+        let mut chain = format!("\n  in {}", p.display());
+        let mut seen = HashSet::new();
+        let mut p = p;
+        while let Some(i) = importers.get(p).filter(|_| seen.insert(p)) {
+            chain += &format!("\n  imported by {}", i.display());
+            p = i.as_path();
+        }
+        chain
+    }
 }
 
 impl<'a> Loader for &'a ZLoad {
-    type ParseError = ();
+    type ParseError = Diagnostic;
     type AST = zokrates_pest_ast::File<'a>;
 
     fn parse<P: AsRef<Path>>(&self, p: &P) -> Result<Self::AST, Self::ParseError> {
-        let mut s = String::new();
-        File::open(p).unwrap().read_to_string(&mut s).unwrap();
+        let unsaved = (p.as_ref().canonicalize().ok()).and_then(|c| self.overlay.get(&c));
+        let s = match unsaved.or_else(|| self.overlay.get(p.as_ref())) {
+            Some(s) => s.clone(),
+            None => {
+                let mut s = String::new();
+                File::open(p).unwrap().read_to_string(&mut s).unwrap();
+                s
+            }
+        };
         debug!("Parsing: {}", p.as_ref().display());
         let s = self.sources.alloc(s);
-        let ast = ast::generate_ast(s);
-        if ast.is_err() {
-            panic!("{}", ast.unwrap_err());
-        }
-        Ok(ast.unwrap())
+        ast::generate_ast(s).map_err(|e| {
+            let (line, col) = e.line_col();
+            Diagnostic {
+                range: SrcRange {
+                    file: p.as_ref().to_path_buf(),
+                    start: (line, col),
+                    end: (line, col + 1),
+                },
+                message: format!("{}{}", e, self.import_chain(p.as_ref())),
+            }
+        })
     }
     fn includes<P: AsRef<Path>>(&self, ast: &Self::AST, p: &P) -> Vec<PathBuf> {
         let mut c = p.as_ref().to_path_buf();
@@ -142,7 +347,25 @@ impl<'a> Loader for &'a ZLoad {
                         ast::ImportDirective::Main(m) => &m.source.value,
                         ast::ImportDirective::From(m) => &m.source.value,
                     };
-                    Some(self.stdlib.canonicalize(&c, ext))
+                    let i_path = match self.stdlib.resolve(&c, ext) {
+                        Ok(i_path) => i_path,
+                        Err(e) => {
+                            let span = match i {
+                                ast::ImportDirective::Main(m) => &m.span,
+                                ast::ImportDirective::From(m) => &m.span,
+                            };
+                            self.errors.borrow_mut().push(Diagnostic {
+                                range: SrcRange::from_span(p.as_ref(), span),
+                                message: format!("{}{}", e, self.import_chain(p.as_ref())),
+                            });
+                            return None;
+                        }
+                    };
+                    self.importers
+                        .borrow_mut()
+                        .entry(i_path.clone())
+                        .or_insert_with(|| p.as_ref().to_path_buf());
+                    Some(i_path)
                 } else {
                     None
                 }
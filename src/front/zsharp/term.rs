@@ -13,9 +13,13 @@ use crate::ir::term::*;
 #[derive(Clone, PartialEq, Eq)]
 pub enum Ty {
     Uint(usize),
+    /// A two's complement signed integer of this width
+    Int(usize),
     Bool,
     Field,
     Struct(String, FieldList<Ty>),
+    /// An anonymous product of these types
+    Tuple(Vec<Ty>),
     Array(usize, Box<Ty>),
     MutArray(usize),
 }
@@ -25,6 +29,7 @@ impl Display for Ty {
         match self {
             Ty::Bool => write!(f, "bool"),
             Ty::Uint(w) => write!(f, "u{w}"),
+            Ty::Int(w) => write!(f, "i{w}"),
             Ty::Field => write!(f, "field"),
             Ty::Struct(n, fields) => {
                 let mut o = f.debug_struct(n);
@@ -33,6 +38,16 @@ impl Display for Ty {
                 }
                 o.finish()
             }
+            Ty::Tuple(tys) => {
+                write!(f, "(")?;
+                tys.iter().enumerate().try_for_each(|(i, ty)| {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{ty}")
+                })?;
+                write!(f, ")")
+            }
             Ty::Array(n, b) => {
                 let mut dims = vec![n];
                 let mut bb = b.as_ref();
@@ -66,13 +81,14 @@ impl Ty {
     fn sort(&self) -> Sort {
         match self {
             Self::Bool => Sort::Bool,
-            Self::Uint(w) => Sort::BitVector(*w),
+            Self::Uint(w) | Self::Int(w) => Sort::BitVector(*w),
             Self::Field => default_field_sort(),
             Self::Array(n, b) => Sort::new_array(default_field_sort(), b.sort(), *n),
             Self::MutArray(n) => Sort::new_array(default_field_sort(), default_field_sort(), *n),
             Self::Struct(_name, fs) => {
                 Sort::Tuple(fs.fields().map(|(_f_name, f_ty)| f_ty.sort()).collect())
             }
+            Self::Tuple(tys) => Sort::Tuple(tys.iter().map(|ty| ty.sort()).collect()),
         }
     }
     fn default_ir_term(&self) -> Term {
@@ -186,6 +202,22 @@ impl T {
         T::new(Ty::Struct(name, field_ty_list), ir_term)
     }
 
+    pub fn new_tuple(elems: Vec<T>) -> T {
+        let (tys, ir_terms): (Vec<_>, Vec<_>) = elems.into_iter().map(|t| (t.ty, t.term)).unzip();
+        T::new(Ty::Tuple(tys), term(Op::Tuple, ir_terms))
+    }
+
+    pub fn unwrap_tuple(self) -> Result<Vec<T>, String> {
+        match &self.ty {
+            Ty::Tuple(tys) => Ok(tys
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| T::new(ty.clone(), term![Op::Field(i); self.term.clone()]))
+                .collect()),
+            s => Err(format!("Not a tuple: {s}")),
+        }
+    }
+
     // XXX(rsw) hrm is there a nicer way to do this?
     pub fn new_field<I>(v: I) -> Self
     where
@@ -231,14 +263,27 @@ impl T {
         match &**val {
             Value::Bool(b) => write!(f, "{b}"),
             Value::Field(fe) => write!(f, "{}f", fe.i()),
+            Value::BitVector(bv) if matches!(self.ty, Ty::Int(_)) => {
+                write!(f, "{}i{}", bv.as_sint(), bv.width())
+            }
             Value::BitVector(bv) => match bv.width() {
                 8 => write!(f, "0x{:02x}", bv.uint()),
                 16 => write!(f, "0x{:04x}", bv.uint()),
                 32 => write!(f, "0x{:08x}", bv.uint()),
                 64 => write!(f, "0x{:016x}", bv.uint()),
+                128 => write!(f, "0x{:032x}", bv.uint()),
+                256 => write!(f, "0x{:064x}", bv.uint()),
                 _ => unreachable!(),
             },
             Value::Tuple(vs) => {
+                if let Ty::Tuple(tys) = &self.ty {
+                    write!(f, "(")?;
+                    tys.iter().zip(vs.iter()).try_for_each(|(ty, v)| {
+                        T::new(ty.clone(), const_(v.clone())).pretty(f)?;
+                        write!(f, ", ")
+                    })?;
+                    return write!(f, ")");
+                }
                 let (n, fl) = if let Ty::Struct(n, fl) = &self.ty {
                     Ok((n, fl))
                 } else {
@@ -292,44 +337,52 @@ impl Display for T {
 fn wrap_bin_op(
     name: &str,
     fu: Option<fn(Term, Term) -> Term>,
+    fi: Option<fn(Term, Term) -> Term>,
     ff: Option<fn(Term, Term) -> Term>,
     fb: Option<fn(Term, Term) -> Term>,
     a: T,
     b: T,
 ) -> Result<T, String> {
-    match (&a.ty, &b.ty, fu, ff, fb) {
-        (Ty::Uint(na), Ty::Uint(nb), Some(fu), _, _) if na == nb => {
+    match (&a.ty, &b.ty, fu, fi, ff, fb) {
+        (Ty::Uint(na), Ty::Uint(nb), Some(fu), _, _, _) if na == nb => {
             Ok(T::new(Ty::Uint(*na), fu(a.term.clone(), b.term.clone())))
         }
-        (Ty::Bool, Ty::Bool, _, _, Some(fb)) => {
+        (Ty::Int(na), Ty::Int(nb), _, Some(fi), _, _) if na == nb => {
+            Ok(T::new(Ty::Int(*na), fi(a.term.clone(), b.term.clone())))
+        }
+        (Ty::Bool, Ty::Bool, _, _, _, Some(fb)) => {
             Ok(T::new(Ty::Bool, fb(a.term.clone(), b.term.clone())))
         }
-        (Ty::Field, Ty::Field, _, Some(ff), _) => {
+        (Ty::Field, Ty::Field, _, _, Some(ff), _) => {
             Ok(T::new(Ty::Field, ff(a.term.clone(), b.term.clone())))
         }
-        (x, y, _, _, _) => Err(format!("Cannot perform op '{name}' on {x} and {y}")),
+        (x, y, _, _, _, _) => Err(format!("Cannot perform op '{name}' on {x} and {y}")),
     }
 }
 
 fn wrap_bin_pred(
     name: &str,
     fu: Option<fn(Term, Term) -> Term>,
+    fi: Option<fn(Term, Term) -> Term>,
     ff: Option<fn(Term, Term) -> Term>,
     fb: Option<fn(Term, Term) -> Term>,
     a: T,
     b: T,
 ) -> Result<T, String> {
-    match (&a.ty, &b.ty, fu, ff, fb) {
-        (Ty::Uint(na), Ty::Uint(nb), Some(fu), _, _) if na == nb => {
+    match (&a.ty, &b.ty, fu, fi, ff, fb) {
+        (Ty::Uint(na), Ty::Uint(nb), Some(fu), _, _, _) if na == nb => {
             Ok(T::new(Ty::Bool, fu(a.term.clone(), b.term.clone())))
         }
-        (Ty::Bool, Ty::Bool, _, _, Some(fb)) => {
+        (Ty::Int(na), Ty::Int(nb), _, Some(fi), _, _) if na == nb => {
+            Ok(T::new(Ty::Bool, fi(a.term.clone(), b.term.clone())))
+        }
+        (Ty::Bool, Ty::Bool, _, _, _, Some(fb)) => {
             Ok(T::new(Ty::Bool, fb(a.term.clone(), b.term.clone())))
         }
-        (Ty::Field, Ty::Field, _, Some(ff), _) => {
+        (Ty::Field, Ty::Field, _, _, Some(ff), _) => {
             Ok(T::new(Ty::Bool, ff(a.term.clone(), b.term.clone())))
         }
-        (x, y, _, _, _) => Err(format!("Cannot perform op '{name}' on {x} and {y}")),
+        (x, y, _, _, _, _) => Err(format!("Cannot perform op '{name}' on {x} and {y}")),
     }
 }
 
@@ -342,7 +395,15 @@ fn add_field(a: Term, b: Term) -> Term {
 }
 
 pub fn add(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("+", Some(add_uint), Some(add_field), None, a, b)
+    wrap_bin_op(
+        "+",
+        Some(add_uint),
+        Some(add_uint),
+        Some(add_field),
+        None,
+        a,
+        b,
+    )
 }
 
 fn sub_uint(a: Term, b: Term) -> Term {
@@ -354,7 +415,15 @@ fn sub_field(a: Term, b: Term) -> Term {
 }
 
 pub fn sub(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("-", Some(sub_uint), Some(sub_field), None, a, b)
+    wrap_bin_op(
+        "-",
+        Some(sub_uint),
+        Some(sub_uint),
+        Some(sub_field),
+        None,
+        a,
+        b,
+    )
 }
 
 fn mul_uint(a: Term, b: Term) -> Term {
@@ -366,7 +435,15 @@ fn mul_field(a: Term, b: Term) -> Term {
 }
 
 pub fn mul(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("*", Some(mul_uint), Some(mul_field), None, a, b)
+    wrap_bin_op(
+        "*",
+        Some(mul_uint),
+        Some(mul_uint),
+        Some(mul_field),
+        None,
+        a,
+        b,
+    )
 }
 
 fn div_uint(a: Term, b: Term) -> Term {
@@ -377,8 +454,36 @@ fn div_field(a: Term, b: Term) -> Term {
     term![Op::PfNaryOp(PfNaryOp::Mul); a, term![Op::PfUnOp(PfUnOp::Recip); b]]
 }
 
+/// `(neg(a), neg(b), |a|, |b|)` for two's complement `a` and `b`
+fn sign_abs(a: Term, b: Term) -> (Term, Term, Term, Term) {
+    let abs = |t: Term| {
+        let n = check(&t).as_bv();
+        let neg = term![Op::BvBit(n - 1); t.clone()];
+        let abs = term![Op::Ite; neg.clone(), term![Op::BvUnOp(BvUnOp::Neg); t.clone()], t];
+        (neg, abs)
+    };
+    let (a_neg, a_abs) = abs(a);
+    let (b_neg, b_abs) = abs(b);
+    (a_neg, b_neg, a_abs, b_abs)
+}
+
+// Signed division truncates towards zero; the remainder has the sign of the dividend.
+fn div_int(a: Term, b: Term) -> Term {
+    let (a_neg, b_neg, a_abs, b_abs) = sign_abs(a, b);
+    let q = div_uint(a_abs, b_abs);
+    term![Op::Ite; term![Op::BoolNaryOp(BoolNaryOp::Xor); a_neg, b_neg], neg_uint(q.clone()), q]
+}
+
 pub fn div(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("/", Some(div_uint), Some(div_field), None, a, b)
+    wrap_bin_op(
+        "/",
+        Some(div_uint),
+        Some(div_int),
+        Some(div_field),
+        None,
+        a,
+        b,
+    )
 }
 
 fn to_dflt_f(t: Term) -> Term {
@@ -396,8 +501,22 @@ fn rem_uint(a: Term, b: Term) -> Term {
     term![Op::BvBinOp(BvBinOp::Urem); a, b]
 }
 
+fn rem_int(a: Term, b: Term) -> Term {
+    let (a_neg, _, a_abs, b_abs) = sign_abs(a, b);
+    let r = rem_uint(a_abs, b_abs);
+    term![Op::Ite; a_neg, neg_uint(r.clone()), r]
+}
+
 pub fn rem(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("%", Some(rem_uint), Some(rem_field), None, a, b)
+    wrap_bin_op(
+        "%",
+        Some(rem_uint),
+        Some(rem_int),
+        Some(rem_field),
+        None,
+        a,
+        b,
+    )
 }
 
 fn bitand_uint(a: Term, b: Term) -> Term {
@@ -405,7 +524,7 @@ fn bitand_uint(a: Term, b: Term) -> Term {
 }
 
 pub fn bitand(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("&", Some(bitand_uint), None, None, a, b)
+    wrap_bin_op("&", Some(bitand_uint), Some(bitand_uint), None, None, a, b)
 }
 
 fn bitor_uint(a: Term, b: Term) -> Term {
@@ -413,7 +532,7 @@ fn bitor_uint(a: Term, b: Term) -> Term {
 }
 
 pub fn bitor(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("|", Some(bitor_uint), None, None, a, b)
+    wrap_bin_op("|", Some(bitor_uint), Some(bitor_uint), None, None, a, b)
 }
 
 fn bitxor_uint(a: Term, b: Term) -> Term {
@@ -421,7 +540,7 @@ fn bitxor_uint(a: Term, b: Term) -> Term {
 }
 
 pub fn bitxor(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("^", Some(bitxor_uint), None, None, a, b)
+    wrap_bin_op("^", Some(bitxor_uint), Some(bitxor_uint), None, None, a, b)
 }
 
 fn or_bool(a: Term, b: Term) -> Term {
@@ -429,7 +548,7 @@ fn or_bool(a: Term, b: Term) -> Term {
 }
 
 pub fn or(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("||", None, None, Some(or_bool), a, b)
+    wrap_bin_op("||", None, None, None, Some(or_bool), a, b)
 }
 
 fn and_bool(a: Term, b: Term) -> Term {
@@ -437,7 +556,7 @@ fn and_bool(a: Term, b: Term) -> Term {
 }
 
 pub fn and(a: T, b: T) -> Result<T, String> {
-    wrap_bin_op("&&", None, None, Some(and_bool), a, b)
+    wrap_bin_op("&&", None, None, None, Some(and_bool), a, b)
 }
 
 fn eq_base(a: T, b: T) -> Result<Term, String> {
@@ -464,6 +583,22 @@ fn ult_uint(a: Term, b: Term) -> Term {
     term![Op::BvBinPred(BvBinPred::Ult); a, b]
 }
 
+fn slt_int(a: Term, b: Term) -> Term {
+    term![Op::BvBinPred(BvBinPred::Slt); a, b]
+}
+
+fn sle_int(a: Term, b: Term) -> Term {
+    term![Op::BvBinPred(BvBinPred::Sle); a, b]
+}
+
+fn sgt_int(a: Term, b: Term) -> Term {
+    term![Op::BvBinPred(BvBinPred::Sgt); a, b]
+}
+
+fn sge_int(a: Term, b: Term) -> Term {
+    term![Op::BvBinPred(BvBinPred::Sge); a, b]
+}
+
 // XXX(constr_opt) see TODO file - only need to expand to MIN of two bit-lengths if done right
 // XXX(constr_opt) do this using subtraction instead?
 fn field_comp(a: Term, b: Term, op: BvBinPred) -> Term {
@@ -478,7 +613,15 @@ fn ult_field(a: Term, b: Term) -> Term {
 }
 
 pub fn ult(a: T, b: T) -> Result<T, String> {
-    wrap_bin_pred("<", Some(ult_uint), Some(ult_field), None, a, b)
+    wrap_bin_pred(
+        "<",
+        Some(ult_uint),
+        Some(slt_int),
+        Some(ult_field),
+        None,
+        a,
+        b,
+    )
 }
 
 fn ule_uint(a: Term, b: Term) -> Term {
@@ -490,7 +633,15 @@ fn ule_field(a: Term, b: Term) -> Term {
 }
 
 pub fn ule(a: T, b: T) -> Result<T, String> {
-    wrap_bin_pred("<=", Some(ule_uint), Some(ule_field), None, a, b)
+    wrap_bin_pred(
+        "<=",
+        Some(ule_uint),
+        Some(sle_int),
+        Some(ule_field),
+        None,
+        a,
+        b,
+    )
 }
 
 fn ugt_uint(a: Term, b: Term) -> Term {
@@ -502,7 +653,15 @@ fn ugt_field(a: Term, b: Term) -> Term {
 }
 
 pub fn ugt(a: T, b: T) -> Result<T, String> {
-    wrap_bin_pred(">", Some(ugt_uint), Some(ugt_field), None, a, b)
+    wrap_bin_pred(
+        ">",
+        Some(ugt_uint),
+        Some(sgt_int),
+        Some(ugt_field),
+        None,
+        a,
+        b,
+    )
 }
 
 fn uge_uint(a: Term, b: Term) -> Term {
@@ -514,7 +673,15 @@ fn uge_field(a: Term, b: Term) -> Term {
 }
 
 pub fn uge(a: T, b: T) -> Result<T, String> {
-    wrap_bin_pred(">=", Some(uge_uint), Some(uge_field), None, a, b)
+    wrap_bin_pred(
+        ">=",
+        Some(uge_uint),
+        Some(sge_int),
+        Some(uge_field),
+        None,
+        a,
+        b,
+    )
 }
 
 pub fn pow(a: T, b: T) -> Result<T, String> {
@@ -549,7 +716,7 @@ fn wrap_un_op(
     a: T,
 ) -> Result<T, String> {
     match (&a.ty, fu, ff, fb) {
-        (Ty::Uint(_), Some(fu), _, _) => Ok(T::new(a.ty.clone(), fu(a.term.clone()))),
+        (Ty::Uint(_) | Ty::Int(_), Some(fu), _, _) => Ok(T::new(a.ty.clone(), fu(a.term.clone()))),
         (Ty::Bool, _, _, Some(fb)) => Ok(T::new(Ty::Bool, fb(a.term.clone()))),
         (Ty::Field, _, Some(ff), _) => Ok(T::new(Ty::Field, ff(a.term.clone()))),
         (x, _, _, _) => Err(format!("Cannot perform op '{name}' on {x}")),
@@ -584,6 +751,7 @@ pub fn not(a: T) -> Result<T, String> {
 pub fn const_int(a: T) -> Result<Integer, String> {
     match const_value(&a.term) {
         Some(Value::Field(f)) => Ok(f.i()),
+        Some(Value::BitVector(f)) if matches!(a.ty, Ty::Int(_)) => Ok(f.as_sint()),
         Some(Value::BitVector(f)) => Ok(f.uint().clone()),
         _ => Err(format!("{a} is not a constant integer")),
     }
@@ -618,20 +786,37 @@ pub fn bool(a: T) -> Result<Term, String> {
     }
 }
 
-fn wrap_shift(name: &str, op: BvBinOp, a: T, b: T) -> Result<T, String> {
+fn wrap_shift(name: &str, op_u: BvBinOp, op_i: BvBinOp, a: T, b: T) -> Result<T, String> {
     let bc = const_int(b)?;
-    match &a.ty {
-        &Ty::Uint(na) => Ok(T::new(a.ty, term![Op::BvBinOp(op); a.term, bv_lit(bc, na)])),
-        x => Err(format!("Cannot perform op '{name}' on {x} and {bc}")),
+    if bc < 0 {
+        return Err(format!(
+            "Cannot perform op '{name}' by a negative amount ({bc})"
+        ));
     }
+    let (na, op) = match &a.ty {
+        &Ty::Uint(na) => (na, op_u),
+        &Ty::Int(na) => (na, op_i),
+        x => return Err(format!("Cannot perform op '{name}' on {x} and {bc}")),
+    };
+    // Shifting by the width or more leaves only copies of the sign bit (Ashr) or zero.
+    let t = if op == BvBinOp::Ashr {
+        let bc = bc.min(Integer::from(na - 1));
+        term![Op::BvBinOp(op); a.term, bv_lit(bc, na)]
+    } else if bc >= na {
+        bv_lit(0, na)
+    } else {
+        term![Op::BvBinOp(op); a.term, bv_lit(bc, na)]
+    };
+    Ok(T::new(a.ty, t))
 }
 
 pub fn shl(a: T, b: T) -> Result<T, String> {
-    wrap_shift("<<", BvBinOp::Shl, a, b)
+    wrap_shift("<<", BvBinOp::Shl, BvBinOp::Shl, a, b)
 }
 
+/// Logical for unsigned integers, arithmetic for signed ones.
 pub fn shr(a: T, b: T) -> Result<T, String> {
-    wrap_shift(">>", BvBinOp::Lshr, a, b)
+    wrap_shift(">>", BvBinOp::Lshr, BvBinOp::Ashr, a, b)
 }
 
 fn ite(c: Term, a: T, b: T) -> Result<T, String> {
@@ -678,6 +863,22 @@ where
     T::new(Ty::Uint(bits), bv_lit(v, bits))
 }
 
+/// A signed integer literal; `v` must be in `[-2^(bits-1), 2^(bits-1))`.
+pub fn int_lit<I>(v: I, bits: usize) -> Result<T, String>
+where
+    Integer: From<I>,
+{
+    let v = Integer::from(v);
+    let half = Integer::from(1) << (bits - 1);
+    if v >= half || Integer::from(-&v) > half {
+        return Err(format!("{v} does not fit in i{bits}"));
+    }
+    Ok(T::new(
+        Ty::Int(bits),
+        bv_lit(v.keep_bits(bits as u32), bits),
+    ))
+}
+
 pub fn slice(arr: T, start: Option<usize>, end: Option<usize>) -> Result<T, String> {
     match &arr.ty {
         Ty::Array(size, _) => {
@@ -733,6 +934,48 @@ pub fn field_store(struct_: T, field: &str, val: T) -> Result<T, String> {
     }
 }
 
+pub fn tuple_select(tuple: &T, idx: usize) -> Result<T, String> {
+    match &tuple.ty {
+        Ty::Tuple(tys) => {
+            if let Some(ty) = tys.get(idx) {
+                Ok(T::new(
+                    ty.clone(),
+                    term![Op::Field(idx); tuple.term.clone()],
+                ))
+            } else {
+                Err(format!(
+                    "Index {idx} out of bounds for tuple of length {}",
+                    tys.len()
+                ))
+            }
+        }
+        a => Err(format!("{a} is not a tuple")),
+    }
+}
+
+pub fn tuple_store(tuple: T, idx: usize, val: T) -> Result<T, String> {
+    match &tuple.ty {
+        Ty::Tuple(tys) => {
+            if let Some(ty) = tys.get(idx) {
+                if ty == &val.ty {
+                    Ok(T::new(
+                        tuple.ty.clone(),
+                        term![Op::Update(idx); tuple.term.clone(), val.term],
+                    ))
+                } else {
+                    Err(format!("term {val} assigned to element {idx} of type {ty}"))
+                }
+            } else {
+                Err(format!(
+                    "Index {idx} out of bounds for tuple of length {}",
+                    tys.len()
+                ))
+            }
+        }
+        a => Err(format!("{a} is not a tuple")),
+    }
+}
+
 fn coerce_to_field(i: T) -> Result<Term, String> {
     match &i.ty {
         Ty::Uint(_) => Ok(to_dflt_f(i.term)),
@@ -833,6 +1076,68 @@ pub fn uint_to_uint(u: T, w: usize) -> Result<T, String> {
     }
 }
 
+pub fn uint_to_int(u: T) -> Result<T, String> {
+    match &u.ty {
+        Ty::Uint(n) => Ok(T::new(Ty::Int(*n), u.term)),
+        u => Err(format!("Cannot do uint-to-int on {u}")),
+    }
+}
+
+pub fn int_to_uint(i: T) -> Result<T, String> {
+    match &i.ty {
+        Ty::Int(n) => Ok(T::new(Ty::Uint(*n), i.term)),
+        i => Err(format!("Cannot do int-to-uint on {i}")),
+    }
+}
+
+pub fn int_to_int(i: T, w: usize) -> Result<T, String> {
+    match &i.ty {
+        Ty::Int(n) if *n <= w => Ok(T::new(Ty::Int(w), term![Op::BvSext(w - n); i.term])),
+        Ty::Int(n) => Err(format!("Tried narrowing int{n}-to-int{w} attempted")),
+        i => Err(format!("Cannot do int-to-int on {i}")),
+    }
+}
+
+/// Flipping the sign bit adds `2^(w-1)`, mapping `[-2^(w-1), 2^(w-1))` onto `[0, 2^w)`.
+fn flip_sign(t: Term, w: usize) -> Term {
+    term![Op::BvNaryOp(BvNaryOp::Xor); t, bv_lit(Integer::from(1) << (w - 1), w)]
+}
+
+pub fn int_to_field(i: T) -> Result<T, String> {
+    match &i.ty {
+        Ty::Int(w) => Ok(T::new(
+            Ty::Field,
+            sub_field(
+                to_dflt_f(flip_sign(i.term, *w)),
+                pf_lit_ir(Integer::from(1) << (w - 1)),
+            ),
+        )),
+        i => Err(format!("Cannot do int-to-field on {i}")),
+    }
+}
+
+/// Fails (like `unpack`) if `f` is not in `[-2^(w-1), 2^(w-1))`.
+pub fn field_to_int(f: T, w: usize) -> Result<T, String> {
+    match &f.ty {
+        Ty::Field => {
+            let biased = add_field(f.term, pf_lit_ir(Integer::from(1) << (w - 1)));
+            Ok(T::new(
+                Ty::Int(w),
+                flip_sign(term![Op::PfToBv(w); biased], w),
+            ))
+        }
+        f => Err(format!("Cannot do field-to-int on {f}")),
+    }
+}
+
+pub fn int_to_bits(i: T) -> Result<T, String> {
+    int_to_uint(i).and_then(uint_to_bits)
+}
+
+pub fn int_from_bits(u: T) -> Result<T, String> {
+    uint_from_bits(u).and_then(uint_to_int)
+}
+
 pub fn uint_to_bits(u: T) -> Result<T, String> {
     match &u.ty {
         Ty::Uint(n) => Ok(T::new(
@@ -850,7 +1155,7 @@ pub fn uint_to_bits(u: T) -> Result<T, String> {
 pub fn uint_from_bits(u: T) -> Result<T, String> {
     match &u.ty {
         Ty::Array(bits, elem_ty) if **elem_ty == Ty::Bool => match bits {
-            8 | 16 | 32 | 64 => Ok(T::new(
+            8 | 16 | 32 | 64 | 128 | 256 => Ok(T::new(
                 Ty::Uint(*bits),
                 term(
                     Op::BvConcat,
@@ -980,8 +1285,8 @@ impl Embeddable for ZSharp {
                     precompute.map(|p| p.term),
                 ),
             ),
-            Ty::Uint(w) => T::new(
-                Ty::Uint(*w),
+            Ty::Uint(w) | Ty::Int(w) => T::new(
+                ty.clone(),
                 ctx.cs.borrow_mut().new_var(
                     &name,
                     Sort::BitVector(*w),
@@ -1034,6 +1339,23 @@ impl Embeddable for ZSharp {
                     })
                     .collect(),
             ),
+            Ty::Tuple(tys) => {
+                let ps: Vec<Option<T>> = match precompute.map(|p| p.unwrap_tuple()) {
+                    Some(Ok(v)) => v.into_iter().map(Some).collect(),
+                    Some(Err(e)) => panic!("{}", e),
+                    None => std::iter::repeat(None).take(tys.len()).collect(),
+                };
+                debug_assert_eq!(tys.len(), ps.len());
+                T::new_tuple(
+                    tys.iter()
+                        .zip(ps)
+                        .enumerate()
+                        .map(|(i, (ty, p))| {
+                            self.declare_input(ctx, ty, idx_name(&name, i), visibility, p)
+                        })
+                        .collect(),
+                )
+            }
         }
     }
     fn ite(&self, _ctx: &mut CirCtx, cond: Term, t: Self::T, f: Self::T) -> Self::T {
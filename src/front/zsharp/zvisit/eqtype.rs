@@ -15,6 +15,7 @@ pub(super) fn eq_type<'ast>(
         (Basic(bty), Basic(bty2)) => eq_basic_type(bty, bty2),
         (Array(aty), Array(aty2)) => eq_array_type(aty, aty2, zgen),
         (Struct(sty), Struct(sty2)) => eq_struct_type(sty, sty2, zgen),
+        (Tuple(tty), Tuple(tty2)) => eq_tuple_type(tty, tty2, zgen),
         _ => Err(ZVisitorError(format!(
             "type mismatch:\n\texpected {ty:?},\n\tfound {ty2:?}"
         ))),
@@ -30,6 +31,12 @@ fn eq_basic_type<'ast>(ty: &ast::BasicType<'ast>, ty2: &ast::BasicType<'ast>) ->
         (U16(_), U16(_)) => Ok(()),
         (U32(_), U32(_)) => Ok(()),
         (U64(_), U64(_)) => Ok(()),
+        (U128(_), U128(_)) => Ok(()),
+        (U256(_), U256(_)) => Ok(()),
+        (I8(_), I8(_)) => Ok(()),
+        (I16(_), I16(_)) => Ok(()),
+        (I32(_), I32(_)) => Ok(()),
+        (I64(_), I64(_)) => Ok(()),
         _ => Err(ZVisitorError(format!(
             "basic type mismatch: \n\texpected {ty:?}, \n\tfound {ty2:?}"
         ))),
@@ -59,6 +66,24 @@ fn eq_array_type<'ast>(
     }
 }
 
+fn eq_tuple_type<'ast>(
+    ty: &ast::TupleType<'ast>,
+    ty2: &ast::TupleType<'ast>,
+    zgen: &ZGen<'ast>,
+) -> ZVisitorResult {
+    if ty.elements.len() != ty2.elements.len() {
+        return Err(ZVisitorError(format!(
+            "tuple type mismatch: \n\texpected {}-tuple, \n\tfound {}-tuple",
+            ty.elements.len(),
+            ty2.elements.len(),
+        )));
+    }
+    ty.elements
+        .iter()
+        .zip(ty2.elements.iter())
+        .try_for_each(|(t, t2)| eq_type(t, t2, zgen))
+}
+
 fn eq_struct_type<'ast>(
     ty: &ast::StructType<'ast>,
     ty2: &ast::StructType<'ast>,
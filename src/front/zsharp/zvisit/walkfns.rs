@@ -157,6 +157,10 @@ pub fn walk_function_definition<'ast, Z: ZVisitorMut<'ast>>(
     visitor: &mut Z,
     fundef: &mut ast::FunctionDefinition<'ast>,
 ) -> ZVisitorResult {
+    fundef
+        .attributes
+        .iter_mut()
+        .try_for_each(|a| visitor.visit_function_attribute(a))?;
     visitor.visit_identifier_expression(&mut fundef.id)?;
     fundef
         .generics
@@ -177,6 +181,17 @@ pub fn walk_function_definition<'ast, Z: ZVisitorMut<'ast>>(
     visitor.visit_span(&mut fundef.span)
 }
 
+pub fn walk_function_attribute<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    attr: &mut ast::FunctionAttribute<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_identifier_expression(&mut attr.id)?;
+    if let Some(n) = &mut attr.argument {
+        visitor.visit_decimal_number(n)?;
+    }
+    visitor.visit_span(&mut attr.span)
+}
+
 pub fn walk_parameter<'ast, Z: ZVisitorMut<'ast>>(
     visitor: &mut Z,
     param: &mut ast::Parameter<'ast>,
@@ -237,6 +252,7 @@ pub fn walk_type<'ast, Z: ZVisitorMut<'ast>>(
         Basic(b) => visitor.visit_basic_type(b),
         Array(a) => visitor.visit_array_type(a),
         Struct(s) => visitor.visit_struct_type(s),
+        Tuple(t) => visitor.visit_tuple_type(t),
     }
 }
 
@@ -252,6 +268,12 @@ pub fn walk_basic_type<'ast, Z: ZVisitorMut<'ast>>(
         U16(u) => visitor.visit_u16_type(u),
         U32(u) => visitor.visit_u32_type(u),
         U64(u) => visitor.visit_u64_type(u),
+        U128(u) => visitor.visit_u128_type(u),
+        U256(u) => visitor.visit_u256_type(u),
+        I8(i) => visitor.visit_i8_type(i),
+        I16(i) => visitor.visit_i16_type(i),
+        I32(i) => visitor.visit_i32_type(i),
+        I64(i) => visitor.visit_i64_type(i),
     }
 }
 
@@ -297,6 +319,48 @@ pub fn walk_u64_type<'ast, Z: ZVisitorMut<'ast>>(
     visitor.visit_span(&mut u64ty.span)
 }
 
+pub fn walk_u128_type<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    u128ty: &mut ast::U128Type<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut u128ty.span)
+}
+
+pub fn walk_u256_type<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    u256ty: &mut ast::U256Type<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut u256ty.span)
+}
+
+pub fn walk_i8_type<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    i8ty: &mut ast::I8Type<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut i8ty.span)
+}
+
+pub fn walk_i16_type<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    i16ty: &mut ast::I16Type<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut i16ty.span)
+}
+
+pub fn walk_i32_type<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    i32ty: &mut ast::I32Type<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut i32ty.span)
+}
+
+pub fn walk_i64_type<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    i64ty: &mut ast::I64Type<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut i64ty.span)
+}
+
 pub fn walk_array_type<'ast, Z: ZVisitorMut<'ast>>(
     visitor: &mut Z,
     aty: &mut ast::ArrayType<'ast>,
@@ -330,6 +394,16 @@ pub fn walk_struct_type<'ast, Z: ZVisitorMut<'ast>>(
     visitor.visit_span(&mut sty.span)
 }
 
+pub fn walk_tuple_type<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    tty: &mut ast::TupleType<'ast>,
+) -> ZVisitorResult {
+    tty.elements
+        .iter_mut()
+        .try_for_each(|t| visitor.visit_type(t))?;
+    visitor.visit_span(&mut tty.span)
+}
+
 pub fn walk_explicit_generics<'ast, Z: ZVisitorMut<'ast>>(
     visitor: &mut Z,
     eg: &mut ast::ExplicitGenerics<'ast>,
@@ -392,6 +466,12 @@ pub fn walk_decimal_suffix<'ast, Z: ZVisitorMut<'ast>>(
         U16(u16s) => visitor.visit_u16_suffix(u16s),
         U32(u32s) => visitor.visit_u32_suffix(u32s),
         U64(u64s) => visitor.visit_u64_suffix(u64s),
+        U128(u128s) => visitor.visit_u128_suffix(u128s),
+        U256(u256s) => visitor.visit_u256_suffix(u256s),
+        I8(i8s) => visitor.visit_i8_suffix(i8s),
+        I16(i16s) => visitor.visit_i16_suffix(i16s),
+        I32(i32s) => visitor.visit_i32_suffix(i32s),
+        I64(i64s) => visitor.visit_i64_suffix(i64s),
         Field(fs) => visitor.visit_field_suffix(fs),
     }
 }
@@ -424,6 +504,48 @@ pub fn walk_u64_suffix<'ast, Z: ZVisitorMut<'ast>>(
     visitor.visit_span(&mut u64s.span)
 }
 
+pub fn walk_u128_suffix<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    u128s: &mut ast::U128Suffix<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut u128s.span)
+}
+
+pub fn walk_u256_suffix<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    u256s: &mut ast::U256Suffix<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut u256s.span)
+}
+
+pub fn walk_i8_suffix<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    i8s: &mut ast::I8Suffix<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut i8s.span)
+}
+
+pub fn walk_i16_suffix<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    i16s: &mut ast::I16Suffix<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut i16s.span)
+}
+
+pub fn walk_i32_suffix<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    i32s: &mut ast::I32Suffix<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut i32s.span)
+}
+
+pub fn walk_i64_suffix<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    i64s: &mut ast::I64Suffix<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut i64s.span)
+}
+
 pub fn walk_field_suffix<'ast, Z: ZVisitorMut<'ast>>(
     visitor: &mut Z,
     fs: &mut ast::FieldSuffix<'ast>,
@@ -456,6 +578,8 @@ pub fn walk_hex_number_expression<'ast, Z: ZVisitorMut<'ast>>(
         U16(u16e) => visitor.visit_u16_number_expression(u16e),
         U32(u32e) => visitor.visit_u32_number_expression(u32e),
         U64(u64e) => visitor.visit_u64_number_expression(u64e),
+        U128(u128e) => visitor.visit_u128_number_expression(u128e),
+        U256(u256e) => visitor.visit_u256_number_expression(u256e),
     }
 }
 
@@ -487,6 +611,20 @@ pub fn walk_u64_number_expression<'ast, Z: ZVisitorMut<'ast>>(
     visitor.visit_span(&mut u64e.span)
 }
 
+pub fn walk_u128_number_expression<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    u128e: &mut ast::U128NumberExpression<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut u128e.span)
+}
+
+pub fn walk_u256_number_expression<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    u256e: &mut ast::U256NumberExpression<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut u256e.span)
+}
+
 pub fn walk_underscore<'ast, Z: ZVisitorMut<'ast>>(
     visitor: &mut Z,
     u: &mut ast::Underscore<'ast>,
@@ -508,6 +646,7 @@ pub fn walk_expression<'ast, Z: ZVisitorMut<'ast>>(
         Literal(le) => visitor.visit_literal_expression(le),
         InlineArray(iae) => visitor.visit_inline_array_expression(iae),
         InlineStruct(ise) => visitor.visit_inline_struct_expression(ise),
+        InlineTuple(ite) => visitor.visit_inline_tuple_expression(ite),
         ArrayInitializer(aie) => visitor.visit_array_initializer_expression(aie),
     }
 }
@@ -574,6 +713,7 @@ pub fn walk_access<'ast, Z: ZVisitorMut<'ast>>(
         Call(ca) => visitor.visit_call_access(ca),
         Select(aa) => visitor.visit_array_access(aa),
         Member(ma) => visitor.visit_member_access(ma),
+        Tuple(ta) => visitor.visit_tuple_access(ta),
     }
 }
 
@@ -652,6 +792,21 @@ pub fn walk_member_access<'ast, Z: ZVisitorMut<'ast>>(
     visitor.visit_span(&mut ma.span)
 }
 
+pub fn walk_tuple_access<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    ta: &mut ast::TupleAccess<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_tuple_index(&mut ta.index)?;
+    visitor.visit_span(&mut ta.span)
+}
+
+pub fn walk_tuple_index<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    ti: &mut ast::TupleIndex<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_span(&mut ti.span)
+}
+
 pub fn walk_inline_array_expression<'ast, Z: ZVisitorMut<'ast>>(
     visitor: &mut Z,
     iae: &mut ast::InlineArrayExpression<'ast>,
@@ -701,6 +856,16 @@ pub fn walk_inline_struct_member<'ast, Z: ZVisitorMut<'ast>>(
     visitor.visit_span(&mut ism.span)
 }
 
+pub fn walk_inline_tuple_expression<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    ite: &mut ast::InlineTupleExpression<'ast>,
+) -> ZVisitorResult {
+    ite.expressions
+        .iter_mut()
+        .try_for_each(|e| visitor.visit_expression(e))?;
+    visitor.visit_span(&mut ite.span)
+}
+
 pub fn walk_array_initializer_expression<'ast, Z: ZVisitorMut<'ast>>(
     visitor: &mut Z,
     aie: &mut ast::ArrayInitializerExpression<'ast>,
@@ -722,6 +887,8 @@ pub fn walk_statement<'ast, Z: ZVisitorMut<'ast>>(
         Assertion(a) => visitor.visit_assertion_statement(a),
         CondStore(a) => visitor.visit_cond_store_statement(a),
         Iteration(i) => visitor.visit_iteration_statement(i),
+        IfElse(i) => visitor.visit_if_else_statement(i),
+        Log(l) => visitor.visit_log_statement(l),
     }
 }
 
@@ -795,6 +962,7 @@ pub fn walk_assignee_access<'ast, Z: ZVisitorMut<'ast>>(
     match acc {
         Select(aa) => visitor.visit_array_access(aa),
         Member(ma) => visitor.visit_member_access(ma),
+        Tuple(ta) => visitor.visit_tuple_access(ta),
     }
 }
 
@@ -820,6 +988,17 @@ pub fn walk_cond_store_statement<'ast, Z: ZVisitorMut<'ast>>(
     visitor.visit_span(&mut s.span)
 }
 
+pub fn walk_log_statement<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    log: &mut ast::LogStatement<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_any_string(&mut log.message)?;
+    log.expressions
+        .iter_mut()
+        .try_for_each(|e| visitor.visit_expression(e))?;
+    visitor.visit_span(&mut log.span)
+}
+
 pub fn walk_iteration_statement<'ast, Z: ZVisitorMut<'ast>>(
     visitor: &mut Z,
     iter: &mut ast::IterationStatement<'ast>,
@@ -833,3 +1012,27 @@ pub fn walk_iteration_statement<'ast, Z: ZVisitorMut<'ast>>(
         .try_for_each(|s| visitor.visit_statement(s))?;
     visitor.visit_span(&mut iter.span)
 }
+
+pub fn walk_if_else_statement<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    ite: &mut ast::IfElseStatement<'ast>,
+) -> ZVisitorResult {
+    visitor.visit_expression(&mut ite.condition)?;
+    ite.consequence
+        .iter_mut()
+        .try_for_each(|s| visitor.visit_statement(s))?;
+    if let Some(e) = &mut ite.alternative {
+        visitor.visit_else_clause(e)?;
+    }
+    visitor.visit_span(&mut ite.span)
+}
+
+pub fn walk_else_clause<'ast, Z: ZVisitorMut<'ast>>(
+    visitor: &mut Z,
+    e: &mut ast::ElseClause<'ast>,
+) -> ZVisitorResult {
+    e.statements
+        .iter_mut()
+        .try_for_each(|s| visitor.visit_statement(s))?;
+    visitor.visit_span(&mut e.span)
+}
@@ -57,6 +57,9 @@ impl<'ast> ZVisitorMut<'ast> for ZConstLiteralRewriter {
     InlineStruct(InlineStructExpression<'ast>),
         -> check that struct types are equal
 
+    InlineTuple(InlineTupleExpression<'ast>),
+        -> each element should have the corresponding element type
+
     ArrayInitializer(ArrayInitializerExpression<'ast>),
         -> value should have type of value inside Array
         -> count should have type Field
@@ -112,9 +115,31 @@ impl<'ast> ZVisitorMut<'ast> for ZConstLiteralRewriter {
                 Ty::Uint(64) => Ok(ast::DecimalSuffix::U64(ast::U64Suffix {
                     span: dle.span,
                 })),
+                Ty::Uint(128) => Ok(ast::DecimalSuffix::U128(ast::U128Suffix {
+                    span: dle.span,
+                })),
+                Ty::Uint(256) => Ok(ast::DecimalSuffix::U256(ast::U256Suffix {
+                    span: dle.span,
+                })),
+                Ty::Int(8) => Ok(ast::DecimalSuffix::I8(ast::I8Suffix {
+                    span: dle.span,
+                })),
+                Ty::Int(16) => Ok(ast::DecimalSuffix::I16(ast::I16Suffix {
+                    span: dle.span,
+                })),
+                Ty::Int(32) => Ok(ast::DecimalSuffix::I32(ast::I32Suffix {
+                    span: dle.span,
+                })),
+                Ty::Int(64) => Ok(ast::DecimalSuffix::I64(ast::I64Suffix {
+                    span: dle.span,
+                })),
                 Ty::Uint(_) => Err(
-                    "ZConstLiteralRewriter: Uint size must be divisible by 8".to_string(),
+                    "ZConstLiteralRewriter: Uint size must be 8, 16, 32, 64, 128, or 256"
+                        .to_string(),
                 ),
+                Ty::Int(_) => {
+                    Err("ZConstLiteralRewriter: Int size must be 8, 16, 32, or 64".to_string())
+                }
                 Ty::Field => Ok(ast::DecimalSuffix::Field(ast::FieldSuffix {
                     span: dle.span,
                 })),
@@ -213,6 +238,34 @@ impl<'ast> ZVisitorMut<'ast> for ZConstLiteralRewriter {
         self.visit_span(&mut ise.span)
     }
 
+    fn visit_inline_tuple_expression(
+        &mut self,
+        ite: &mut ast::InlineTupleExpression<'ast>,
+    ) -> ZVisitorResult {
+        let to_ty = self.replace(None);
+        let el_tys = match to_ty.as_ref() {
+            Some(Ty::Tuple(tys)) if tys.len() == ite.expressions.len() => Ok(tys.clone()),
+            Some(Ty::Tuple(tys)) => Err(format!(
+                "ZConstLiteralRewriter: rewriting {}-element InlineTupleExpression to {}-tuple",
+                ite.expressions.len(),
+                tys.len(),
+            )),
+            Some(_) => Err(
+                "ZConstLiteralRewriter: rewriting InlineTupleExpression to non-Tuple type"
+                    .to_string(),
+            ),
+            None => Ok(vec![]),
+        }?;
+
+        for (i, e) in ite.expressions.iter_mut().enumerate() {
+            self.to_ty = el_tys.get(i).cloned();
+            self.visit_expression(e)?;
+        }
+        self.to_ty = to_ty;
+
+        self.visit_span(&mut ite.span)
+    }
+
     fn visit_inline_array_expression(
         &mut self,
         iae: &mut ast::InlineArrayExpression<'ast>,
@@ -295,6 +348,24 @@ impl<'ast> ZVisitorMut<'ast> for ZConstLiteralRewriter {
         self.visit_span(&mut aty.span)
     }
 
+    fn visit_tuple_type(&mut self, tty: &mut ast::TupleType<'ast>) -> ZVisitorResult {
+        // each element of TupleType should match the corresponding element of to_ty
+        let to_ty = self.replace(None);
+        let el_tys = match to_ty.as_ref() {
+            Some(Ty::Tuple(tys)) if tys.len() == tty.elements.len() => Ok(tys.clone()),
+            Some(_) => Err("ZConstLiteralRewriter: Tuple type mismatch".to_string()),
+            None => Ok(vec![]),
+        }?;
+
+        for (i, t) in tty.elements.iter_mut().enumerate() {
+            self.to_ty = el_tys.get(i).cloned();
+            self.visit_type(t)?;
+        }
+        self.to_ty = to_ty;
+
+        self.visit_span(&mut tty.span)
+    }
+
     fn visit_explicit_generics(&mut self, eg: &mut ast::ExplicitGenerics<'ast>) -> ZVisitorResult {
         // always rewrite ConstantGenericValue literals to type U32
         let to_ty = self.replace(Some(Ty::Uint(32)));
@@ -354,4 +425,56 @@ impl<'ast> ZVisitorMut<'ast> for ZConstLiteralRewriter {
         }
         walk_u64_type(self, u64ty)
     }
+
+    fn visit_u128_type(&mut self, u128ty: &mut ast::U128Type<'ast>) -> ZVisitorResult {
+        if self.to_ty.is_some() && !matches!(self.to_ty, Some(Ty::Uint(128))) {
+            return Err("ZConstLiteralRewriter: u128 type mismatch"
+                .to_string()
+                .into());
+        }
+        walk_u128_type(self, u128ty)
+    }
+
+    fn visit_u256_type(&mut self, u256ty: &mut ast::U256Type<'ast>) -> ZVisitorResult {
+        if self.to_ty.is_some() && !matches!(self.to_ty, Some(Ty::Uint(256))) {
+            return Err("ZConstLiteralRewriter: u256 type mismatch"
+                .to_string()
+                .into());
+        }
+        walk_u256_type(self, u256ty)
+    }
+
+    fn visit_i8_type(&mut self, i8ty: &mut ast::I8Type<'ast>) -> ZVisitorResult {
+        if self.to_ty.is_some() && !matches!(self.to_ty, Some(Ty::Int(8))) {
+            return Err("ZConstLiteralRewriter: i8 type mismatch".to_string().into());
+        }
+        walk_i8_type(self, i8ty)
+    }
+
+    fn visit_i16_type(&mut self, i16ty: &mut ast::I16Type<'ast>) -> ZVisitorResult {
+        if self.to_ty.is_some() && !matches!(self.to_ty, Some(Ty::Int(16))) {
+            return Err("ZConstLiteralRewriter: i16 type mismatch"
+                .to_string()
+                .into());
+        }
+        walk_i16_type(self, i16ty)
+    }
+
+    fn visit_i32_type(&mut self, i32ty: &mut ast::I32Type<'ast>) -> ZVisitorResult {
+        if self.to_ty.is_some() && !matches!(self.to_ty, Some(Ty::Int(32))) {
+            return Err("ZConstLiteralRewriter: i32 type mismatch"
+                .to_string()
+                .into());
+        }
+        walk_i32_type(self, i32ty)
+    }
+
+    fn visit_i64_type(&mut self, i64ty: &mut ast::I64Type<'ast>) -> ZVisitorResult {
+        if self.to_ty.is_some() && !matches!(self.to_ty, Some(Ty::Int(64))) {
+            return Err("ZConstLiteralRewriter: i64 type mismatch"
+                .to_string()
+                .into());
+        }
+        walk_i64_type(self, i64ty)
+    }
 }
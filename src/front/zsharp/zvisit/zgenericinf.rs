@@ -204,6 +204,25 @@ impl<'ast, 'gen, const IS_CNST: bool> ZGenericInf<'ast, 'gen, IS_CNST> {
             TT::Basic(dty_b) => self.fdef_gen_ty_basic(arg_ty, dty_b),
             TT::Array(dty_a) => self.fdef_gen_ty_array(arg_ty, dty_a),
             TT::Struct(dty_s) => self.fdef_gen_ty_struct_or_type(arg_ty, dty_s),
+            TT::Tuple(dty_t) => self.fdef_gen_ty_tuple(arg_ty, dty_t),
+        }
+    }
+
+    fn fdef_gen_ty_tuple(
+        &mut self,
+        arg_ty: Ty,
+        def_ty: &ast::TupleType<'ast>,
+    ) -> Result<(), String> {
+        match arg_ty {
+            Ty::Tuple(arg_tys) if arg_tys.len() == def_ty.elements.len() => arg_tys
+                .into_iter()
+                .zip(def_ty.elements.iter())
+                .try_for_each(|(arg_ty, def_ty)| self.fdef_gen_ty(arg_ty, def_ty)),
+            _ => Err(format!(
+                "Type mismatch unifying generics: got {}, decl was {}-tuple",
+                arg_ty,
+                def_ty.elements.len(),
+            )),
         }
     }
 
@@ -438,6 +457,7 @@ impl<'ast, 'gen, const IS_CNST: bool> ZGenericInf<'ast, 'gen, IS_CNST> {
             InlineStruct(_) => {
                 Err("ZGenericInf: got InlineStruct in array dim expr (unimpl)".into())
             }
+            InlineTuple(_) => Err("ZGenericInf: got InlineTuple in array dim expr (unimpl)".into()),
             ArrayInitializer(_) => {
                 Err("ZGenericInf: got ArrayInitializer in array dim expr (unimpl)".into())
             }
@@ -19,6 +19,8 @@ pub(in super::super) struct ZStatementWalker<'ast, 'ret> {
     gens: &'ret [ast::IdentifierExpression<'ast>],
     zgen: &'ret ZGen<'ast>,
     vars: Vec<HashMap<String, ast::Type<'ast>>>,
+    // the innermost statement visited so far; on error, the one that failed
+    stmt_span: Option<ast::Span<'ast>>,
 }
 
 impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
@@ -30,16 +32,25 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
     ) -> Self {
         let vars = vec![prms
             .iter()
-            .map(|p| (p.id.value.clone(), p.ty.clone()))
+            .map(|p| {
+                zgen.record_type(&p.id.value, &p.id.span, &p.ty);
+                (p.id.value.clone(), p.ty.clone())
+            })
             .collect()];
         Self {
             rets,
             gens,
             zgen,
             vars,
+            stmt_span: None,
         }
     }
 
+    /// The span of the statement being visited (or that failed to type-check).
+    pub(in super::super) fn stmt_span(&self) -> Option<&ast::Span<'ast>> {
+        self.stmt_span.as_ref()
+    }
+
     fn eq_type(&self, ty: &ast::Type<'ast>, ty2: &ast::Type<'ast>) -> ZVisitorResult {
         eq_type(ty, ty2, self.zgen)
     }
@@ -84,6 +95,7 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
             Literal(le) => self.unify_literal(ty, le),
             InlineArray(ia) => self.unify_inline_array(ty, ia),
             InlineStruct(is) => self.unify_inline_struct(ty, is),
+            InlineTuple(it) => self.unify_inline_tuple(ty, it),
             ArrayInitializer(ai) => self.unify_array_initializer(ty, ai),
         }
     }
@@ -164,13 +176,9 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
                         "ZStatementWalker: fn {} has no return type",
                         &id.value,
                     )))
-                } else if fdef.returns.len() > 1 {
-                    // XXX(unimpl) multiple return types not implemented
-                    Err(ZVisitorError(format!(
-                        "ZStatementWalker: fn {} has multiple returns",
-                        &id.value,
-                    )))
                 } else {
+                    // multiple returns were collapsed into a tuple in visit_declarations
+                    assert_eq!(fdef.returns.len(), 1);
                     let rty = if alen == 1 { rty } else { None };
                     Ok((self.get_call_ty(fdef, ca, rty)?, 1))
                 }
@@ -272,6 +280,35 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
         }
     }
 
+    fn unify_inline_tuple(
+        &self,
+        ty: ast::Type<'ast>,
+        it: &mut ast::InlineTupleExpression<'ast>,
+    ) -> ZVisitorResult {
+        let tt = if let ast::Type::Tuple(tt) = ty {
+            tt
+        } else {
+            return Err(ZVisitorError(format!(
+                "ZStatementWalker: inline tuple wanted type {:?}:\n{}",
+                &ty,
+                span_to_string(&it.span),
+            )));
+        };
+
+        if tt.elements.len() != it.expressions.len() {
+            return Err(ZVisitorError(format!(
+                "ZStatementWalker: inline tuple has {} elements, expected {}:\n{}",
+                it.expressions.len(),
+                tt.elements.len(),
+                span_to_string(&it.span),
+            )));
+        }
+        tt.elements
+            .into_iter()
+            .zip(it.expressions.iter_mut())
+            .try_for_each(|(ety, e)| self.unify_expression(ety, e))
+    }
+
     fn unify_inline_array(
         &self,
         ty: ast::Type<'ast>,
@@ -341,17 +378,18 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
 
         let (lt, rt) = match &be.op {
             BitXor | BitAnd | BitOr => match &bt {
-                U8(_) | U16(_) | U32(_) | U64(_) => Ok((Basic(bt.clone()), Basic(bt))),
+                U8(_) | U16(_) | U32(_) | U64(_) | U128(_) | U256(_) | I8(_) | I16(_) | I32(_)
+                | I64(_) => Ok((Basic(bt.clone()), Basic(bt))),
                 _ => Err(ZVisitorError(
-                    "ZStatementWalker: Bit/Rem operators require U* operands".to_owned(),
+                    "ZStatementWalker: Bit/Rem operators require U* or I* operands".to_owned(),
                 )),
             },
             RightShift | LeftShift => match &bt {
-                U8(_) | U16(_) | U32(_) | U64(_) => {
-                    Ok((Basic(bt), Basic(U32(ast::U32Type { span: be.span }))))
-                }
+                U8(_) | U16(_) | U32(_) | U64(_) | U128(_) | U256(_) | I8(_) | I16(_) | I32(_)
+                | I64(_) => Ok((Basic(bt), Basic(U32(ast::U32Type { span: be.span })))),
                 _ => Err(ZVisitorError(
-                    "ZStatementWalker: << and >> operators require U* left operand".to_owned(),
+                    "ZStatementWalker: << and >> operators require U* or I* left operand"
+                        .to_owned(),
                 )),
             },
             Or | And => match &bt {
@@ -362,7 +400,8 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
             },
             Add | Sub | Mul | Div | Rem => match &bt {
                 Boolean(_) => Err(ZVisitorError(
-                    "ZStatementWalker: +,-,*,/ operators require Field or U* operands".to_owned(),
+                    "ZStatementWalker: +,-,*,/ operators require Field, U*, or I* operands"
+                        .to_owned(),
                 )),
                 _ => Ok((Basic(bt.clone()), Basic(bt))),
             },
@@ -448,14 +487,15 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
         let ety = match &ue.op {
             Pos(_) | Neg(_) => match &bt {
                 Boolean(_) => Err(ZVisitorError(
-                    "ZStatementWalker: +,- unary operators require Field or U* operands"
+                    "ZStatementWalker: +,- unary operators require Field, U*, or I* operands"
                         .to_string(),
                 )),
                 _ => Ok(Basic(bt)),
             },
             Not(_) => match &bt {
                 Field(_) => Err(ZVisitorError(
-                    "ZStatementWalker: ! unary operator requires U* or Bool operand".to_string(),
+                    "ZStatementWalker: ! unary operator requires U*, I*, or Bool operand"
+                        .to_string(),
                 )),
                 _ => Ok(Basic(bt)),
             },
@@ -499,6 +539,8 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
                     HNE::U16(_) if matches!(&bt, U16(_)) => Ok(()),
                     HNE::U32(_) if matches!(&bt, U32(_)) => Ok(()),
                     HNE::U64(_) if matches!(&bt, U64(_)) => Ok(()),
+                    HNE::U128(_) if matches!(&bt, U128(_)) => Ok(()),
+                    HNE::U256(_) if matches!(&bt, U256(_)) => Ok(()),
                     _ => Err(ZVisitorError(format!(
                         "ZStatementWalker: HexLiteral seemed to want type {:?}:\n{}",
                         &bt,
@@ -515,6 +557,12 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
                         (DS::U16(_), U16(_)) => Ok(()),
                         (DS::U32(_), U32(_)) => Ok(()),
                         (DS::U64(_), U64(_)) => Ok(()),
+                        (DS::U128(_), U128(_)) => Ok(()),
+                        (DS::U256(_), U256(_)) => Ok(()),
+                        (DS::I8(_), I8(_)) => Ok(()),
+                        (DS::I16(_), I16(_)) => Ok(()),
+                        (DS::I32(_), I32(_)) => Ok(()),
+                        (DS::I64(_), I64(_)) => Ok(()),
                         _ => Err(ZVisitorError(format!(
                             "ZStatementWalker: DecimalLiteral wanted {:?} found {:?}:\n{}",
                             &bt,
@@ -532,6 +580,12 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
                         U16(_) => Ok(DS::U16(ast::U16Suffix { span: dle.span })),
                         U32(_) => Ok(DS::U32(ast::U32Suffix { span: dle.span })),
                         U64(_) => Ok(DS::U64(ast::U64Suffix { span: dle.span })),
+                        U128(_) => Ok(DS::U128(ast::U128Suffix { span: dle.span })),
+                        U256(_) => Ok(DS::U256(ast::U256Suffix { span: dle.span })),
+                        I8(_) => Ok(DS::I8(ast::I8Suffix { span: dle.span })),
+                        I16(_) => Ok(DS::I16(ast::I16Suffix { span: dle.span })),
+                        I32(_) => Ok(DS::I32(ast::I32Suffix { span: dle.span })),
+                        I64(_) => Ok(DS::I64(ast::I64Suffix { span: dle.span })),
                     }
                     .map(|ds| {
                         dle.suffix.replace(ds);
@@ -603,6 +657,26 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
                         ));
                     }
                 }
+                Tuple(tacc) => {
+                    if let Type::Tuple(tty) = ty {
+                        let n_elms = tty.elements.len();
+                        tty.elements
+                            .into_iter()
+                            .nth(tacc.index.value)
+                            .ok_or_else(|| {
+                                ZVisitorError(format!(
+                                    "ZStatementWalker: index {} out of bounds for {}-tuple:\n{}",
+                                    tacc.index.value,
+                                    n_elms,
+                                    span_to_string(&tacc.span),
+                                ))
+                            })?
+                    } else {
+                        return Err(ZVisitorError(
+                            "ZStatementWalker: tried to access a non-Tuple as a Tuple".to_string(),
+                        ));
+                    }
+                }
             }
         }
 
@@ -659,21 +733,21 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
     }
 
     fn lookup_type(&self, id: &ast::IdentifierExpression<'ast>) -> ZResult<ast::Type<'ast>> {
-        if self.generic_defined(&id.value) {
+        let ty = if self.generic_defined(&id.value) {
             // generics are always U32
-            Ok(ast::Type::Basic(ast::BasicType::U32(ast::U32Type {
-                span: id.span,
-            })))
+            ast::Type::Basic(ast::BasicType::U32(ast::U32Type { span: id.span }))
         } else if let Some(t) = self.zgen.const_ty_lookup_(&id.value) {
-            Ok(t.clone())
+            t.clone()
         } else {
             self.lookup_var(&id.value).ok_or_else(|| {
                 ZVisitorError(format!(
                     "ZStatementWalker: identifier {} undefined",
                     &id.value
                 ))
-            })
-        }
+            })?
+        };
+        self.zgen.record_type(&id.value, &id.span, &ty);
+        Ok(ty)
     }
 
     fn apply_varonly<F, R>(&mut self, nm: &str, f: F) -> ZResult<R>
@@ -703,6 +777,25 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
         })
     }
 
+    fn lhs_type(
+        &mut self,
+        tioa: &ast::TypedIdentifierOrAssignee<'ast>,
+    ) -> ZResult<ast::Type<'ast>> {
+        use ast::TypedIdentifierOrAssignee::*;
+        let (na, accs, span) = match tioa {
+            Assignee(a) => (&a.id.value, a.accesses.as_ref(), &a.span),
+            TypedIdentifier(ti) => (&ti.identifier.value, &[][..], &ti.span),
+        };
+        let ty = self.lookup_type_varonly(na)?.ok_or_else(|| {
+            ZVisitorError(format!(
+                "ZStatementWalker: found expression with undeclared LHS {}:\n{}",
+                na,
+                span_to_string(span),
+            ))
+        })?;
+        self.walk_accesses(ty, accs, aacc_to_msacc)
+    }
+
     fn push_scope(&mut self) {
         self.vars.push(HashMap::new());
     }
@@ -711,12 +804,13 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
         self.vars.pop();
     }
 
-    // shallow canonicalization: flatten down to the first Basic, Array, or non-alias Struct
+    // shallow canonicalization: flatten down to the first Basic, Array, Tuple, or non-alias Struct
     fn canon_type(&self, ty: ast::Type<'ast>) -> ZResult<ast::Type<'ast>> {
         use ast::Type::*;
         match ty {
             Basic(b) => Ok(ast::Type::Basic(b)),
             Array(a) => Ok(ast::Type::Array(a)),
+            Tuple(t) => Ok(ast::Type::Tuple(t)),
             Struct(s) => match self.get_struct_or_type(&s.id.value)? {
                 Ok(_) => Ok(ast::Type::Struct(s)),
                 Err(tydef) => self.canon_type(tydef.ty.clone()),
@@ -726,17 +820,25 @@ impl<'ast, 'ret> ZStatementWalker<'ast, 'ret> {
 }
 
 impl<'ast, 'ret> ZVisitorMut<'ast> for ZStatementWalker<'ast, 'ret> {
+    fn visit_statement(&mut self, stmt: &mut ast::Statement<'ast>) -> ZVisitorResult {
+        self.stmt_span = Some(*stmt.span());
+        walk_statement(self, stmt)
+    }
+
     fn visit_return_statement(&mut self, ret: &mut ast::ReturnStatement<'ast>) -> ZVisitorResult {
-        if self.rets.len() != ret.expressions.len() {
-            return Err(ZVisitorError(
-                "ZStatementWalker: mismatched return expression/type".to_owned(),
-            ));
+        // a multi-return statement returns a tuple (see visit_declarations)
+        if ret.expressions.len() > 1 {
+            let expressions = std::mem::take(&mut ret.expressions);
+            ret.expressions
+                .push(ast::Expression::InlineTuple(ast::InlineTupleExpression {
+                    expressions,
+                    span: ret.span,
+                }));
         }
 
-        // XXX(unimpl) multi-return statements not supported
-        if self.rets.len() > 1 {
+        if self.rets.len() != ret.expressions.len() {
             return Err(ZVisitorError(
-                "ZStatementWalker: multi-returns not supported".to_owned(),
+                "ZStatementWalker: mismatched return expression/type".to_owned(),
             ));
         }
 
@@ -774,6 +876,8 @@ impl<'ast, 'ret> ZVisitorMut<'ast> for ZStatementWalker<'ast, 'ret> {
 
         self.push_scope(); // {
         self.insert_var(&iter.index.value, iter.ty.clone())?;
+        self.zgen
+            .record_type(&iter.index.value, &iter.index.span, &iter.ty);
         self.visit_identifier_expression(&mut iter.index)?;
 
         // type propagation for index expressions
@@ -790,6 +894,31 @@ impl<'ast, 'ret> ZVisitorMut<'ast> for ZStatementWalker<'ast, 'ret> {
         self.visit_span(&mut iter.span)
     }
 
+    fn visit_if_else_statement(&mut self, ite: &mut ast::IfElseStatement<'ast>) -> ZVisitorResult {
+        let bool_ty =
+            ast::Type::Basic(ast::BasicType::Boolean(ast::BooleanType { span: ite.span }));
+        self.unify(Some(bool_ty), &mut ite.condition)?;
+        self.visit_expression(&mut ite.condition)?;
+
+        self.push_scope(); // {
+        ite.consequence
+            .iter_mut()
+            .try_for_each(|s| self.visit_statement(s))?;
+        self.pop_scope(); // }
+
+        if let Some(e) = &mut ite.alternative {
+            self.visit_else_clause(e)?;
+        }
+        self.visit_span(&mut ite.span)
+    }
+
+    fn visit_else_clause(&mut self, e: &mut ast::ElseClause<'ast>) -> ZVisitorResult {
+        self.push_scope(); // {
+        walk_else_clause(self, e)?;
+        self.pop_scope(); // }
+        Ok(())
+    }
+
     fn visit_definition_statement(
         &mut self,
         def: &mut ast::DefinitionStatement<'ast>,
@@ -800,35 +929,26 @@ impl<'ast, 'ret> ZVisitorMut<'ast> for ZStatementWalker<'ast, 'ret> {
             .iter_mut()
             .try_for_each(|l| self.visit_typed_identifier_or_assignee(l))?;
 
-        // unify lhs and rhs
-        // XXX(unimpl) multi-LHS statements not supported
-        if def.lhs.len() > 1 {
-            return Err(ZVisitorError(
-                "ZStatementWalker: multi-LHS assignments not supported".to_owned(),
-            ));
-        }
-        let ty_accs = def
+        // unify lhs and rhs; a multi-LHS definition destructures a tuple
+        let mut tys = def
             .lhs
-            .first()
-            .map(|tioa| {
-                use ast::TypedIdentifierOrAssignee::*;
-                let (na, acc) = match tioa {
-                    Assignee(a) => (&a.id.value, a.accesses.as_ref()),
-                    TypedIdentifier(ti) => (&ti.identifier.value, &[][..]),
-                };
-                self.lookup_type_varonly(na).map(|t| t.map(|t| (t, acc)))
-            })
-            .transpose()?
-            .flatten();
-        if let Some((ty, accs)) = ty_accs {
-            let ty = self.walk_accesses(ty, accs, aacc_to_msacc)?;
-            self.unify(Some(ty), &mut def.expression)?;
-        } else {
-            return Err(ZVisitorError(format!(
-                "ZStatementWalker: found expression with no LHS:\n{}",
-                span_to_string(&def.span),
-            )));
-        }
+            .iter()
+            .map(|tioa| self.lhs_type(tioa))
+            .collect::<ZResult<Vec<_>>>()?;
+        let ty = match tys.len() {
+            0 => {
+                return Err(ZVisitorError(format!(
+                    "ZStatementWalker: found expression with no LHS:\n{}",
+                    span_to_string(&def.span),
+                )))
+            }
+            1 => tys.pop().unwrap(),
+            _ => ast::Type::Tuple(ast::TupleType {
+                elements: tys,
+                span: def.span,
+            }),
+        };
+        self.unify(Some(ty), &mut def.expression)?;
         self.visit_expression(&mut def.expression)?;
         self.visit_span(&mut def.span)
     }
@@ -855,6 +975,8 @@ impl<'ast, 'ret> ZVisitorMut<'ast> for ZStatementWalker<'ast, 'ret> {
     fn visit_typed_identifier(&mut self, ti: &mut ast::TypedIdentifier<'ast>) -> ZVisitorResult {
         ZConstLiteralRewriter::new(None).visit_type(&mut ti.ty)?;
         self.insert_var(&ti.identifier.value, ti.ty.clone())?;
+        self.zgen
+            .record_type(&ti.identifier.value, &ti.identifier.span, &ti.ty);
         walk_typed_identifier(self, ti)
     }
 
@@ -930,6 +1052,7 @@ impl<'ast, 'ret> ZVisitorMut<'ast> for ZStatementWalker<'ast, 'ret> {
 enum MSAccRef<'a, 'ast> {
     Select(&'a ast::ArrayAccess<'ast>),
     Member(&'a ast::MemberAccess<'ast>),
+    Tuple(&'a ast::TupleAccess<'ast>),
 }
 
 fn aacc_to_msacc<'a, 'ast>(i: &'a ast::AssigneeAccess<'ast>) -> ZResult<MSAccRef<'a, 'ast>> {
@@ -937,6 +1060,7 @@ fn aacc_to_msacc<'a, 'ast>(i: &'a ast::AssigneeAccess<'ast>) -> ZResult<MSAccRef
     Ok(match i {
         Select(t) => MSAccRef::Select(t),
         Member(t) => MSAccRef::Member(t),
+        Tuple(t) => MSAccRef::Tuple(t),
     })
 }
 
@@ -945,6 +1069,7 @@ fn acc_to_msacc<'a, 'ast>(i: &'a ast::Access<'ast>) -> ZResult<MSAccRef<'a, 'ast
     match i {
         Select(t) => Ok(MSAccRef::Select(t)),
         Member(t) => Ok(MSAccRef::Member(t)),
+        Tuple(t) => Ok(MSAccRef::Tuple(t)),
         Call(t) => Err(ZVisitorError(format!(
             "Illegal fn call:\n{}",
             span_to_string(&t.span),
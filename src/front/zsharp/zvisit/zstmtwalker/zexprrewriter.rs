@@ -26,6 +26,7 @@ impl<'ast> ZVisitorMut<'ast> for ZExpressionRewriter<'ast> {
             Literal(le) => self.visit_literal_expression(le),
             InlineArray(iae) => self.visit_inline_array_expression(iae),
             InlineStruct(ise) => self.visit_inline_struct_expression(ise),
+            InlineTuple(ite) => self.visit_inline_tuple_expression(ite),
             ArrayInitializer(aie) => self.visit_array_initializer_expression(aie),
             Identifier(ie) => {
                 if let Some(e) = self.gvmap.get(&ie.value) {
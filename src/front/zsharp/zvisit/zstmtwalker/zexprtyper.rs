@@ -38,23 +38,26 @@ impl<'ast, 'ret, 'wlk> ZExpressionTyper<'ast, 'ret, 'wlk> {
         ty: ast::Type<'ast>,
         cnt: ast::Expression<'ast>,
         spn: &ast::Span<'ast>,
-    ) -> ast::ArrayType<'ast> {
+    ) -> ZResult<ast::ArrayType<'ast>> {
         use ast::Type::*;
         match ty {
             Array(mut aty) => {
                 aty.dimensions.insert(0, cnt);
-                aty
+                Ok(aty)
             }
-            Basic(bty) => ast::ArrayType {
+            Basic(bty) => Ok(ast::ArrayType {
                 ty: ast::BasicOrStructType::Basic(bty),
                 dimensions: vec![cnt],
                 span: *spn,
-            },
-            Struct(sty) => ast::ArrayType {
+            }),
+            Struct(sty) => Ok(ast::ArrayType {
                 ty: ast::BasicOrStructType::Struct(sty),
                 dimensions: vec![cnt],
                 span: *spn,
-            },
+            }),
+            Tuple(_) => Err(ZVisitorError(
+                "ZExpressionTyper: arrays of tuples are not supported".to_string(),
+            )),
         }
     }
 }
@@ -76,6 +79,7 @@ impl<'ast, 'ret, 'wlk> ZVisitorMut<'ast> for ZExpressionTyper<'ast, 'ret, 'wlk>
             Literal(le) => self.visit_literal_expression(le),
             InlineArray(iae) => self.visit_inline_array_expression(iae),
             InlineStruct(ise) => self.visit_inline_struct_expression(ise),
+            InlineTuple(ite) => self.visit_inline_tuple_expression(ite),
             ArrayInitializer(aie) => self.visit_array_initializer_expression(aie),
         }
     }
@@ -201,6 +205,12 @@ impl<'ast, 'ret, 'wlk> ZVisitorMut<'ast> for ZExpressionTyper<'ast, 'ret, 'wlk>
             DS::U16(s) => self.ty.replace(Basic(U16(ast::U16Type { span: s.span }))),
             DS::U32(s) => self.ty.replace(Basic(U32(ast::U32Type { span: s.span }))),
             DS::U64(s) => self.ty.replace(Basic(U64(ast::U64Type { span: s.span }))),
+            DS::U128(s) => self.ty.replace(Basic(U128(ast::U128Type { span: s.span }))),
+            DS::U256(s) => self.ty.replace(Basic(U256(ast::U256Type { span: s.span }))),
+            DS::I8(s) => self.ty.replace(Basic(I8(ast::I8Type { span: s.span }))),
+            DS::I16(s) => self.ty.replace(Basic(I16(ast::I16Type { span: s.span }))),
+            DS::I32(s) => self.ty.replace(Basic(I32(ast::I32Type { span: s.span }))),
+            DS::I64(s) => self.ty.replace(Basic(I64(ast::I64Type { span: s.span }))),
             DS::Field(s) => self
                 .ty
                 .replace(Basic(Field(ast::FieldType { span: s.span }))),
@@ -219,6 +229,8 @@ impl<'ast, 'ret, 'wlk> ZVisitorMut<'ast> for ZExpressionTyper<'ast, 'ret, 'wlk>
             HNE::U16(s) => self.ty.replace(Basic(U16(ast::U16Type { span: s.span }))),
             HNE::U32(s) => self.ty.replace(Basic(U32(ast::U32Type { span: s.span }))),
             HNE::U64(s) => self.ty.replace(Basic(U64(ast::U64Type { span: s.span }))),
+            HNE::U128(s) => self.ty.replace(Basic(U128(ast::U128Type { span: s.span }))),
+            HNE::U256(s) => self.ty.replace(Basic(U256(ast::U256Type { span: s.span }))),
         };
         Ok(())
     }
@@ -232,7 +244,7 @@ impl<'ast, 'ret, 'wlk> ZVisitorMut<'ast> for ZExpressionTyper<'ast, 'ret, 'wlk>
 
         self.visit_expression(&mut aie.value)?;
         if let Some(ty) = self.take()? {
-            let ty = self.arrayize(ty, aie.count.as_ref().clone(), &aie.span);
+            let ty = self.arrayize(ty, aie.count.as_ref().clone(), &aie.span)?;
             self.ty.replace(Array(ty));
         }
         Ok(())
@@ -295,21 +307,45 @@ impl<'ast, 'ret, 'wlk> ZVisitorMut<'ast> for ZExpressionTyper<'ast, 'ret, 'wlk>
                 }
             })?;
 
-        self.ty = acc_ty.map(|at| {
-            ast::Type::Array(self.arrayize(
-                at,
-                ast::Expression::Literal(ast::LiteralExpression::HexLiteral(
-                    ast::HexLiteralExpression {
-                        value: ast::HexNumberExpression::U32(ast::U32NumberExpression {
-                            value: format!("{acc_len:04x}"),
+        self.ty = acc_ty
+            .map(|at| {
+                self.arrayize(
+                    at,
+                    ast::Expression::Literal(ast::LiteralExpression::HexLiteral(
+                        ast::HexLiteralExpression {
+                            value: ast::HexNumberExpression::U32(ast::U32NumberExpression {
+                                value: format!("{acc_len:04x}"),
+                                span: iae.span,
+                            }),
                             span: iae.span,
-                        }),
-                        span: iae.span,
-                    },
-                )),
-                &iae.span,
-            ))
-        });
+                        },
+                    )),
+                    &iae.span,
+                )
+                .map(ast::Type::Array)
+            })
+            .transpose()?;
+        Ok(())
+    }
+
+    fn visit_inline_tuple_expression(
+        &mut self,
+        ite: &mut ast::InlineTupleExpression<'ast>,
+    ) -> ZVisitorResult {
+        assert!(self.ty.is_none());
+        let mut elements = Vec::with_capacity(ite.expressions.len());
+        for e in ite.expressions.iter_mut() {
+            self.visit_expression(e)?;
+            match self.take()? {
+                Some(ty) => elements.push(ty),
+                // if any element is untyped, so is the tuple
+                None => return Ok(()),
+            }
+        }
+        self.ty.replace(ast::Type::Tuple(ast::TupleType {
+            elements,
+            span: ite.span,
+        }));
         Ok(())
     }
 
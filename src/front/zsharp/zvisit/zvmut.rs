@@ -101,6 +101,13 @@ pub trait ZVisitorMut<'ast>: Sized {
         walk_function_definition(self, fundef)
     }
 
+    fn visit_function_attribute(
+        &mut self,
+        attr: &mut ast::FunctionAttribute<'ast>,
+    ) -> ZVisitorResult {
+        walk_function_attribute(self, attr)
+    }
+
     fn visit_parameter(&mut self, param: &mut ast::Parameter<'ast>) -> ZVisitorResult {
         walk_parameter(self, param)
     }
@@ -171,6 +178,30 @@ pub trait ZVisitorMut<'ast>: Sized {
         walk_u64_type(self, u64ty)
     }
 
+    fn visit_u128_type(&mut self, u128ty: &mut ast::U128Type<'ast>) -> ZVisitorResult {
+        walk_u128_type(self, u128ty)
+    }
+
+    fn visit_u256_type(&mut self, u256ty: &mut ast::U256Type<'ast>) -> ZVisitorResult {
+        walk_u256_type(self, u256ty)
+    }
+
+    fn visit_i8_type(&mut self, i8ty: &mut ast::I8Type<'ast>) -> ZVisitorResult {
+        walk_i8_type(self, i8ty)
+    }
+
+    fn visit_i16_type(&mut self, i16ty: &mut ast::I16Type<'ast>) -> ZVisitorResult {
+        walk_i16_type(self, i16ty)
+    }
+
+    fn visit_i32_type(&mut self, i32ty: &mut ast::I32Type<'ast>) -> ZVisitorResult {
+        walk_i32_type(self, i32ty)
+    }
+
+    fn visit_i64_type(&mut self, i64ty: &mut ast::I64Type<'ast>) -> ZVisitorResult {
+        walk_i64_type(self, i64ty)
+    }
+
     fn visit_array_type(&mut self, aty: &mut ast::ArrayType<'ast>) -> ZVisitorResult {
         walk_array_type(self, aty)
     }
@@ -186,6 +217,10 @@ pub trait ZVisitorMut<'ast>: Sized {
         walk_struct_type(self, sty)
     }
 
+    fn visit_tuple_type(&mut self, tty: &mut ast::TupleType<'ast>) -> ZVisitorResult {
+        walk_tuple_type(self, tty)
+    }
+
     fn visit_explicit_generics(&mut self, eg: &mut ast::ExplicitGenerics<'ast>) -> ZVisitorResult {
         walk_explicit_generics(self, eg)
     }
@@ -235,6 +270,30 @@ pub trait ZVisitorMut<'ast>: Sized {
         walk_u64_suffix(self, u64s)
     }
 
+    fn visit_u128_suffix(&mut self, u128s: &mut ast::U128Suffix<'ast>) -> ZVisitorResult {
+        walk_u128_suffix(self, u128s)
+    }
+
+    fn visit_u256_suffix(&mut self, u256s: &mut ast::U256Suffix<'ast>) -> ZVisitorResult {
+        walk_u256_suffix(self, u256s)
+    }
+
+    fn visit_i8_suffix(&mut self, i8s: &mut ast::I8Suffix<'ast>) -> ZVisitorResult {
+        walk_i8_suffix(self, i8s)
+    }
+
+    fn visit_i16_suffix(&mut self, i16s: &mut ast::I16Suffix<'ast>) -> ZVisitorResult {
+        walk_i16_suffix(self, i16s)
+    }
+
+    fn visit_i32_suffix(&mut self, i32s: &mut ast::I32Suffix<'ast>) -> ZVisitorResult {
+        walk_i32_suffix(self, i32s)
+    }
+
+    fn visit_i64_suffix(&mut self, i64s: &mut ast::I64Suffix<'ast>) -> ZVisitorResult {
+        walk_i64_suffix(self, i64s)
+    }
+
     fn visit_field_suffix(&mut self, fs: &mut ast::FieldSuffix<'ast>) -> ZVisitorResult {
         walk_field_suffix(self, fs)
     }
@@ -288,6 +347,20 @@ pub trait ZVisitorMut<'ast>: Sized {
         walk_u64_number_expression(self, u64e)
     }
 
+    fn visit_u128_number_expression(
+        &mut self,
+        u128e: &mut ast::U128NumberExpression<'ast>,
+    ) -> ZVisitorResult {
+        walk_u128_number_expression(self, u128e)
+    }
+
+    fn visit_u256_number_expression(
+        &mut self,
+        u256e: &mut ast::U256NumberExpression<'ast>,
+    ) -> ZVisitorResult {
+        walk_u256_number_expression(self, u256e)
+    }
+
     fn visit_underscore(&mut self, u: &mut ast::Underscore<'ast>) -> ZVisitorResult {
         walk_underscore(self, u)
     }
@@ -388,6 +461,14 @@ pub trait ZVisitorMut<'ast>: Sized {
         walk_member_access(self, ma)
     }
 
+    fn visit_tuple_access(&mut self, ta: &mut ast::TupleAccess<'ast>) -> ZVisitorResult {
+        walk_tuple_access(self, ta)
+    }
+
+    fn visit_tuple_index(&mut self, ti: &mut ast::TupleIndex<'ast>) -> ZVisitorResult {
+        walk_tuple_index(self, ti)
+    }
+
     fn visit_inline_array_expression(
         &mut self,
         iae: &mut ast::InlineArrayExpression<'ast>,
@@ -406,6 +487,13 @@ pub trait ZVisitorMut<'ast>: Sized {
         walk_spread(self, spread)
     }
 
+    fn visit_inline_tuple_expression(
+        &mut self,
+        ite: &mut ast::InlineTupleExpression<'ast>,
+    ) -> ZVisitorResult {
+        walk_inline_tuple_expression(self, ite)
+    }
+
     fn visit_inline_struct_expression(
         &mut self,
         ise: &mut ast::InlineStructExpression<'ast>,
@@ -485,4 +573,16 @@ pub trait ZVisitorMut<'ast>: Sized {
     ) -> ZVisitorResult {
         walk_iteration_statement(self, iter)
     }
+
+    fn visit_if_else_statement(&mut self, ite: &mut ast::IfElseStatement<'ast>) -> ZVisitorResult {
+        walk_if_else_statement(self, ite)
+    }
+
+    fn visit_else_clause(&mut self, e: &mut ast::ElseClause<'ast>) -> ZVisitorResult {
+        walk_else_clause(self, e)
+    }
+
+    fn visit_log_statement(&mut self, log: &mut ast::LogStatement<'ast>) -> ZVisitorResult {
+        walk_log_statement(self, log)
+    }
 }
@@ -229,8 +229,8 @@ pub fn fold_cache(node: &Term, cache: &mut TermCache<TTerm>, ignore: &[Op]) -> T
                         }
                         (Ashr, Some(a), Some(b)) => cbv(a.clone().ashr(b)),
                         (Ashr, _, Some(b)) => {
-                            assert!(b.uint() < &Integer::from(b.width()));
-                            let n = b.uint().to_usize().unwrap();
+                            // Shifts by the width or more just copy the sign bit
+                            let n = b.uint().to_usize().unwrap_or(usize::MAX).min(b.width() - 1);
                             Some(term![Op::BvSext(n);
                                    term![Op::new_bv_extract(b.width()-1, n); c0]])
                         }
@@ -397,6 +397,7 @@ pub fn fold_cache(node: &Term, cache: &mut TermCache<TTerm>, ignore: &[Op]) -> T
             };
             new_t_opt.unwrap_or_else(|| term(t.op().clone(), t.cs().iter().map(cc_get).collect()))
         };
+        src_loc::inherit(&t, &new_t);
         cache.put(t.downgrade(), new_t.downgrade());
         retainer.push(new_t);
     }
@@ -13,11 +13,14 @@ pub mod sha;
 pub mod tuple;
 mod visit;
 
+use std::collections::BTreeMap;
 use std::num::NonZero;
+use std::time::Instant;
 
 use super::term::*;
 
 use log::{debug, info, trace};
+use serde::Serialize;
 
 #[derive(Clone, Debug)]
 /// An optimization pass
@@ -61,8 +64,58 @@ pub enum Opt {
     FitsInBitsIp,
 }
 
+/// Statistics for one optimization pass; see [opt_with_stats].
+#[derive(Debug, Clone, Serialize)]
+pub struct PassStats {
+    /// The pass
+    pub pass: String,
+    /// Wall-clock time, in seconds
+    pub seconds: f64,
+    /// Statistics for each computation, after the pass
+    pub after: BTreeMap<String, ComputationStats>,
+}
+
 /// Run optimizations on `cs`, in this order, returning the new constraint system.
-pub fn opt<I: IntoIterator<Item = Opt>>(mut cs: Computations, optimizations: I) -> Computations {
+pub fn opt<I: IntoIterator<Item = Opt>>(cs: Computations, optimizations: I) -> Computations {
+    opt_impl(cs, optimizations, None)
+}
+
+/// Run optimizations on `cs`, in this order, returning the new constraint system and statistics
+/// for each pass.
+pub fn opt_with_stats<I: IntoIterator<Item = Opt>>(
+    cs: Computations,
+    optimizations: I,
+) -> (Computations, Vec<PassStats>) {
+    let mut stats = Vec::new();
+    let cs = opt_impl(cs, optimizations, Some(&mut stats));
+    (cs, stats)
+}
+
+fn record_pass(
+    stats: &mut Option<&mut Vec<PassStats>>,
+    pass: &Opt,
+    start: Instant,
+    cs: &Computations,
+) {
+    if let Some(stats) = stats {
+        let seconds = start.elapsed().as_secs_f64();
+        stats.push(PassStats {
+            pass: format!("{pass:?}"),
+            seconds,
+            after: cs
+                .comps
+                .iter()
+                .map(|(name, c)| (name.clone(), c.stats()))
+                .collect(),
+        });
+    }
+}
+
+fn opt_impl<I: IntoIterator<Item = Opt>>(
+    mut cs: Computations,
+    optimizations: I,
+    mut stats: Option<&mut Vec<PassStats>>,
+) -> Computations {
     for c in cs.comps.values() {
         trace!("Before all opts: {}", text::serialize_computation(c));
         info!("Before all opts: {} terms", c.stats().main.n_terms);
@@ -71,9 +124,11 @@ pub fn opt<I: IntoIterator<Item = Opt>>(mut cs: Computations, optimizations: I)
     }
     for i in optimizations {
         debug!("Applying: {:?}", i);
+        let start = Instant::now();
 
         if let Opt::Link = i {
             link::link_all_function_calls(&mut cs);
+            record_pass(&mut stats, &i, start, &cs);
             continue;
         }
 
@@ -173,6 +228,7 @@ pub fn opt<I: IntoIterator<Item = Opt>>(mut cs: Computations, optimizations: I)
             #[cfg(debug_assertions)]
             c.precomputes.check_topo_orderable();
         }
+        record_pass(&mut stats, &i, start, &cs);
         if crate::cfg::cfg().ir.frequent_gc {
             garbage_collect();
         }
@@ -74,6 +74,7 @@ pub trait RewritePass {
                                 .collect(),
                         )
                     });
+                    src_loc::inherit(&top, &new_t);
                     cache.insert(top.clone(), new_t);
                 }
             }
@@ -149,7 +149,10 @@ impl BitVector {
     /// arithmetic right shift
     pub fn ashr(mut self, other: &Self) -> Self {
         assert_eq!(self.width, other.width);
-        let n = other.uint.to_u32().unwrap();
+        let n = other
+            .uint
+            .to_u32()
+            .map_or(self.width as u32, |n| n.min(self.width as u32));
         let b = self.uint.get_bit(self.width as u32 - 1);
         self.uint >>= n;
         for i in 0..n {
@@ -301,4 +304,12 @@ mod test {
             }
         }
     }
+    #[test]
+    fn ashr_by_width() {
+        let bv = |i: u32| BitVector::new(Integer::from(i), 8);
+        for n in [7, 8, 9, 255] {
+            assert_eq!(bv(0b1000_0000).ashr(&bv(n)), bv(0xff));
+            assert_eq!(bv(0b0100_0000).ashr(&bv(n)), bv(0));
+        }
+    }
 }
@@ -43,6 +43,7 @@ pub mod lin;
 pub mod map;
 pub mod precomp;
 pub mod serde_mods;
+pub mod src_loc;
 pub mod text;
 pub mod ty;
 
@@ -1056,6 +1057,7 @@ pub fn garbage_collect() {
     // lock the collector before locking anything else
     collect_terms();
     collect_types();
+    src_loc::collect();
     super::opt::cfold::collect();
 }
 
@@ -1392,10 +1394,10 @@ pub fn const_(value: Value) -> Term {
 /// Make a term with arguments.
 #[track_caller]
 pub fn term(op: Op, cs: Vec<Term>) -> Term {
-    #[cfg_attr(not(debug_assertions), allow(clippy::let_and_return))]
     let t = hc::Table::create(&op, cs);
     #[cfg(debug_assertions)]
     check_rec(&t);
+    src_loc::created(&t);
     t
 }
 
@@ -1573,6 +1575,11 @@ impl ComputationMetadata {
         self.party_ids.len() as u8 - 1
     }
 
+    /// The number of parties.
+    pub fn n_parties(&self) -> usize {
+        self.party_ids.len()
+    }
+
     /// Add a new input to the computation, visible to `party`, or public if `party` is [None].
     pub fn new_input(&mut self, name: String, party: Option<PartyId>, sort: Sort) {
         let var_md = VariableMetadata {
@@ -2075,7 +2082,7 @@ impl Computation {
 }
 
 /// Term DAG statistics
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct DagStats {
     /// number of terms
     pub n_terms: usize,
@@ -2130,7 +2137,7 @@ impl DagDetailedStats {
 }
 
 /// Computation statistics
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ComputationStats {
     /// main
     pub main: DagStats,
@@ -21,6 +21,50 @@ pub struct PreComp {
     pub outputs: FxHashMap<String, Term>,
     sequence: Vec<(String, Sort)>,
     inputs: FxHashSet<(String, Sort)>,
+    /// Diagnostic messages to print during witness computation. See [PreComp::add_log].
+    #[serde(default)]
+    pub logs: Vec<Log>,
+}
+
+/// A diagnostic message (e.g., from a Z# `log!` statement) whose arguments are precomputation
+/// outputs.
+///
+/// Logs are not constrained; they exist only to observe the witness computation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Log {
+    /// The message, with one `{}` placeholder per argument
+    pub format: String,
+    /// For each argument, the names of the (scalar) outputs that hold its value
+    pub args: Vec<Vec<String>>,
+}
+
+impl Log {
+    /// Fill the placeholders in `format` with `args`.
+    ///
+    /// An argument with one value is printed as that value; others are printed as a list.
+    pub fn render<S: AsRef<str>>(format: &str, args: &[Vec<S>]) -> String {
+        let mut out = String::new();
+        let mut pieces = format.split("{}");
+        out.push_str(pieces.next().unwrap());
+        for (i, piece) in pieces.enumerate() {
+            match args.get(i).map(|a| a.as_slice()) {
+                Some([v]) => out.push_str(v.as_ref()),
+                Some(vs) => {
+                    out.push('[');
+                    for (j, v) in vs.iter().enumerate() {
+                        if j > 0 {
+                            out.push_str(", ");
+                        }
+                        out.push_str(v.as_ref());
+                    }
+                    out.push(']');
+                }
+                None => out.push_str("{}"),
+            }
+            out.push_str(piece);
+        }
+        out
+    }
 }
 
 impl PreComp {
@@ -51,6 +95,26 @@ impl PreComp {
         let old = self.outputs.insert(name, value);
         assert!(old.is_none());
     }
+    /// Add a diagnostic message. Each argument is a list of scalar terms; they become outputs of
+    /// the precomputation.
+    pub fn add_log(&mut self, format: String, args: Vec<Vec<Term>>) {
+        let log_idx = self.logs.len();
+        let args = args
+            .into_iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                arg.into_iter()
+                    .enumerate()
+                    .map(|(j, value)| {
+                        let name = format!("__log.{log_idx}.{i}.{j}");
+                        self.add_output(name.clone(), value);
+                        name
+                    })
+                    .collect()
+            })
+            .collect();
+        self.logs.push(Log { format, args });
+    }
     /// Overwrite a step
     pub fn change_output(&mut self, name: &str, value: Term) {
         *self.outputs.get_mut(name).unwrap() = value;
@@ -319,4 +383,36 @@ mod test {
         assert_eq!(p_extra.sequence, p.sequence);
         assert_eq!(p_extra.outputs.len(), 3);
     }
+
+    #[test]
+    fn log_render() {
+        assert_eq!(Log::render::<&str>("no args", &[]), "no args");
+        assert_eq!(
+            Log::render("x = {}, ys = {}!", &[vec!["1"], vec!["2", "3"]]),
+            "x = 1, ys = [2, 3]!"
+        );
+        assert_eq!(Log::render("{} and {}", &[vec!["true"]]), "true and {}");
+    }
+
+    #[test]
+    fn add_log() {
+        let mut p = PreComp::new();
+        let a = parse_term(b"(declare ((a bool)) a)");
+        let b = parse_term(b"(declare ((b (bv 4))) b)");
+        p.add_log(
+            "a = {}, [b, b] = {}".into(),
+            vec![vec![a], vec![b.clone(), b]],
+        );
+        assert_eq!(p.outputs.len(), 3);
+        assert_eq!(
+            p.logs,
+            vec![Log {
+                format: "a = {}, [b, b] = {}".into(),
+                args: vec![
+                    vec!["__log.0.0.0".into()],
+                    vec!["__log.0.1.0".into(), "__log.0.1.1".into()]
+                ],
+            }]
+        );
+    }
 }
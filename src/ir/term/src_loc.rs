@@ -0,0 +1,126 @@
+//! Source locations for terms
+//!
+//! A frontend can attribute the terms it creates to source locations: while a [LocGuard] from
+//! [enter] is live, every newly-created term is tagged with that location. Passes that rewrite
+//! terms can carry the tag over with [inherit]. Backends use [get] to attribute costs back to the
+//! source (e.g., the R1CS lowering, with `--r1cs-source-profile`).
+//!
+//! Tagging is off unless [enable]d. Tags do not keep terms alive.
+
+use super::*;
+
+use circ_hc::collections::cache::NodeCache;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// A source location, with the call stack that reached it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SrcLoc {
+    /// The source file
+    pub file: PathBuf,
+    /// The (1-indexed) line
+    pub line: usize,
+    /// The functions on the call stack, outermost first
+    pub stack: Vec<String>,
+}
+
+type LocTable = NodeCache<Op, hc::Table, Rc<SrcLoc>>;
+
+thread_local! {
+    static ENABLED: Cell<bool> = Cell::new(false);
+    static CURRENT: RefCell<Option<Rc<SrcLoc>>> = RefCell::new(None);
+    static LOCS: RefCell<LocTable> = RefCell::new(NodeCache::new());
+}
+
+/// Start tagging terms with source locations.
+pub fn enable() {
+    ENABLED.with(|e| e.set(true));
+}
+
+/// Are terms being tagged with source locations?
+pub fn is_enabled() -> bool {
+    ENABLED.with(|e| e.get())
+}
+
+/// Restores the previous location when dropped.
+#[must_use]
+pub struct LocGuard(Option<Rc<SrcLoc>>);
+
+impl Drop for LocGuard {
+    fn drop(&mut self) {
+        let prev = self.0.take();
+        CURRENT.with(|c| *c.borrow_mut() = prev);
+    }
+}
+
+/// Attribute new terms to `loc` until the guard is dropped.
+pub fn enter(loc: SrcLoc) -> LocGuard {
+    LocGuard(CURRENT.with(|c| c.borrow_mut().replace(Rc::new(loc))))
+}
+
+/// Called on every term creation.
+pub(super) fn created(t: &Term) {
+    if is_enabled() {
+        if let Some(loc) = CURRENT.with(|c| c.borrow().clone()) {
+            LOCS.with(|l| {
+                l.borrow_mut().entry(t.downgrade()).or_insert(loc);
+            });
+        }
+    }
+}
+
+/// The source location of `t`, if known.
+pub fn get(t: &Term) -> Option<Rc<SrcLoc>> {
+    if is_enabled() {
+        LOCS.with(|l| l.borrow().get(&t.downgrade()).cloned())
+    } else {
+        None
+    }
+}
+
+/// If `new` has no location, give it the location of `old`.
+///
+/// For passes that replace `old` by `new`.
+pub fn inherit(old: &Term, new: &Term) {
+    if is_enabled() && old != new {
+        if let Some(loc) = get(old) {
+            LOCS.with(|l| {
+                l.borrow_mut().entry(new.downgrade()).or_insert(loc);
+            });
+        }
+    }
+}
+
+/// Remove the locations of dead terms.
+pub(super) fn collect() {
+    LOCS.with(|l| l.borrow_mut().collect());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tag_and_inherit() {
+        enable();
+        let a = var("src_loc_a".into(), Sort::Bool);
+        let loc = SrcLoc {
+            file: "f.zok".into(),
+            line: 3,
+            stack: vec!["main".into()],
+        };
+        let t = {
+            let _g = enter(loc.clone());
+            term![NOT; a.clone()]
+        };
+        assert_eq!(get(&t).as_deref(), Some(&loc));
+        // created before entering
+        assert_eq!(get(&a), None);
+        // created after the guard was dropped
+        let u = term![AND; a.clone(), a.clone()];
+        assert_eq!(get(&u), None);
+        inherit(&t, &u);
+        assert_eq!(get(&u).as_deref(), Some(&loc));
+    }
+}
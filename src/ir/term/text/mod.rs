@@ -48,6 +48,7 @@
 //!     * boolean: `true`, `false`
 //!     * integer: `I`
 //!     * bit-vector: `#xFFFF...`, `#bBBBB...`
+//!       * or `(#bv N I)`: the N-bit two's complement representation of (possibly negative) I
 //!     * field literal: `#fDD` or `#fDDmDD`.
 //!       * In the former case, an ambient modulus must be set.
 //!     * tuple: `(#t V1 ... Vn)`
@@ -175,6 +176,7 @@ enum CtrlOp {
     ArrayValue,
     MapValue,
     ListValue,
+    BvValue,
     SetDefaultModulus,
 }
 
@@ -226,6 +228,7 @@ impl<'src> IrInterp<'src> {
             Leaf(Ident, b"#a") => Err(CtrlOp::ArrayValue),
             Leaf(Ident, b"#l") => Err(CtrlOp::ListValue),
             Leaf(Ident, b"#m") => Err(CtrlOp::MapValue),
+            Leaf(Ident, b"#bv") => Err(CtrlOp::BvValue),
             Leaf(Ident, b"set_default_modulus") => Err(CtrlOp::SetDefaultModulus),
             Leaf(Ident, b"ite") => Ok(Op::Ite),
             Leaf(Ident, b"=") => Ok(Op::Eq),
@@ -576,6 +579,20 @@ impl<'src> IrInterp<'src> {
                             vals,
                         )))
                     }
+                    Err(CtrlOp::BvValue) => {
+                        assert_eq!(tts.len(), 3, "A bit-vector should be (#bv N I)");
+                        let width = self.usize(&tts[1]);
+                        let i = self.int(&tts[2]);
+                        let bound = Integer::from(1) << width;
+                        assert!(
+                            *i < bound && Integer::from(-&*i) << 1 <= bound,
+                            "{i} does not fit in {width} bits"
+                        );
+                        const_(Value::BitVector(BitVector::new(
+                            Integer::from(&*i).keep_bits(width as u32),
+                            width,
+                        )))
+                    }
                     Err(CtrlOp::TupleValue) => const_(Value::Tuple(
                         tts[1..]
                             .iter()
@@ -1420,4 +1437,27 @@ mod test {
         let t2 = parse_term(s.as_bytes());
         assert_eq!(t, t2);
     }
+
+    #[test]
+    fn bv_value() {
+        let m = parse_value_map(b"(let ((a (#bv 8 -5)) (b (#bv 8 -128)) (c (#bv 4 9))) true)");
+        assert_eq!(
+            m["a"],
+            Value::BitVector(BitVector::new(Integer::from(0xfb), 8))
+        );
+        assert_eq!(
+            m["b"],
+            Value::BitVector(BitVector::new(Integer::from(0x80), 8))
+        );
+        assert_eq!(
+            m["c"],
+            Value::BitVector(BitVector::new(Integer::from(9), 4))
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn bv_value_too_small() {
+        parse_value_map(b"(let ((a (#bv 8 -129))) true)");
+    }
 }
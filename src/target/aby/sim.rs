@@ -0,0 +1,597 @@
+//! In-process simulator for ABY bytecode
+//!
+//! Runs the files written by [to_aby](super::trans::to_aby) (bytecode, share map and constants)
+//! for `n` simulated parties in one process, without the ABY framework.
+//!
+//! Every wire holds one share per party. Arithmetic shares are additive mod `2^32`; Boolean and
+//! Yao shares are XOR shares (a garbled circuit computes the same function, so Yao gates are not
+//! actually garbled). Gates that are linear in their share type are computed locally on the
+//! shares. Other gates are computed by an ideal functionality, which reconstructs the inputs and
+//! re-shares the output. A gate input with a different share type than the gate is converted first.
+//!
+//! Like the lowering, the simulator assumes that bit-vectors are 32 bits wide.
+
+use super::assignment::ShareType;
+use super::utils::get_path;
+use crate::ir::term::*;
+
+use fxhash::FxHashMap;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// One bytecode line: `n_in n_out in.. out.. OP`
+#[derive(Debug, Clone)]
+struct Gate {
+    ins: Vec<String>,
+    outs: Vec<String>,
+    op: String,
+}
+
+impl Gate {
+    fn parse(line: &str) -> Result<Self, String> {
+        let toks: Vec<&str> = line.split_whitespace().collect();
+        let bad = || format!("Bad ABY bytecode: {line}");
+        let n_in: usize = toks.first().and_then(|t| t.parse().ok()).ok_or_else(bad)?;
+        let n_out: usize = toks.get(1).and_then(|t| t.parse().ok()).ok_or_else(bad)?;
+        if toks.len() != n_in + n_out + 3 {
+            return Err(bad());
+        }
+        let strings = |ts: &[&str]| ts.iter().map(|t| t.to_string()).collect();
+        Ok(Gate {
+            ins: strings(&toks[2..2 + n_in]),
+            outs: strings(&toks[2 + n_in..2 + n_in + n_out]),
+            op: toks[toks.len() - 1].to_string(),
+        })
+    }
+
+    /// The `i`th input, as a wire
+    fn wire(&self, i: usize) -> Result<i32, String> {
+        self.int(&self.ins, i)
+    }
+
+    /// The `i`th input, as a literal
+    fn lit(&self, i: usize) -> Result<usize, String> {
+        self.int(&self.ins, i).map(|l| l as usize)
+    }
+
+    fn out(&self) -> Result<i32, String> {
+        self.int(&self.outs, 0)
+    }
+
+    fn int(&self, toks: &[String], i: usize) -> Result<i32, String> {
+        toks.get(i)
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| format!("Bad operand {} of {:?}", i, self))
+    }
+}
+
+/// ABY bytecode, as written by [to_aby](super::trans::to_aby)
+#[derive(Debug, Default)]
+pub struct Program {
+    /// Bytecode for each computation
+    comps: HashMap<String, Vec<Gate>>,
+    /// Constant definitions
+    consts: Vec<Gate>,
+    /// The share type of each wire
+    share_types: HashMap<i32, ShareType>,
+}
+
+fn parse_lines(text: &str) -> Result<Vec<Gate>, String> {
+    text.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(Gate::parse)
+        .collect()
+}
+
+impl Program {
+    /// Parse a program from the contents of its files: the bytecode of each computation, the
+    /// constants and the share map.
+    pub fn parse<'a>(
+        bytecode: impl IntoIterator<Item = (&'a str, &'a str)>,
+        consts: &str,
+        share_map: &str,
+    ) -> Result<Self, String> {
+        let comps = bytecode
+            .into_iter()
+            .map(|(name, text)| Ok((name.to_owned(), parse_lines(text)?)))
+            .collect::<Result<_, String>>()?;
+        let consts = parse_lines(consts)?;
+        let mut share_types = HashMap::new();
+        for line in share_map.lines().filter(|l| !l.trim().is_empty()) {
+            let bad = || format!("Bad ABY share map entry: {line}");
+            let (wire, ty) = line.trim().split_once(' ').ok_or_else(bad)?;
+            let ty = match ty {
+                "a" => ShareType::Arithmetic,
+                "b" => ShareType::Boolean,
+                "y" => ShareType::Yao,
+                _ => return Err(bad()),
+            };
+            share_types.insert(wire.parse().map_err(|_| bad())?, ty);
+        }
+        Ok(Program {
+            comps,
+            consts,
+            share_types,
+        })
+    }
+
+    /// Load the files that `to_aby(.., path, lang, ..)` wrote.
+    pub fn load(path: &Path, lang: &str) -> Result<Self, String> {
+        let read = |p: &str| fs::read_to_string(p).map_err(|e| format!("Could not read {p}: {e}"));
+        let const_path = get_path(path, lang, "const", false);
+        let consts = read(&const_path)?;
+        let share_map = read(&get_path(path, lang, "share_map", false))?;
+        // bytecode files are named like the constant file, with `{comp}_bytecode` for `const`
+        let const_path = Path::new(&const_path);
+        let prefix = const_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix("const.txt"))
+            .unwrap();
+        let dir = const_path.parent().unwrap();
+        let mut bytecode = Vec::new();
+        for entry in fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))? {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(comp) = file_name
+                .strip_prefix(prefix)
+                .and_then(|n| n.strip_suffix("_bytecode.txt"))
+            {
+                let text = read(&entry.path().to_string_lossy())?;
+                bytecode.push((comp.to_owned(), text));
+            }
+        }
+        Self::parse(
+            bytecode.iter().map(|(c, t)| (c.as_str(), t.as_str())),
+            &consts,
+            &share_map,
+        )
+    }
+}
+
+/// Statistics from a simulated execution
+#[derive(Debug, Default, Clone)]
+pub struct SimStats {
+    /// Gates computed locally on shares
+    pub local_gates: usize,
+    /// Gates computed by the ideal functionality
+    pub interactive_gates: usize,
+    /// Share conversions, by (from, to)
+    pub conversions: FxHashMap<(ShareType, ShareType), usize>,
+}
+
+/// A shared (flattened) value: one share vector per party
+#[derive(Debug, Clone)]
+struct Shared {
+    ty: ShareType,
+    shares: Vec<Vec<u32>>,
+}
+
+/// Simulates `parties` parties running a [Program].
+pub struct Simulator<'p> {
+    prog: &'p Program,
+    parties: usize,
+    rng: ChaCha20Rng,
+    consts: HashMap<i32, Shared>,
+    /// Statistics for the runs so far
+    pub stats: SimStats,
+}
+
+impl<'p> Simulator<'p> {
+    /// Create a simulator with `parties` parties. Randomness is seeded by `seed`.
+    pub fn new(prog: &'p Program, parties: usize, seed: u64) -> Result<Self, String> {
+        if parties < 2 {
+            return Err(format!("An MPC needs at least two parties, not {parties}"));
+        }
+        let mut sim = Simulator {
+            prog,
+            parties,
+            rng: ChaCha20Rng::seed_from_u64(seed),
+            consts: HashMap::new(),
+            stats: SimStats::default(),
+        };
+        for g in &prog.consts {
+            let out = g.out()?;
+            let ty = sim.ty(out)?;
+            let value = match g.op.as_str() {
+                // `2 1 value bitlen out CONS`
+                "CONS" => {
+                    let value: i64 = g.ins[0].parse().map_err(|_| format!("Bad const {g:?}"))?;
+                    vec![value as u32]
+                }
+                // `n 1 elem.. out CONS_ARRAY`
+                "CONS_ARRAY" | "CONS_TUPLE" => {
+                    let mut value = Vec::new();
+                    for i in 0..g.ins.len() {
+                        let elem = g.wire(i)?;
+                        let elem = sim
+                            .consts
+                            .get(&elem)
+                            .ok_or_else(|| format!("Constant {elem} is used before it is set"))?;
+                        value.extend(sim.reveal(elem));
+                    }
+                    value
+                }
+                op => return Err(format!("Unknown ABY constant op: {op}")),
+            };
+            let shared = sim.share(ty, &value);
+            sim.consts.insert(out, shared);
+        }
+        Ok(sim)
+    }
+
+    /// Run `main` on `inputs`, returning the (flattened) outputs.
+    pub fn run(&mut self, inputs: &FxHashMap<String, Value>) -> Result<Vec<Vec<u32>>, String> {
+        let outs = self.run_comp("main", Args::Inputs(inputs))?;
+        Ok(outs.iter().map(|o| self.reveal(o)).collect())
+    }
+
+    fn ty(&self, wire: i32) -> Result<ShareType, String> {
+        self.prog
+            .share_types
+            .get(&wire)
+            .copied()
+            .ok_or_else(|| format!("Wire {wire} is missing from the share map"))
+    }
+
+    fn get<'w>(&'w self, wires: &'w HashMap<i32, Shared>, wire: i32) -> Result<&'w Shared, String> {
+        wires
+            .get(&wire)
+            .or_else(|| self.consts.get(&wire))
+            .ok_or_else(|| format!("Wire {wire} is used before it is set"))
+    }
+
+    fn share(&mut self, ty: ShareType, value: &[u32]) -> Shared {
+        let mut shares: Vec<Vec<u32>> = (1..self.parties)
+            .map(|_| value.iter().map(|_| self.rng.gen()).collect())
+            .collect();
+        let last = value
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                shares.iter().fold(*v, |acc, s| match ty {
+                    ShareType::Arithmetic => acc.wrapping_sub(s[i]),
+                    ShareType::Boolean | ShareType::Yao => acc ^ s[i],
+                })
+            })
+            .collect();
+        shares.push(last);
+        Shared { ty, shares }
+    }
+
+    fn reveal(&self, s: &Shared) -> Vec<u32> {
+        let len = s.shares[0].len();
+        (0..len)
+            .map(|i| {
+                s.shares.iter().fold(0, |acc, sh| match s.ty {
+                    ShareType::Arithmetic => acc.wrapping_add(sh[i]),
+                    ShareType::Boolean | ShareType::Yao => acc ^ sh[i],
+                })
+            })
+            .collect()
+    }
+
+    fn convert(&mut self, s: Shared, ty: ShareType) -> Shared {
+        if s.ty == ty {
+            s
+        } else {
+            *self.stats.conversions.entry((s.ty, ty)).or_default() += 1;
+            let value = self.reveal(&s);
+            self.share(ty, &value)
+        }
+    }
+
+    fn run_comp(&mut self, name: &str, mut args: Args) -> Result<Vec<Shared>, String> {
+        let prog = self.prog;
+        let gates = prog
+            .comps
+            .get(name)
+            .ok_or_else(|| format!("No bytecode for computation {name}"))?;
+        let mut wires: HashMap<i32, Shared> = HashMap::new();
+        let mut outs = Vec::new();
+        for g in gates {
+            match g.op.as_str() {
+                // `3 1 name vis bitlen out IN`, `2 1 name vis out IN` or (unused) `1 0 name IN`
+                "IN" => {
+                    let arg = match &mut args {
+                        Args::Shares(shares) => Some(
+                            shares
+                                .next()
+                                .ok_or_else(|| format!("Too few arguments to {name}"))?,
+                        ),
+                        Args::Inputs(_) => None,
+                    };
+                    if g.outs.is_empty() {
+                        continue;
+                    }
+                    let out = g.out()?;
+                    let ty = self.ty(out)?;
+                    let shared = match (arg, &args) {
+                        (Some(arg), _) => self.convert(arg, ty),
+                        (None, Args::Inputs(inputs)) => {
+                            let value = inputs
+                                .get(&g.ins[0])
+                                .ok_or_else(|| format!("Missing input {}", g.ins[0]))?;
+                            self.share(ty, &flatten(value))
+                        }
+                        (None, Args::Shares(_)) => unreachable!(),
+                    };
+                    wires.insert(out, shared);
+                }
+                // `1 0 wire OUT`
+                "OUT" => outs.push(self.get(&wires, g.wire(0)?)?.clone()),
+                _ => {
+                    let out = g.out()?;
+                    let shared = self.gate(&wires, g)?;
+                    wires.insert(out, shared);
+                }
+            }
+        }
+        Ok(outs)
+    }
+
+    fn gate(&mut self, wires: &HashMap<i32, Shared>, g: &Gate) -> Result<Shared, String> {
+        let ty = self.ty(g.out()?)?;
+        let op = g.op.as_str();
+        if let Some(callee) = op.strip_prefix("CALL(").and_then(|o| o.strip_suffix(')')) {
+            let args = (0..g.ins.len())
+                .map(|i| Ok(self.get(wires, g.wire(i)?)?.clone()))
+                .collect::<Result<Vec<_>, String>>()?;
+            let mut shares = vec![Vec::new(); self.parties];
+            for o in self.run_comp(callee, Args::Shares(args.into_iter()))? {
+                let o = self.convert(o, ty);
+                for (s, o) in shares.iter_mut().zip(o.shares) {
+                    s.extend(o);
+                }
+            }
+            return Ok(Shared { ty, shares });
+        }
+        // which inputs are literals, rather than wires
+        let lits: &[usize] = match op {
+            "SHL" | "LSHR" | "FIELD" | "SELECT_CONS" | "STORE_CONS" | "UPDATE" => &[1],
+            "FIELD_VEC" => &[1, 2],
+            _ => &[],
+        };
+        let mut args = Vec::new();
+        for i in (0..g.ins.len()).filter(|i| !lits.contains(i)) {
+            let shared = self.get(wires, g.wire(i)?)?.clone();
+            args.push(self.convert(shared, ty));
+        }
+        let arith = ty == ShareType::Arithmetic;
+        let parties = self.parties;
+        let local = |f: &dyn Fn(usize, &[&[u32]]) -> Vec<u32>| {
+            let shares = (0..parties)
+                .map(|p| {
+                    let party_args: Vec<&[u32]> = args.iter().map(|a| &a.shares[p][..]).collect();
+                    f(p, &party_args)
+                })
+                .collect();
+            Shared { ty, shares }
+        };
+        let shared = match op {
+            "ADD" if arith => local(&|_, a| zip(a[0], a[1], u32::wrapping_add)),
+            "SUB" if arith => local(&|_, a| zip(a[0], a[1], u32::wrapping_sub)),
+            "XOR" if !arith => local(&|_, a| zip(a[0], a[1], |x, y| x ^ y)),
+            // one party flips the bit
+            "NOT" if !arith => local(&|p, a| vec![a[0][0] ^ (p == 0) as u32]),
+            "SHL" => {
+                let n = g.lit(1)? as u32;
+                local(&|_, a| vec![a[0][0].checked_shl(n).unwrap_or(0)])
+            }
+            "LSHR" if !arith => {
+                let n = g.lit(1)? as u32;
+                local(&|_, a| vec![a[0][0].checked_shr(n).unwrap_or(0)])
+            }
+            // rewiring
+            "FIELD" | "SELECT_CONS" => {
+                let i = g.lit(1)?;
+                local(&|_, a| vec![a[0][i]])
+            }
+            "FIELD_VEC" => {
+                let (offset, len) = (g.lit(1)?, g.lit(2)?);
+                local(&|_, a| a[0][offset..offset + len].to_vec())
+            }
+            "STORE_CONS" | "UPDATE" => {
+                let i = g.lit(1)?;
+                local(&|_, a| {
+                    let mut v = a[0].to_vec();
+                    v[i..i + a[1].len()].copy_from_slice(a[1]);
+                    v
+                })
+            }
+            "TUPLE" => local(&|_, a| a.concat()),
+            _ => {
+                let values: Vec<Vec<u32>> = args.iter().map(|a| self.reveal(a)).collect();
+                let value = ideal(op, &values, g.lit(1).ok())?;
+                self.stats.interactive_gates += 1;
+                return Ok(self.share(ty, &value));
+            }
+        };
+        self.stats.local_gates += 1;
+        Ok(shared)
+    }
+}
+
+/// The inputs to a computation: `main` reads named inputs, and called computations get shares.
+enum Args<'a> {
+    Inputs(&'a FxHashMap<String, Value>),
+    Shares(std::vec::IntoIter<Shared>),
+}
+
+fn zip(a: &[u32], b: &[u32], f: impl Fn(u32, u32) -> u32) -> Vec<u32> {
+    a.iter().zip(b).map(|(x, y)| f(*x, *y)).collect()
+}
+
+/// The plaintext semantics of gate `op`. `lit` is the literal operand, for operators that have one.
+fn ideal(op: &str, a: &[Vec<u32>], lit: Option<usize>) -> Result<Vec<u32>, String> {
+    let arity = match op {
+        "NOT" | "LSHR" => 1,
+        "MUX" | "STORE" => 3,
+        _ => 2,
+    };
+    if a.len() != arity {
+        return Err(format!("{op} expects {arity} operands, got {}", a.len()));
+    }
+    let pred = |p: bool| vec![p as u32];
+    Ok(match op {
+        "ADD" => zip(&a[0], &a[1], u32::wrapping_add),
+        "SUB" => zip(&a[0], &a[1], u32::wrapping_sub),
+        "MUL" => zip(&a[0], &a[1], u32::wrapping_mul),
+        "AND" => zip(&a[0], &a[1], |x, y| x & y),
+        "OR" => zip(&a[0], &a[1], |x, y| x | y),
+        "XOR" => zip(&a[0], &a[1], |x, y| x ^ y),
+        // division by zero follows the IR: all ones, and the dividend
+        "DIV" => zip(&a[0], &a[1], |x, y| x.checked_div(y).unwrap_or(u32::MAX)),
+        "REM" => zip(&a[0], &a[1], |x, y| x.checked_rem(y).unwrap_or(x)),
+        "GT" => pred(a[0][0] > a[1][0]),
+        "LT" => pred(a[0][0] < a[1][0]),
+        "GE" => pred(a[0][0] >= a[1][0]),
+        "LE" => pred(a[0][0] <= a[1][0]),
+        "EQ" => pred(a[0] == a[1]),
+        "NOT" => vec![a[0][0] ^ 1],
+        "LSHR" => {
+            let n = lit.ok_or("LSHR needs a shift amount")? as u32;
+            vec![a[0][0].checked_shr(n).unwrap_or(0)]
+        }
+        "MUX" => {
+            if a[0][0] != 0 {
+                a[1].clone()
+            } else {
+                a[2].clone()
+            }
+        }
+        "SELECT" => vec![*a[0]
+            .get(a[1][0] as usize)
+            .ok_or_else(|| format!("SELECT index {} out of bounds", a[1][0]))?],
+        "STORE" => {
+            let mut v = a[0].clone();
+            if let Some(e) = v.get_mut(a[1][0] as usize) {
+                *e = a[2][0];
+            }
+            v
+        }
+        _ => return Err(format!("Unknown ABY op: {op}")),
+    })
+}
+
+/// Flatten a value the way the ABY lowering lays it out.
+pub fn flatten(v: &Value) -> Vec<u32> {
+    match v {
+        Value::Bool(b) => vec![*b as u32],
+        Value::BitVector(bv) => vec![bv.uint().to_u32_wrapping()],
+        Value::Array(arr) => arr.values().iter().flat_map(flatten).collect(),
+        Value::Tuple(vs) => vs.iter().flat_map(flatten).collect(),
+        _ => panic!("Unsupported value in ABY: {}", v),
+    }
+}
+
+/// Simulate the ABY program that `to_aby(.., path, lang, ..)` wrote for `cs` with `parties`
+/// parties, and check that its outputs match [eval] of `cs`'s `main`.
+pub fn check_against_eval(
+    cs: &Computations,
+    path: &Path,
+    lang: &str,
+    inputs: &FxHashMap<String, Value>,
+    parties: usize,
+) -> Result<SimStats, String> {
+    let prog = Program::load(path, lang)?;
+    let mut sim = Simulator::new(&prog, parties, 0)?;
+    let actual = sim.run(inputs)?;
+    let main = cs.get("main");
+    let expected: Vec<Vec<u32>> = main
+        .outputs
+        .iter()
+        .map(|o| flatten(&eval(o, inputs)))
+        .collect();
+    if actual == expected {
+        Ok(sim.stats)
+    } else {
+        Err(format!(
+            "ABY simulation gave {actual:?}, but evaluation gave {expected:?}"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::target::aby::trans::to_aby;
+
+    fn inputs(pairs: &[(&str, u32)]) -> FxHashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(n, v)| {
+                (
+                    n.to_string(),
+                    Value::BitVector(BitVector::new((*v).into(), 32)),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mixed_sharing_and_calls() {
+        // main(a, b) = f(a * b) + 7, where f(x) = x >> 1; 7 is a constant
+        let prog = Program::parse(
+            [
+                (
+                    "main",
+                    "2 1 a 0 0 IN\n2 1 b 1 1 IN\n2 1 0 1 2 MUL\n1 1 2 3 CALL(f)\n2 1 3 4 5 ADD\n1 0 5 OUT\n",
+                ),
+                ("f", "2 1 x 0 6 IN\n2 1 6 1 7 LSHR\n1 0 7 OUT\n"),
+            ],
+            "2 1 7 32 4 CONS\n",
+            "0 a\n1 a\n2 a\n3 a\n4 a\n5 a\n6 b\n7 b\n",
+        )
+        .unwrap();
+        for parties in [2, 3, 5] {
+            let mut sim = Simulator::new(&prog, parties, 1).unwrap();
+            let out = sim.run(&inputs(&[("a", 6), ("b", 7)])).unwrap();
+            assert_eq!(out, vec![vec![6 * 7 / 2 + 7]]);
+            assert_eq!(sim.stats.interactive_gates, 1);
+            assert_eq!(sim.stats.local_gates, 2);
+            use ShareType::*;
+            assert_eq!(sim.stats.conversions.get(&(Arithmetic, Boolean)), Some(&1));
+            assert_eq!(sim.stats.conversions.get(&(Boolean, Arithmetic)), Some(&1));
+        }
+    }
+
+    #[test]
+    fn bad_bytecode() {
+        assert!(Program::parse([("main", "2 1 0 OUT\n")], "", "").is_err());
+        assert!(Program::parse(std::iter::empty(), "", "0 z\n").is_err());
+    }
+
+    #[test]
+    fn end_to_end() {
+        let c = text::parse_computation(
+            b"
+            (computation
+                (metadata
+                    (parties A B)
+                    (inputs (a (bv 32) (party 0)) (b (bv 32) (party 1)))
+                    (commitments)
+                )
+                (precompute () () (tuple))
+                (ite (bvult a b) (bvmul (bvsub b a) #x00000003) (bvudiv a b))
+            )",
+        );
+        let mut cs = Computations::new();
+        cs.comps.insert("main".into(), c);
+        let path = Path::new("aby_sim_test.zok");
+        for ss in ["b", "y", "a+b", "a+y"] {
+            let lang = format!("sim_{}", ss.replace('+', ""));
+            to_aby(cs.clone(), path, &lang, "hycc", ss);
+            for (a, b) in [(3, 10), (10, 3), (5, 0)] {
+                let stats = check_against_eval(&cs, path, &lang, &inputs(&[("a", a), ("b", b)]), 2)
+                    .unwrap();
+                assert!(stats.interactive_gates > 0);
+            }
+            let dir = get_path(path, &lang, "const", false);
+            fs::remove_dir_all(Path::new(&dir).parent().unwrap()).unwrap();
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! AIRs: algebraic intermediate representations for uniform computations
+//!
+//! An alternative to [crate::target::r1cs] for STARK provers (e.g., Winterfell). An [Air]
+//! describes an execution trace: a table with one row per step and a fixed set of columns. Each
+//! [Transition] constraint relates a row to the next one, and boundary constraints pin the state
+//! columns of the first and last steps to public values.
+//!
+//! This backend handles programs whose `main` iterates a fixed step function (in Z#, see
+//! `UniformLoop`). The step function is lowered to R1CS once; each R1CS
+//! constraint `a * b = c` becomes a transition constraint of degree at most two, in which the
+//! step function's parameter refers to the state columns of the current row, its return value
+//! refers to the state columns of the next row, and every other R1CS variable gets an auxiliary
+//! column. So, the trace width does not depend on the number of steps.
+//!
+//! The trace length is a power of two (and at least [MIN_TRACE_LEN]); rows past the last step
+//! keep applying the step function, so the transition constraints hold on every row.
+
+pub mod trans;
+
+use crate::ir::term::*;
+use crate::target::r1cs::{ProverData as R1csProverData, Var};
+
+use circ_fields::{FieldT, FieldV};
+use fxhash::FxHashMap as HashMap;
+use serde_json::{json, Value as Json};
+
+/// The shortest trace that Winterfell accepts
+pub const MIN_TRACE_LEN: usize = 8;
+
+/// Winterfell's fields: names and (decimal) moduli
+pub const WINTERFELL_FIELDS: [(&str, &str); 3] = [
+    ("f62", "4611624995532046337"),
+    ("f64", "18446744069414584321"),
+    ("f128", "340282366920938463463374557953744961537"),
+];
+
+/// A cell, relative to the row that a transition constraint is evaluated at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cell {
+    /// The column
+    pub column: usize,
+    /// Whether the cell is in the next row (rather than the current one)
+    pub next: bool,
+}
+
+impl Cell {
+    fn to_json(self) -> Json {
+        json!({ "column": self.column, "next": self.next })
+    }
+}
+
+/// An affine combination of cells
+#[derive(Debug, Clone)]
+pub struct Affine {
+    /// The constant term
+    pub constant: FieldV,
+    /// The coefficient of each cell
+    pub terms: Vec<(Cell, FieldV)>,
+}
+
+impl Affine {
+    /// Does this combination refer to any cells?
+    pub fn is_constant(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    fn eval(&self, cur: &[FieldV], next: &[FieldV]) -> FieldV {
+        let mut acc = self.constant.clone();
+        for (cell, coeff) in &self.terms {
+            let row = if cell.next { next } else { cur };
+            acc += &(row[cell.column].clone() * coeff);
+        }
+        acc
+    }
+
+    fn to_json(&self) -> Json {
+        json!({
+            "constant": self.constant.i().to_string(),
+            "terms": self
+                .terms
+                .iter()
+                .map(|(cell, coeff)| json!({ "cell": cell.to_json(), "coeff": coeff.i().to_string() }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// A transition constraint: `a * b - c = 0`
+#[derive(Debug, Clone)]
+pub struct Transition {
+    /// The left factor
+    pub a: Affine,
+    /// The right factor
+    pub b: Affine,
+    /// The product
+    pub c: Affine,
+}
+
+impl Transition {
+    /// The degree of this constraint, as a polynomial in the cells
+    pub fn degree(&self) -> usize {
+        if self.a.is_constant() || self.b.is_constant() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Evaluate this constraint over consecutive rows. The result is zero iff it holds.
+    pub fn eval(&self, cur: &[FieldV], next: &[FieldV]) -> FieldV {
+        self.a.eval(cur, next) * &self.b.eval(cur, next) - &self.c.eval(cur, next)
+    }
+}
+
+/// An AIR for a uniform loop
+#[derive(Debug, Clone)]
+pub struct Air {
+    field: FieldT,
+    /// Column names: the state columns (named after `main`'s loop state), then the auxiliary
+    /// columns (named after R1CS variables).
+    columns: Vec<String>,
+    n_state: usize,
+    transitions: Vec<Transition>,
+    steps: usize,
+}
+
+impl Air {
+    /// The field
+    pub fn field(&self) -> &FieldT {
+        &self.field
+    }
+
+    /// The column names
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// The number of columns
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// The number of state columns; these come first.
+    pub fn n_state(&self) -> usize {
+        self.n_state
+    }
+
+    /// The transition constraints
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /// The number of steps; the final state is in this row.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// The number of rows in the trace
+    pub fn trace_len(&self) -> usize {
+        (self.steps + 1).next_power_of_two().max(MIN_TRACE_LEN)
+    }
+
+    /// Check a trace against the transition constraints and the boundary constraints for the
+    /// given initial and final states.
+    pub fn check(&self, trace: &Trace, init: &[FieldV], result: &[FieldV]) -> Result<(), String> {
+        if trace.rows.len() != self.trace_len() {
+            return Err(format!(
+                "Trace has {} rows, expected {}",
+                trace.rows.len(),
+                self.trace_len()
+            ));
+        }
+        if let Some(i) = trace.rows.iter().position(|r| r.len() != self.width()) {
+            return Err(format!("Row {} does not have {} columns", i, self.width()));
+        }
+        for (step, values) in [(0, init), (self.steps, result)] {
+            for (j, v) in values.iter().enumerate() {
+                if &trace.rows[step][j] != v {
+                    return Err(format!(
+                        "Boundary constraint on {} at step {} does not hold",
+                        self.columns[j], step
+                    ));
+                }
+            }
+        }
+        for (i, rows) in trace.rows.windows(2).enumerate() {
+            for (k, t) in self.transitions.iter().enumerate() {
+                if !t.eval(&rows[0], &rows[1]).is_zero() {
+                    return Err(format!(
+                        "Transition constraint {} does not hold at step {}",
+                        k, i
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The name of the Winterfell field this AIR is over, if it is over one. Winterfell supports
+    /// no other fields; use `--field-custom-modulus` to pick one.
+    pub fn winterfell_field(&self) -> Option<&'static str> {
+        let modulus = self.field.modulus().to_string();
+        WINTERFELL_FIELDS
+            .iter()
+            .find(|(_, m)| *m == modulus)
+            .map(|(name, _)| *name)
+    }
+
+    /// A JSON description of this AIR, following Winterfell's `Air` trait.
+    ///
+    /// `winterfell_field` names the field (see [Air::winterfell_field]); it is null if Winterfell
+    /// does not support the field.
+    ///
+    /// Each transition constraint `a * b - c` (over the current and next rows) corresponds to an
+    /// entry of `evaluate_transition`'s result, with the listed degree. The `init` and `result`
+    /// public inputs are asserted at the listed steps of the state columns.
+    pub fn to_json(&self) -> Json {
+        let boundary = |name: &str, step: usize| -> Vec<Json> {
+            (0..self.n_state)
+                .map(|j| json!({ "column": j, "step": step, "public_input": [name, j] }))
+                .collect()
+        };
+        json!({
+            "field": self.field.modulus().to_string(),
+            "winterfell_field": self.winterfell_field(),
+            "trace_width": self.width(),
+            "trace_length": self.trace_len(),
+            "steps": self.steps,
+            "columns": self.columns,
+            "public_inputs": {
+                "init": self.columns[..self.n_state],
+                "result": self.columns[..self.n_state],
+            },
+            "transition_degrees": self.transitions.iter().map(|t| t.degree()).collect::<Vec<_>>(),
+            "transitions": self
+                .transitions
+                .iter()
+                .map(|t| json!({ "a": t.a.to_json(), "b": t.b.to_json(), "c": t.c.to_json() }))
+                .collect::<Vec<_>>(),
+            "assertions": boundary("init", 0)
+                .into_iter()
+                .chain(boundary("result", self.steps))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// An execution trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    /// The rows
+    pub rows: Vec<Vec<FieldV>>,
+}
+
+impl Trace {
+    /// The state columns of a row
+    pub fn state<'a>(&'a self, air: &Air, row: usize) -> &'a [FieldV] {
+        &self.rows[row][..air.n_state]
+    }
+
+    /// The trace in column-major order (like Winterfell's `TraceTable::init`), as decimal strings.
+    pub fn to_json(&self) -> Json {
+        let width = self.rows.first().map_or(0, |r| r.len());
+        let columns: Vec<Vec<String>> = (0..width)
+            .map(|j| self.rows.iter().map(|r| r[j].i().to_string()).collect())
+            .collect();
+        json!({ "columns": columns })
+    }
+}
+
+/// An [Air], with what's needed to generate traces for it
+#[derive(Debug)]
+pub struct ProverData {
+    /// The AIR
+    pub air: Air,
+    /// The step function, as R1CS
+    r1cs: R1csProverData,
+    /// For each state column, the step function's input
+    inputs: Vec<(String, Sort)>,
+    /// For each state column, the R1CS variable for the step function's return
+    next: Vec<Var>,
+    /// The R1CS variables for the auxiliary columns
+    aux: Vec<Var>,
+}
+
+impl ProverData {
+    /// Generate the trace that starts from the given state, keyed by state column name.
+    pub fn trace(&self, init: &HashMap<String, Value>) -> Result<Trace, String> {
+        let mut state: Vec<Value> = self.air.columns[..self.air.n_state]
+            .iter()
+            .map(|name| {
+                init.get(name)
+                    .cloned()
+                    .ok_or_else(|| format!("Missing initial value for {name}"))
+            })
+            .collect::<Result<_, _>>()?;
+        let mut rows = Vec::with_capacity(self.air.trace_len());
+        for _ in 0..self.air.trace_len() {
+            let inputs: HashMap<String, Value> = self
+                .inputs
+                .iter()
+                .map(|(name, _)| name.clone())
+                .zip(state.iter().cloned())
+                .collect();
+            let values = self.r1cs.extend_r1cs_witness(&inputs);
+            let mut row: Vec<FieldV> = state
+                .iter()
+                .map(|v| self.encode(v))
+                .collect::<Result<_, _>>()?;
+            row.extend(self.aux.iter().map(|v| values[v].clone()));
+            rows.push(row);
+            state = self
+                .next
+                .iter()
+                .zip(&self.inputs)
+                .map(|(v, (_, sort))| decode(&values[v], sort))
+                .collect();
+        }
+        Ok(Trace { rows })
+    }
+
+    fn encode(&self, v: &Value) -> Result<FieldV, String> {
+        match v {
+            Value::Field(f) => Ok(f.clone()),
+            Value::Bool(b) => Ok(self.air.field.new_v(*b as u8)),
+            Value::BitVector(bv) => Ok(self.air.field.new_v(bv.uint())),
+            v => Err(format!("Cannot put {v:?} in a trace cell")),
+        }
+    }
+}
+
+fn decode(v: &FieldV, sort: &Sort) -> Value {
+    match sort {
+        Sort::Field(_) => Value::Field(v.clone()),
+        Sort::Bool => Value::Bool(v.is_one()),
+        Sort::BitVector(w) => Value::BitVector(BitVector::new(v.i(), *w)),
+        s => unreachable!("state of sort {}", s),
+    }
+}
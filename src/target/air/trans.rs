@@ -0,0 +1,231 @@
+//! Lowering a uniform loop's step function to an AIR
+//!
+//! The step function is lowered with [to_r1cs]; see the [parent module](super) for how R1CS
+//! variables become cells.
+
+use super::*;
+use crate::cfg::CircCfg;
+use crate::target::r1cs::trans::to_r1cs;
+use crate::target::r1cs::{Lc, VarType};
+
+/// Lower `cs`, a compiled step function, to an AIR for `steps` iterations of it.
+///
+/// The step function's input `param` (and each input `param.<suffix>`) is part of the current
+/// state; `return` (and each `return.<suffix>`) is the matching part of the next state. The state
+/// columns are named `state` (and `state.<suffix>`).
+///
+/// Fails if the step function's R1CS does not determine some part of the next state, or has
+/// variables other than the state and (final) witnesses.
+pub fn to_air(
+    cs: &Computation,
+    param: &str,
+    state: &str,
+    steps: usize,
+    cfg: &CircCfg,
+) -> Result<ProverData, String> {
+    let (r1cs, _) = to_r1cs(cs, cfg).finalize(cs);
+    let field = cfg.field().clone();
+
+    // The step function's inputs, one per state column. The `return` inputs have the same suffixes.
+    let inputs: Vec<(String, Sort)> = cs
+        .metadata
+        .ordered_input_names()
+        .into_iter()
+        .filter(|name| {
+            name.strip_prefix(param)
+                .map_or(false, |s| s.is_empty() || s.starts_with('.'))
+        })
+        .map(|name| {
+            let sort = cs.metadata.input_sort(&name);
+            (name, sort)
+        })
+        .collect();
+    let vars_by_name: HashMap<&str, Var> = r1cs
+        .r1cs
+        .names
+        .iter()
+        .map(|(v, name)| (name.as_str(), *v))
+        .collect();
+
+    let mut columns = Vec::new();
+    let mut cells: HashMap<Var, Cell> = HashMap::default();
+    let mut next = Vec::new();
+    for (j, (name, _)) in inputs.iter().enumerate() {
+        let suffix = &name[param.len()..];
+        columns.push(format!("{state}{suffix}"));
+        if let Some(v) = vars_by_name.get(name.as_str()) {
+            cells.insert(
+                *v,
+                Cell {
+                    column: j,
+                    next: false,
+                },
+            );
+        }
+        let ret = format!("return{suffix}");
+        let v = *vars_by_name
+            .get(ret.as_str())
+            .ok_or_else(|| format!("The step function does not determine {ret}"))?;
+        cells.insert(
+            v,
+            Cell {
+                column: j,
+                next: true,
+            },
+        );
+        next.push(v);
+    }
+    let n_state = columns.len();
+    let mut aux = Vec::new();
+    for v in &r1cs.r1cs.vars {
+        if cells.contains_key(v) {
+            continue;
+        }
+        match v.ty() {
+            VarType::FinalWit => {
+                cells.insert(
+                    *v,
+                    Cell {
+                        column: columns.len(),
+                        next: false,
+                    },
+                );
+                columns.push(r1cs.r1cs.names[v].clone());
+                aux.push(*v);
+            }
+            ty => {
+                return Err(format!(
+                    "AIR export does not support {:?} variable {}",
+                    ty, r1cs.r1cs.names[v]
+                ))
+            }
+        }
+    }
+
+    let affine = |lc: &Lc| {
+        let mut terms: Vec<(Cell, FieldV)> = lc
+            .monomials
+            .iter()
+            .map(|(v, coeff)| (cells[v], coeff.clone()))
+            .collect();
+        terms.sort_by_key(|t| t.0);
+        Affine {
+            constant: lc.constant.clone(),
+            terms,
+        }
+    };
+    let transitions = r1cs
+        .r1cs
+        .constraints
+        .iter()
+        .map(|(a, b, c)| Transition {
+            a: affine(a),
+            b: affine(b),
+            c: affine(c),
+        })
+        .collect();
+
+    Ok(ProverData {
+        air: Air {
+            field,
+            columns,
+            n_state,
+            transitions,
+            steps,
+        },
+        r1cs,
+        inputs,
+        next,
+        aux,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ir::proof::Constraints;
+
+    /// `(x, y) -> (x^3 + 7, x + y)`
+    fn step(field: &FieldT) -> Computation {
+        let v = |n: &str| var(n.to_owned(), Sort::Field(field.clone()));
+        let next_x =
+            term![PF_ADD; term![PF_MUL; v("s.0"), v("s.0"), v("s.0")], pf_lit(field.new_v(7))];
+        let next_y = term![PF_ADD; v("s.0"), v("s.1")];
+        let assertion = term![AND;
+            term![EQ; v("return.0"), next_x.clone()],
+            term![EQ; v("return.1"), next_y.clone()]
+        ];
+        let mut cs = Computation::from_constraint_system_parts(
+            vec![assertion],
+            vec![v("s.0"), v("s.1"), v("return.0"), v("return.1")],
+        );
+        cs.precomputes.add_output("return.0".into(), next_x);
+        cs.precomputes.add_output("return.1".into(), next_y);
+        cs
+    }
+
+    #[test]
+    fn hash_chain() {
+        let cfg = CircCfg::default();
+        let field = cfg.field().clone();
+        let steps = 9;
+        let pd = to_air(&step(&field), "s", "h", steps, &cfg).unwrap();
+        let air = &pd.air;
+        assert_eq!(air.n_state(), 2);
+        assert_eq!(&air.columns()[..2], &["h.0".to_owned(), "h.1".to_owned()]);
+        assert_eq!(air.trace_len(), 16);
+        assert_eq!(air.winterfell_field(), None);
+        assert!(air.to_json()["winterfell_field"].is_null());
+        assert!(air.transitions().iter().all(|t| t.degree() <= 2));
+
+        let init: HashMap<String, Value> = vec![
+            ("h.0".to_owned(), Value::Field(field.new_v(2))),
+            ("h.1".to_owned(), Value::Field(field.new_v(0))),
+        ]
+        .into_iter()
+        .collect();
+        let mut trace = pd.trace(&init).unwrap();
+        assert_eq!(trace.state(air, 1), &[field.new_v(15), field.new_v(2)]);
+        let (mut x, mut y) = (field.new_v(2), field.new_v(0));
+        for _ in 0..steps {
+            let x3 = x.clone() * &x * &x;
+            y += &x;
+            x = x3 + &field.new_v(7);
+        }
+        let init_v = trace.state(air, 0).to_vec();
+        let result = vec![x, y];
+        assert_eq!(trace.state(air, steps), &result[..]);
+        air.check(&trace, &init_v, &result).unwrap();
+
+        let wrong = vec![result[0].clone(), result[1].clone() + &field.new_v(1)];
+        assert!(air.check(&trace, &init_v, &wrong).is_err());
+        trace.rows[3][1] += &field.new_v(1);
+        assert!(air.check(&trace, &init_v, &result).is_err());
+    }
+
+    #[test]
+    fn undetermined_state() {
+        let cfg = CircCfg::default();
+        let field = cfg.field().clone();
+        let v = |n: &str| var(n.to_owned(), Sort::Field(field.clone()));
+        // `return.1` is not an input of the step function
+        let mut cs = Computation::from_constraint_system_parts(
+            vec![term![EQ; v("return.0"), v("s.1")]],
+            vec![v("s.0"), v("s.1"), v("return.0")],
+        );
+        cs.precomputes.add_output("return.0".into(), v("s.1"));
+        let e = to_air(&cs, "s", "h", 4, &cfg).unwrap_err();
+        assert!(e.contains("does not determine return.1"), "{}", e);
+    }
+
+    #[test]
+    fn winterfell_fields() {
+        for (name, modulus) in WINTERFELL_FIELDS {
+            let mut opt = crate::cfg::CircOpt::default();
+            opt.field.custom_modulus = modulus.into();
+            let cfg = CircCfg::from(opt);
+            let pd = to_air(&step(cfg.field()), "s", "h", 4, &cfg).unwrap();
+            assert_eq!(pd.air.winterfell_field(), Some(name));
+        }
+    }
+}
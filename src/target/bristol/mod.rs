@@ -0,0 +1,112 @@
+//! Bristol Fashion boolean circuits
+//!
+//! The [format](https://nigelsmart.github.io/MPC-Circuits/) used by emp-toolkit, MP-SPDZ and
+//! other garbled-circuit tools: a header with the gate and wire counts and the bit-widths of the
+//! input and output values, then one gate per line. Input wires come first and output wires come
+//! last. Within a value, bits are numbered least-significant first.
+
+pub mod trans;
+
+use std::fmt::{self, Display, Formatter};
+
+/// A wire number
+pub type Wire = usize;
+
+/// A Bristol Fashion gate. The output wire is last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gate {
+    /// Conjunction
+    And(Wire, Wire, Wire),
+    /// Exclusive or
+    Xor(Wire, Wire, Wire),
+    /// Negation
+    Inv(Wire, Wire),
+    /// Copy a wire
+    Eqw(Wire, Wire),
+    /// Set a wire to a constant
+    Eq(bool, Wire),
+}
+
+/// A boolean circuit
+#[derive(Debug, Clone, Default)]
+pub struct Circuit {
+    /// The number of wires
+    pub n_wires: usize,
+    /// The width of each input value
+    pub inputs: Vec<usize>,
+    /// The width of each output value
+    pub outputs: Vec<usize>,
+    /// The gates, in topological order
+    pub gates: Vec<Gate>,
+}
+
+impl Circuit {
+    /// The number of AND gates: the cost of garbling the circuit.
+    pub fn n_and(&self) -> usize {
+        self.gates
+            .iter()
+            .filter(|g| matches!(g, Gate::And(..)))
+            .count()
+    }
+
+    /// Evaluate the circuit on input values (as bits, least-significant first).
+    pub fn eval(&self, inputs: &[Vec<bool>]) -> Vec<Vec<bool>> {
+        assert_eq!(inputs.len(), self.inputs.len(), "wrong number of inputs");
+        let mut wires: Vec<Option<bool>> = vec![None; self.n_wires];
+        let mut next = 0;
+        for (value, width) in inputs.iter().zip(&self.inputs) {
+            assert_eq!(value.len(), *width, "wrong input width");
+            for bit in value {
+                wires[next] = Some(*bit);
+                next += 1;
+            }
+        }
+        let get = |wires: &[Option<bool>], w: Wire| wires[w].expect("wire used before it is set");
+        for g in &self.gates {
+            let (out, v) = match *g {
+                Gate::And(a, b, o) => (o, get(&wires, a) & get(&wires, b)),
+                Gate::Xor(a, b, o) => (o, get(&wires, a) ^ get(&wires, b)),
+                Gate::Inv(a, o) => (o, !get(&wires, a)),
+                Gate::Eqw(a, o) => (o, get(&wires, a)),
+                Gate::Eq(c, o) => (o, c),
+            };
+            wires[out] = Some(v);
+        }
+        let mut next = self.n_wires - self.outputs.iter().sum::<usize>();
+        self.outputs
+            .iter()
+            .map(|width| {
+                let value = (next..next + width).map(|w| get(&wires, w)).collect();
+                next += width;
+                value
+            })
+            .collect()
+    }
+}
+
+fn write_widths(f: &mut Formatter<'_>, widths: &[usize]) -> fmt::Result {
+    write!(f, "{}", widths.len())?;
+    for w in widths {
+        write!(f, " {w}")?;
+    }
+    writeln!(f)
+}
+
+impl Display for Circuit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", self.gates.len(), self.n_wires)?;
+        write_widths(f, &self.inputs)?;
+        write_widths(f, &self.outputs)?;
+        writeln!(f)?;
+        for g in &self.gates {
+            match g {
+                Gate::And(a, b, o) => writeln!(f, "2 1 {a} {b} {o} AND")?,
+                Gate::Xor(a, b, o) => writeln!(f, "2 1 {a} {b} {o} XOR")?,
+                Gate::Inv(a, o) => writeln!(f, "1 1 {a} {o} INV")?,
+                Gate::Eqw(a, o) => writeln!(f, "1 1 {a} {o} EQW")?,
+                Gate::Eq(c, o) => writeln!(f, "1 1 {} {o} EQ", *c as u8)?,
+            }
+        }
+        Ok(())
+    }
+}
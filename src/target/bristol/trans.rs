@@ -0,0 +1,644 @@
+//! Lowering IR to Bristol Fashion
+//!
+//! Bit-vectors are bit-blasted. Since XOR and INV gates are free when garbling, the lowering
+//! minimizes AND gates:
+//! * OR, if-then-else and majority take one AND each: `a | b = a ^ b ^ ab`,
+//!   `ite(s, a, b) = b ^ s(a ^ b)` and `maj(a, b, c) = c ^ (a ^ c)(b ^ c)`
+//! * adders, subtractors and comparators compute carries with majority: one AND per bit
+//! * constants are propagated, gates are hash-consed, and dead gates are removed
+//!
+//! The computation must be free of tuples, arrays and fields (e.g., after `Opt::Tuple`,
+//! `Opt::Obliv` and `Opt::Binarize`).
+
+use super::{Circuit, Gate, Wire};
+use crate::ir::term::*;
+
+use fxhash::{FxHashMap as HashMap, FxHashSet as HashSet};
+use log::debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Bit {
+    Const(bool),
+    Wire(Wire),
+}
+
+use Bit::Const;
+
+/// Builds a circuit, simplifying as it goes.
+#[derive(Default)]
+struct Builder {
+    next_wire: Wire,
+    gates: Vec<Gate>,
+    ands: HashMap<(Wire, Wire), Wire>,
+    xors: HashMap<(Wire, Wire), Wire>,
+    /// Negations, in both directions
+    invs: HashMap<Wire, Wire>,
+}
+
+impl Builder {
+    fn fresh(&mut self) -> Wire {
+        let w = self.next_wire;
+        self.next_wire += 1;
+        w
+    }
+
+    fn not(&mut self, a: Bit) -> Bit {
+        match a {
+            Const(c) => Const(!c),
+            Bit::Wire(a) => Bit::Wire(match self.invs.get(&a) {
+                Some(o) => *o,
+                None => {
+                    let o = self.fresh();
+                    self.gates.push(Gate::Inv(a, o));
+                    self.invs.insert(a, o);
+                    self.invs.insert(o, a);
+                    o
+                }
+            }),
+        }
+    }
+
+    /// A hash-consed binary gate
+    fn binary(&mut self, is_and: bool, a: Wire, b: Wire) -> Bit {
+        let key = (a.min(b), a.max(b));
+        let table = if is_and { &self.ands } else { &self.xors };
+        if let Some(o) = table.get(&key) {
+            return Bit::Wire(*o);
+        }
+        let o = self.fresh();
+        if is_and {
+            self.gates.push(Gate::And(key.0, key.1, o));
+            self.ands.insert(key, o);
+        } else {
+            self.gates.push(Gate::Xor(key.0, key.1, o));
+            self.xors.insert(key, o);
+        }
+        Bit::Wire(o)
+    }
+
+    fn and(&mut self, a: Bit, b: Bit) -> Bit {
+        match (a, b) {
+            (Const(false), _) | (_, Const(false)) => Const(false),
+            (Const(true), x) | (x, Const(true)) => x,
+            (Bit::Wire(a), Bit::Wire(b)) if a == b => Bit::Wire(a),
+            (Bit::Wire(a), Bit::Wire(b)) if self.invs.get(&a) == Some(&b) => Const(false),
+            (Bit::Wire(a), Bit::Wire(b)) => self.binary(true, a, b),
+        }
+    }
+
+    fn xor(&mut self, a: Bit, b: Bit) -> Bit {
+        match (a, b) {
+            (Const(false), x) | (x, Const(false)) => x,
+            (Const(true), x) | (x, Const(true)) => self.not(x),
+            (Bit::Wire(a), Bit::Wire(b)) if a == b => Const(false),
+            (Bit::Wire(a), Bit::Wire(b)) if self.invs.get(&a) == Some(&b) => Const(true),
+            (Bit::Wire(a), Bit::Wire(b)) => self.binary(false, a, b),
+        }
+    }
+
+    fn or(&mut self, a: Bit, b: Bit) -> Bit {
+        let x = self.xor(a, b);
+        let n = self.and(a, b);
+        self.xor(x, n)
+    }
+
+    /// `if s { a } else { b }`
+    fn mux(&mut self, s: Bit, a: Bit, b: Bit) -> Bit {
+        let d = self.xor(a, b);
+        let d = self.and(s, d);
+        self.xor(b, d)
+    }
+
+    fn maj(&mut self, a: Bit, b: Bit, c: Bit) -> Bit {
+        let ac = self.xor(a, c);
+        let bc = self.xor(b, c);
+        let n = self.and(ac, bc);
+        self.xor(c, n)
+    }
+
+    fn not_bv(&mut self, a: &[Bit]) -> Vec<Bit> {
+        a.iter().map(|x| self.not(*x)).collect()
+    }
+
+    fn zip(&mut self, a: &[Bit], b: &[Bit], f: fn(&mut Self, Bit, Bit) -> Bit) -> Vec<Bit> {
+        assert_eq!(a.len(), b.len());
+        a.iter().zip(b).map(|(x, y)| f(self, *x, *y)).collect()
+    }
+
+    fn mux_bv(&mut self, s: Bit, a: &[Bit], b: &[Bit]) -> Vec<Bit> {
+        assert_eq!(a.len(), b.len());
+        a.iter().zip(b).map(|(x, y)| self.mux(s, *x, *y)).collect()
+    }
+
+    fn or_all(&mut self, a: &[Bit]) -> Bit {
+        a.iter().fold(Const(false), |acc, x| self.or(acc, *x))
+    }
+
+    /// `a + b + c`, and the carry out if `carry_out` is set
+    fn add_carry(&mut self, a: &[Bit], b: &[Bit], mut c: Bit, carry_out: bool) -> (Vec<Bit>, Bit) {
+        assert_eq!(a.len(), b.len());
+        let mut sum = Vec::with_capacity(a.len());
+        for i in 0..a.len() {
+            let x = self.xor(a[i], b[i]);
+            sum.push(self.xor(x, c));
+            if carry_out || i + 1 < a.len() {
+                c = self.maj(a[i], b[i], c);
+            }
+        }
+        (sum, c)
+    }
+
+    fn add(&mut self, a: &[Bit], b: &[Bit]) -> Vec<Bit> {
+        self.add_carry(a, b, Const(false), false).0
+    }
+
+    fn sub(&mut self, a: &[Bit], b: &[Bit]) -> Vec<Bit> {
+        let nb = self.not_bv(b);
+        self.add_carry(a, &nb, Const(true), false).0
+    }
+
+    /// Unsigned `a >= b`: the carry out of `a - b`
+    fn uge(&mut self, a: &[Bit], b: &[Bit]) -> Bit {
+        assert_eq!(a.len(), b.len());
+        let mut c = Const(true);
+        for (x, y) in a.iter().zip(b) {
+            let ny = self.not(*y);
+            c = self.maj(*x, ny, c);
+        }
+        c
+    }
+
+    fn eq(&mut self, a: &[Bit], b: &[Bit]) -> Bit {
+        let diffs = self.zip(a, b, Self::xor);
+        let any = self.or_all(&diffs);
+        self.not(any)
+    }
+
+    fn mul(&mut self, a: &[Bit], b: &[Bit]) -> Vec<Bit> {
+        let n = a.len();
+        let mut acc = vec![Const(false); n];
+        for i in 0..n {
+            let partial: Vec<Bit> = (0..n)
+                .map(|j| {
+                    if j < i {
+                        Const(false)
+                    } else {
+                        self.and(a[j - i], b[i])
+                    }
+                })
+                .collect();
+            acc = self.add(&acc, &partial);
+        }
+        acc
+    }
+
+    /// Restoring division: quotient and remainder. Division by zero gives all ones and `a`.
+    fn udivrem(&mut self, a: &[Bit], b: &[Bit]) -> (Vec<Bit>, Vec<Bit>) {
+        let n = a.len();
+        let mut b_ext = b.to_vec();
+        b_ext.push(Const(false));
+        let mut q = vec![Const(false); n];
+        let mut r = vec![Const(false); n + 1];
+        for i in (0..n).rev() {
+            // r = 2r + a_i; r < b before the shift, so the top bit can be dropped
+            r.pop();
+            r.insert(0, a[i]);
+            let ge = self.uge(&r, &b_ext);
+            let d = self.sub(&r, &b_ext);
+            r = self.mux_bv(ge, &d, &r);
+            q[i] = ge;
+        }
+        r.pop();
+        (q, r)
+    }
+
+    /// Shift by a constant. Left if `left`, and right (filling with `fill`) otherwise.
+    fn shift_const(a: &[Bit], k: usize, left: bool, fill: Bit) -> Vec<Bit> {
+        let n = a.len();
+        (0..n)
+            .map(|i| match left {
+                true if i >= k => a[i - k],
+                true => Const(false),
+                false if i + k < n => a[i + k],
+                false => fill,
+            })
+            .collect()
+    }
+
+    /// A barrel shifter
+    fn shift(&mut self, a: &[Bit], s: &[Bit], left: bool, fill: Bit) -> Vec<Bit> {
+        let n = a.len();
+        let mut cur = a.to_vec();
+        let mut too_far = Vec::new();
+        for (k, s_k) in s.iter().enumerate() {
+            if k < usize::BITS as usize && (1usize << k) < n {
+                let shifted = Self::shift_const(&cur, 1 << k, left, fill);
+                cur = self.mux_bv(*s_k, &shifted, &cur);
+            } else {
+                too_far.push(*s_k);
+            }
+        }
+        let too_far = self.or_all(&too_far);
+        let filled = vec![if left { Const(false) } else { fill }; n];
+        self.mux_bv(too_far, &filled, &cur)
+    }
+}
+
+struct ToBristol {
+    b: Builder,
+    cache: TermMap<Vec<Bit>>,
+    inputs: HashMap<String, Vec<Bit>>,
+}
+
+fn width(s: &Sort) -> usize {
+    match s {
+        Sort::Bool => 1,
+        Sort::BitVector(w) => *w,
+        _ => panic!("Unsupported sort in bristol: {}", s),
+    }
+}
+
+impl ToBristol {
+    fn get(&self, t: &Term) -> &[Bit] {
+        self.cache
+            .get(t)
+            .unwrap_or_else(|| panic!("Missing term in bristol: {}", t))
+    }
+
+    fn get_bit(&self, t: &Term) -> Bit {
+        let bits = self.get(t);
+        assert_eq!(bits.len(), 1);
+        bits[0]
+    }
+
+    fn embed(&mut self, t: Term) {
+        for c in PostOrderIter::new(t) {
+            if !self.cache.contains_key(&c) {
+                let bits = self.lower(&c);
+                debug_assert_eq!(bits.len(), width(&check(&c)));
+                self.cache.insert(c, bits);
+            }
+        }
+    }
+
+    /// Lower `t`, whose arguments have been lowered.
+    fn lower(&mut self, t: &Term) -> Vec<Bit> {
+        let cs = t.cs();
+        let args: Vec<Vec<Bit>> = cs.iter().map(|c| self.get(c).to_vec()).collect();
+        let bit = |i: usize| {
+            assert_eq!(args[i].len(), 1);
+            args[i][0]
+        };
+        let b = &mut self.b;
+        match t.op() {
+            Op::Var(v) => self
+                .inputs
+                .get(&v.name)
+                .unwrap_or_else(|| panic!("Variable {} is not an input", v.name))
+                .clone(),
+            Op::Const(v) => match &**v {
+                Value::Bool(c) => vec![Const(*c)],
+                Value::BitVector(bv) => (0..bv.width())
+                    .map(|i| Const(bv.uint().get_bit(i as u32)))
+                    .collect(),
+                _ => panic!("Unsupported constant in bristol: {}", t),
+            },
+            Op::Ite => {
+                let s = bit(0);
+                b.mux_bv(s, &args[1], &args[2])
+            }
+            Op::Eq => vec![b.eq(&args[0], &args[1])],
+            Op::Not => vec![b.not(bit(0))],
+            Op::Implies => {
+                let na = b.not(bit(0));
+                vec![b.or(na, bit(1))]
+            }
+            Op::BoolMaj => vec![b.maj(bit(0), bit(1), bit(2))],
+            Op::BoolNaryOp(o) => {
+                let (f, id): (fn(&mut Builder, Bit, Bit) -> Bit, bool) = match o {
+                    BoolNaryOp::And => (Builder::and, true),
+                    BoolNaryOp::Or => (Builder::or, false),
+                    BoolNaryOp::Xor => (Builder::xor, false),
+                };
+                vec![args.iter().fold(Const(id), |acc, a| f(b, acc, a[0]))]
+            }
+            Op::BvNaryOp(o) => {
+                let f: fn(&mut Builder, &[Bit], &[Bit]) -> Vec<Bit> = match o {
+                    BvNaryOp::Add => Builder::add,
+                    BvNaryOp::Mul => Builder::mul,
+                    BvNaryOp::And => |b, x, y| b.zip(x, y, Builder::and),
+                    BvNaryOp::Or => |b, x, y| b.zip(x, y, Builder::or),
+                    BvNaryOp::Xor => |b, x, y| b.zip(x, y, Builder::xor),
+                };
+                let mut acc = args[0].clone();
+                for a in &args[1..] {
+                    acc = f(b, &acc, a);
+                }
+                acc
+            }
+            Op::BvUnOp(BvUnOp::Not) => b.not_bv(&args[0]),
+            Op::BvUnOp(BvUnOp::Neg) => {
+                let zero = vec![Const(false); args[0].len()];
+                b.sub(&zero, &args[0])
+            }
+            Op::BvBinOp(o) => {
+                let (x, y) = (&args[0], &args[1]);
+                let msb = *x.last().unwrap();
+                match o {
+                    BvBinOp::Sub => b.sub(x, y),
+                    BvBinOp::Udiv => b.udivrem(x, y).0,
+                    BvBinOp::Urem => b.udivrem(x, y).1,
+                    BvBinOp::Shl => b.shift(x, y, true, Const(false)),
+                    BvBinOp::Lshr => b.shift(x, y, false, Const(false)),
+                    BvBinOp::Ashr => b.shift(x, y, false, msb),
+                }
+            }
+            Op::BvBinPred(p) => {
+                let (mut x, mut y) = (args[0].clone(), args[1].clone());
+                // signed comparisons are unsigned comparisons with flipped sign bits
+                if matches!(
+                    p,
+                    BvBinPred::Slt | BvBinPred::Sgt | BvBinPred::Sle | BvBinPred::Sge
+                ) {
+                    let m = x.len() - 1;
+                    x[m] = b.not(x[m]);
+                    y[m] = b.not(y[m]);
+                }
+                vec![match p {
+                    BvBinPred::Uge | BvBinPred::Sge => b.uge(&x, &y),
+                    BvBinPred::Ule | BvBinPred::Sle => b.uge(&y, &x),
+                    BvBinPred::Ult | BvBinPred::Slt => {
+                        let ge = b.uge(&x, &y);
+                        b.not(ge)
+                    }
+                    BvBinPred::Ugt | BvBinPred::Sgt => {
+                        let le = b.uge(&y, &x);
+                        b.not(le)
+                    }
+                }]
+            }
+            Op::BoolToBv => args[0].clone(),
+            Op::BvBit(i) => vec![args[0][*i]],
+            Op::BvExtract(hi, lo) => args[0][*lo as usize..=*hi as usize].to_vec(),
+            // the first argument holds the high bits
+            Op::BvConcat => args.iter().rev().flatten().copied().collect(),
+            Op::BvUext(n) => {
+                let mut bits = args[0].clone();
+                bits.extend(std::iter::repeat(Const(false)).take(*n));
+                bits
+            }
+            Op::BvSext(n) => {
+                let mut bits = args[0].clone();
+                let msb = *bits.last().unwrap();
+                bits.extend(std::iter::repeat(msb).take(*n));
+                bits
+            }
+            _ => panic!("Unsupported operator in bristol: {}", t.op()),
+        }
+    }
+}
+
+/// Lower `c` to a Bristol Fashion circuit.
+///
+/// Each party has one input value: its inputs, in the order of
+/// [ComputationMetadata::ordered_input_names], concatenated. Public inputs, if there are any,
+/// form one more value. Each output of `c` is an output value.
+pub fn to_bristol(c: &Computation) -> Circuit {
+    let md = &c.metadata;
+    let names = md.ordered_input_names();
+    let n_parties = names
+        .iter()
+        .filter_map(|n| md.get_input_visibility(n))
+        .map(|p| p as usize + 1)
+        .max()
+        .unwrap_or(0)
+        .max(md.n_parties());
+    let has_public = names.iter().any(|n| md.get_input_visibility(n).is_none());
+    let mut converter = ToBristol {
+        b: Builder::default(),
+        cache: TermMap::default(),
+        inputs: HashMap::default(),
+    };
+    let mut inputs = Vec::new();
+    for group in (0..n_parties).map(Some).chain(has_public.then_some(None)) {
+        let mut bits = 0;
+        for name in names
+            .iter()
+            .filter(|n| md.get_input_visibility(n).map(|p| p as usize) == group)
+        {
+            let w = width(&md.input_sort(name));
+            let wires = (0..w).map(|_| Bit::Wire(converter.b.fresh())).collect();
+            converter.inputs.insert(name.clone(), wires);
+            bits += w;
+        }
+        inputs.push(bits);
+    }
+    let mut outputs = Vec::new();
+    for o in &c.outputs {
+        converter.embed(o.clone());
+        outputs.push(converter.get(o).to_vec());
+    }
+    let circuit = finish(converter.b, inputs, outputs);
+    debug!(
+        "Bristol: {} gates ({} AND), {} wires",
+        circuit.gates.len(),
+        circuit.n_and(),
+        circuit.n_wires
+    );
+    circuit
+}
+
+/// Put the output bits on the last wires, and remove dead gates.
+fn finish(mut b: Builder, inputs: Vec<usize>, outputs: Vec<Vec<Bit>>) -> Circuit {
+    let n_inputs: usize = inputs.iter().sum();
+    let mut claimed = HashSet::default();
+    let mut out_wires = Vec::new();
+    for bit in outputs.iter().flatten() {
+        // an output must be the only use of its wire, so copy inputs and repeated outputs
+        let w = match *bit {
+            Bit::Wire(w) if w >= n_inputs && claimed.insert(w) => w,
+            Bit::Wire(w) => {
+                let o = b.fresh();
+                b.gates.push(Gate::Eqw(w, o));
+                o
+            }
+            Const(c) => {
+                let o = b.fresh();
+                b.gates.push(Gate::Eq(c, o));
+                o
+            }
+        };
+        claimed.insert(w);
+        out_wires.push(w);
+    }
+    let mut live: HashSet<Wire> = out_wires.iter().copied().collect();
+    let mut gates: Vec<Gate> = b
+        .gates
+        .into_iter()
+        .rev()
+        .filter(|g| {
+            let (ins, out) = match *g {
+                Gate::And(x, y, o) | Gate::Xor(x, y, o) => (vec![x, y], o),
+                Gate::Inv(x, o) | Gate::Eqw(x, o) => (vec![x], o),
+                Gate::Eq(_, o) => (vec![], o),
+            };
+            let keep = live.contains(&out);
+            if keep {
+                live.extend(ins);
+            }
+            keep
+        })
+        .collect();
+    gates.reverse();
+    // inputs, then other live wires, then outputs
+    let mut renumber: HashMap<Wire, Wire> = (0..n_inputs).map(|w| (w, w)).collect();
+    for g in &gates {
+        let out = match *g {
+            Gate::And(_, _, o) | Gate::Xor(_, _, o) | Gate::Inv(_, o) | Gate::Eqw(_, o) => o,
+            Gate::Eq(_, o) => o,
+        };
+        if !claimed.contains(&out) {
+            let next = renumber.len();
+            renumber.insert(out, next);
+        }
+    }
+    for w in &out_wires {
+        let next = renumber.len();
+        renumber.insert(*w, next);
+    }
+    let r = |w: &Wire| renumber[w];
+    let gates = gates
+        .iter()
+        .map(|g| match g {
+            Gate::And(x, y, o) => Gate::And(r(x), r(y), r(o)),
+            Gate::Xor(x, y, o) => Gate::Xor(r(x), r(y), r(o)),
+            Gate::Inv(x, o) => Gate::Inv(r(x), r(o)),
+            Gate::Eqw(x, o) => Gate::Eqw(r(x), r(o)),
+            Gate::Eq(c, o) => Gate::Eq(*c, r(o)),
+        })
+        .collect();
+    Circuit {
+        n_wires: renumber.len(),
+        inputs,
+        outputs: outputs.iter().map(|o| o.len()).collect(),
+        gates,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bits(v: u64, w: usize) -> Vec<bool> {
+        (0..w).map(|i| (v >> i) & 1 == 1).collect()
+    }
+
+    fn from_bits(b: &[bool]) -> u64 {
+        b.iter().rev().fold(0, |acc, b| (acc << 1) | *b as u64)
+    }
+
+    /// Check the circuit for `c` (with one 8-bit input per party) against evaluation.
+    fn check_8bit(c: &Computation) {
+        let circuit = to_bristol(c);
+        assert_eq!(circuit.inputs, vec![8, 8]);
+        let names = c.metadata.ordered_input_names();
+        for (x, y) in [
+            (0, 0),
+            (1, 0),
+            (3, 5),
+            (200, 7),
+            (255, 255),
+            (128, 3),
+            (9, 200),
+        ] {
+            let env: fxhash::FxHashMap<String, Value> = names
+                .iter()
+                .zip([x, y])
+                .map(|(n, v)| {
+                    let bv = BitVector::new(rug::Integer::from(v), 8);
+                    (n.clone(), Value::BitVector(bv))
+                })
+                .collect();
+            let actual = circuit.eval(&[bits(x, 8), bits(y, 8)]);
+            for (o, a) in c.outputs.iter().zip(actual) {
+                let expected = match eval(o, &env) {
+                    Value::Bool(b) => b as u64,
+                    Value::BitVector(bv) => bv.uint().to_u64().unwrap(),
+                    v => panic!("{}", v),
+                };
+                assert_eq!(from_bits(&a), expected, "{} on {}, {}", o, x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn arith() {
+        let mut c = text::parse_computation(
+            b"
+            (computation
+                (metadata
+                    (parties A B)
+                    (inputs (a (bv 8) (party 0)) (b (bv 8) (party 1)))
+                    (commitments)
+                )
+                (precompute () () (tuple))
+                (tuple
+                    (bvadd a b) (bvsub a b) (bvmul a b) (bvudiv a b) (bvurem a b)
+                    (bvneg a) (bvnot a) (bvand a b) (bvor a b) (bvxor a b)
+                    (bvshl a b) (bvlshr a b) (bvashr a b)
+                    (bvult a b) (bvslt a b) (bvuge a b) (bvsle a b) (= a b)
+                    (ite (bvugt a b) a b)
+                    ((extract 6 3) a) (concat a b) ((sext 4) a) ((uext 4) b)
+                )
+            )",
+        );
+        c.outputs = c.outputs[0].cs().to_vec();
+        check_8bit(&c);
+    }
+
+    #[test]
+    fn maj_adder_cost() {
+        let c = text::parse_computation(
+            b"
+            (computation
+                (metadata
+                    (parties A B)
+                    (inputs (a (bv 32) (party 0)) (b (bv 32) (party 1)))
+                    (commitments)
+                )
+                (precompute () () (tuple))
+                (bvadd a b)
+            )",
+        );
+        let circuit = to_bristol(&c);
+        // one AND per carry
+        assert_eq!(circuit.n_and(), 31);
+        assert_eq!(circuit.inputs, vec![32, 32]);
+        assert_eq!(circuit.outputs, vec![32]);
+        assert_eq!(circuit.n_wires - circuit.gates.len(), 64);
+        let out = circuit.eval(&[bits(3_000_000_000, 32), bits(2_000_000_000, 32)]);
+        assert_eq!(from_bits(&out[0]), (5_000_000_000u64) % (1 << 32));
+    }
+
+    #[test]
+    fn format() {
+        let c = text::parse_computation(
+            b"
+            (computation
+                (metadata
+                    (parties A B)
+                    (inputs (a bool (party 0)) (b bool (party 1)) (p bool))
+                    (commitments)
+                )
+                (precompute () () (tuple))
+                (or (and a b) p)
+            )",
+        );
+        let circuit = to_bristol(&c);
+        assert_eq!(circuit.inputs, vec![1, 1, 1]);
+        assert_eq!(
+            circuit.to_string(),
+            "4 7\n3 1 1 1\n1 1\n\n2 1 0 1 3 AND\n2 1 2 3 4 XOR\n2 1 2 3 5 AND\n2 1 4 5 6 XOR\n"
+        );
+    }
+}
@@ -0,0 +1,444 @@
+//! PLONKish relations: gate tables with custom gates
+//!
+//! An alternative to [crate::target::r1cs] for proof systems with custom gates (e.g., halo2). A
+//! [Plonkish] relation is a table with [WIDTH] advice columns (`a`, `b`, `c`). Each row enables
+//! one [Gate], and all cells that hold the same [Wire] are tied together by copy constraints.
+//!
+//! The standard arithmetic gate, `q_l a + q_r b + q_o c + q_m ab + q_c = 0`, is always available;
+//! it covers addition and multiplication. The [CustomGate]s are opt-in:
+//! * [CustomGate::Pow5]: `c = a^5`, the Poseidon S-box
+//! * [CustomGate::EcAdd]: incomplete addition of affine points on a short Weierstrass curve
+//!
+//! Public inputs occupy the `a` cells of the first rows, one per row, and are copied from the
+//! instance column.
+
+pub mod trans;
+
+use crate::ir::term::*;
+use crate::target::r1cs::wit_comp::{StagedWitComp, StagedWitCompEvaluator};
+
+use circ_fields::{FieldT, FieldV};
+use fxhash::FxHashMap as HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// A wire: a value that may appear in many cells
+pub type Wire = usize;
+
+/// The number of advice columns
+pub const WIDTH: usize = 3;
+
+/// The names of the advice columns
+pub const COLUMNS: [&str; WIDTH] = ["a", "b", "c"];
+
+/// An opt-in gate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CustomGate {
+    /// `c = a^5`
+    Pow5,
+    /// `(a, b) + (c, a') = (b', c')`, where primes denote the next row.
+    ///
+    /// This is incomplete addition: it is only sound when the x coordinates differ, so the
+    /// lowering also checks that they do. It is only used when division by zero is incomplete.
+    EcAdd,
+}
+
+impl CustomGate {
+    /// All custom gates
+    pub const ALL: [CustomGate; 2] = [CustomGate::Pow5, CustomGate::EcAdd];
+
+    /// The gate's name, as accepted by [CustomGate::from_str]
+    pub fn name(&self) -> &'static str {
+        match self {
+            CustomGate::Pow5 => "pow5",
+            CustomGate::EcAdd => "ec-add",
+        }
+    }
+
+    /// The name of the gate's selector column
+    pub fn selector(&self) -> &'static str {
+        match self {
+            CustomGate::Pow5 => "q_pow5",
+            CustomGate::EcAdd => "q_ec_add",
+        }
+    }
+
+    /// The polynomials that must vanish on rows where the gate is enabled, in halo2's notation:
+    /// `a(r)` is column `a`, rotated by `r` rows.
+    pub fn polys(&self) -> Vec<&'static str> {
+        match self {
+            CustomGate::Pow5 => vec!["a(0)^5 - c(0)"],
+            CustomGate::EcAdd => vec![
+                "(b(1) + c(0) + a(0)) * (c(0) - a(0))^2 - (a(1) - b(0))^2",
+                "(c(1) + b(0)) * (c(0) - a(0)) - (a(1) - b(0)) * (a(0) - b(1))",
+            ],
+        }
+    }
+
+    /// How many rows the gate spans
+    pub fn height(&self) -> usize {
+        match self {
+            CustomGate::Pow5 => 1,
+            CustomGate::EcAdd => 2,
+        }
+    }
+}
+
+impl Display for CustomGate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for CustomGate {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CustomGate::ALL
+            .iter()
+            .find(|g| g.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<_> = CustomGate::ALL.iter().map(|g| g.name()).collect();
+                format!(
+                    "Unknown custom gate '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// The gate enabled on a row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Gate {
+    /// `q_l a + q_r b + q_o c + q_m ab + q_c = 0`
+    Arith {
+        /// left coefficient
+        q_l: FieldV,
+        /// right coefficient
+        q_r: FieldV,
+        /// output coefficient
+        q_o: FieldV,
+        /// product coefficient
+        q_m: FieldV,
+        /// constant
+        q_c: FieldV,
+    },
+    /// A custom gate
+    Custom(CustomGate),
+    /// No gate: the row holds public inputs, or cells for a custom gate on an earlier row
+    None,
+}
+
+/// A row of the table. Empty cells are zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Row {
+    /// The enabled gate
+    pub gate: Gate,
+    /// The wire in each advice column
+    pub cells: [Option<Wire>; WIDTH],
+}
+
+/// A PLONKish relation
+#[derive(Debug, Clone)]
+pub struct Plonkish {
+    field: FieldT,
+    custom_gates: Vec<CustomGate>,
+    rows: Vec<Row>,
+    /// The name of each wire. Input wires keep their IR names.
+    names: Vec<String>,
+    /// For each wire, a term that computes it from the inputs and the precomputation's outputs
+    terms: Vec<Term>,
+    /// The public wires, in instance order
+    public: Vec<Wire>,
+    precompute: precomp::PreComp,
+}
+
+impl Plonkish {
+    /// The field
+    pub fn field(&self) -> &FieldT {
+        &self.field
+    }
+
+    /// The custom gates that may be enabled
+    pub fn custom_gates(&self) -> &[CustomGate] {
+        &self.custom_gates
+    }
+
+    /// The table
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// The number of wires
+    pub fn n_wires(&self) -> usize {
+        self.names.len()
+    }
+
+    /// The name of a wire
+    pub fn name(&self, w: Wire) -> &str {
+        &self.names[w]
+    }
+
+    /// The public wires, in instance order
+    pub fn public(&self) -> &[Wire] {
+        &self.public
+    }
+
+    /// How many rows enable each gate (`arith`, or a custom gate's name)
+    pub fn gate_counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for r in &self.rows {
+            let name = match &r.gate {
+                Gate::Arith { .. } => "arith",
+                Gate::Custom(g) => g.name(),
+                Gate::None => continue,
+            };
+            *counts.entry(name).or_default() += 1;
+        }
+        counts
+    }
+
+    /// The cells (column, row) of each wire, in table order. Copy constraints tie each wire's
+    /// cells together.
+    pub fn cells(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut cells = vec![Vec::new(); self.names.len()];
+        for (i, r) in self.rows.iter().enumerate() {
+            for (col, w) in r.cells.iter().enumerate() {
+                if let Some(w) = w {
+                    cells[*w].push((col, i));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Compute every wire from the inputs, using the IR evaluator.
+    pub fn eval_wires(&self, inputs: &HashMap<String, Value>) -> Vec<FieldV> {
+        let after_precompute = self.precompute.eval(inputs);
+        let mut cache = Default::default();
+        self.terms
+            .iter()
+            .map(|t| {
+                eval_cached(t, &after_precompute, &mut cache)
+                    .as_pf()
+                    .clone()
+            })
+            .collect()
+    }
+
+    /// Check that the wire values satisfy every gate.
+    pub fn check_values(&self, values: &[FieldV]) -> Result<(), String> {
+        assert_eq!(
+            values.len(),
+            self.names.len(),
+            "wrong number of wire values"
+        );
+        let zero = self.field.zero();
+        let cell = |row: usize, col: usize| match self.rows[row].cells[col] {
+            Some(w) => values[w].clone(),
+            None => zero.clone(),
+        };
+        for (i, r) in self.rows.iter().enumerate() {
+            let [a, b, c] = [cell(i, 0), cell(i, 1), cell(i, 2)];
+            let ok = match &r.gate {
+                Gate::Arith {
+                    q_l,
+                    q_r,
+                    q_o,
+                    q_m,
+                    q_c,
+                } => {
+                    let v =
+                        a.clone() * q_l + &(b.clone() * q_r) + &(c * q_o) + &(a * &b * q_m) + q_c;
+                    v.is_zero()
+                }
+                Gate::Custom(CustomGate::Pow5) => a.pow(5) == c,
+                Gate::Custom(CustomGate::EcAdd) => {
+                    if i + 1 >= self.rows.len() {
+                        return Err(format!("row {i}: ec-add gate on the last row"));
+                    }
+                    let (x1, y1, x2) = (a, b, c);
+                    let (y2, x3, y3) = (cell(i + 1, 0), cell(i + 1, 1), cell(i + 1, 2));
+                    let dx = x2.clone() - &x1;
+                    let dy = y2 - &y1;
+                    let p1 = (x3.clone() + &x2 + &x1) * &dx * &dx - &(dy.clone() * &dy);
+                    let p2 = (y3 + &y1) * &dx - &(dy * &(x1 - &x3));
+                    p1.is_zero() && p2.is_zero()
+                }
+                Gate::None => true,
+            };
+            if !ok {
+                let names: Vec<String> = r
+                    .cells
+                    .iter()
+                    .map(|w| match w {
+                        Some(w) => format!("{} = {}", self.names[*w], values[*w]),
+                        None => "-".into(),
+                    })
+                    .collect();
+                return Err(format!(
+                    "row {}: unsatisfied {:?}\n  cells: {}",
+                    i,
+                    r.gate,
+                    names.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check all gates on the witness computed from these inputs. Panics if one fails.
+    pub fn check_all(&self, inputs: &HashMap<String, Value>) {
+        if let Err(e) = self.check_values(&self.eval_wires(inputs)) {
+            panic!("Error! Bad gate: {}", e)
+        }
+    }
+
+    /// A description of the relation for halo2-style provers.
+    ///
+    /// The fixed columns are the arithmetic gate's coefficients and one selector per custom
+    /// gate. Field elements are decimal strings.
+    pub fn to_json(&self) -> Json {
+        const ARITH: [&str; 5] = ["q_l", "q_r", "q_o", "q_m", "q_c"];
+        let n = self.rows.len();
+        let mut fixed: BTreeMap<&str, Vec<String>> = ARITH
+            .iter()
+            .copied()
+            .chain(self.custom_gates.iter().map(|g| g.selector()))
+            .map(|c| (c, vec!["0".to_owned(); n]))
+            .collect();
+        for (i, r) in self.rows.iter().enumerate() {
+            match &r.gate {
+                Gate::Arith {
+                    q_l,
+                    q_r,
+                    q_o,
+                    q_m,
+                    q_c,
+                } => {
+                    for (col, q) in ARITH.iter().zip([q_l, q_r, q_o, q_m, q_c]) {
+                        fixed.get_mut(col).unwrap()[i] = q.i().to_string();
+                    }
+                }
+                Gate::Custom(g) => fixed.get_mut(g.selector()).unwrap()[i] = "1".to_owned(),
+                Gate::None => {}
+            }
+        }
+        let mut gates = vec![json!({
+            "name": "arith",
+            "polys": ["q_l(0) * a(0) + q_r(0) * b(0) + q_o(0) * c(0) + q_m(0) * a(0) * b(0) + q_c(0)"],
+        })];
+        for g in &self.custom_gates {
+            gates.push(json!({
+                "name": g.name(),
+                "polys": g
+                    .polys()
+                    .iter()
+                    .map(|p| format!("{}(0) * ({})", g.selector(), p))
+                    .collect::<Vec<_>>(),
+            }));
+        }
+        let cell = |(col, row): (usize, usize)| json!({ "column": COLUMNS[col], "row": row });
+        let copies: Vec<Json> = self
+            .cells()
+            .into_iter()
+            .flat_map(|cells| {
+                cells
+                    .windows(2)
+                    .map(|p| json!([cell(p[0]), cell(p[1])]))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let instance: Vec<Json> = (0..self.public.len())
+            .map(|i| json!({ "name": self.names[self.public[i]], "cell": cell((0, i)) }))
+            .collect();
+        json!({
+            "field": self.field.modulus().to_string(),
+            "k": crate::target::bitsize(n.saturating_sub(1)).max(1),
+            "rows": n,
+            "columns": {
+                "advice": COLUMNS,
+                "fixed": fixed.keys().collect::<Vec<_>>(),
+                "instance": ["instance"],
+            },
+            "gates": gates,
+            "fixed": fixed,
+            "copies": copies,
+            "instance": instance,
+        })
+    }
+
+    /// Attach a witness computation, for provers.
+    pub fn finalize(self, cs: &Computation) -> ProverData {
+        let mut precompute = cs.precomputes.clone();
+        for (name, t) in self.names.iter().zip(&self.terms) {
+            if !precompute.outputs().contains_key(name) {
+                precompute.add_output(name.clone(), t.clone());
+            }
+        }
+        precompute.flatten();
+        let mut outputs = precompute.outputs;
+        let vars: HashMap<String, Sort> =
+            PostOrderIter::from_roots_and_skips(outputs.values().cloned(), Default::default())
+                .filter_map(|t| match t.op() {
+                    Op::Var(v) => Some((v.name.to_string(), v.sort.clone())),
+                    _ => None,
+                })
+                .collect();
+        let terms = self
+            .names
+            .iter()
+            .map(|n| outputs.remove(n).unwrap())
+            .collect();
+        let mut comp = StagedWitComp::default();
+        comp.add_stage(vars, terms);
+        ProverData {
+            plonkish: self,
+            precompute: comp,
+        }
+    }
+}
+
+/// Relation-related data that a prover needs
+#[derive(Debug)]
+pub struct ProverData {
+    /// The relation
+    pub plonkish: Plonkish,
+    /// Witness computation: one output per wire
+    pub precompute: StagedWitComp,
+}
+
+impl ProverData {
+    /// Compute the value of every wire.
+    pub fn witness(&self, inputs: &HashMap<String, Value>) -> Vec<FieldV> {
+        let mut eval = StagedWitCompEvaluator::new(&self.precompute);
+        eval.eval_stage(inputs.clone())
+            .into_iter()
+            .map(|v| v.as_pf().clone())
+            .collect()
+    }
+
+    /// The advice columns, row by row.
+    pub fn advice(&self, witness: &[FieldV]) -> Vec<[FieldV; WIDTH]> {
+        let zero = self.plonkish.field.zero();
+        self.plonkish
+            .rows
+            .iter()
+            .map(|r| {
+                r.cells
+                    .map(|w| w.map_or_else(|| zero.clone(), |w| witness[w].clone()))
+            })
+            .collect()
+    }
+
+    /// Check all gates on the witness computed from these inputs. Panics if one fails.
+    pub fn check_all(&self, inputs: &HashMap<String, Value>) {
+        if let Err(e) = self.plonkish.check_values(&self.witness(inputs)) {
+            panic!("Error! Bad gate: {}", e)
+        }
+    }
+}
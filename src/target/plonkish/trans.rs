@@ -0,0 +1,970 @@
+//! Lowering IR to a PLONKish gate table
+//!
+//! Field terms become linear combinations of wires; a combination only gets its own wire
+//! (and the rows that compute it) when it is an argument to a gate. Booleans are 0/1 wires.
+//! Bit-vectors are not supported: computations should be over the field.
+//!
+//! With [CustomGate::Pow5] enabled, a product of five copies of one term (in any nesting) takes
+//! one row. With [CustomGate::EcAdd] enabled, the lowering recognizes incomplete point addition
+//! written out as
+//!
+//! ```text
+//! l = (y2 - y1) / (x2 - x1)
+//! x3 = l * l - x1 - x2
+//! y3 = l * (x1 - x3) - y1
+//! ```
+//!
+//! and computes `x3` and `y3` with one ec-add gate, plus two rows that check that `x1 != x2`.
+
+use super::*;
+use crate::cfg::CircCfg;
+use crate::ir::term::extras::free_variables;
+
+use circ_opt::FieldDivByZero;
+use log::debug;
+
+/// `sum(coeffs[w] * w) + constant`
+#[derive(Debug, Clone)]
+struct Lin {
+    coeffs: BTreeMap<Wire, FieldV>,
+    constant: FieldV,
+}
+
+impl Lin {
+    fn constant(constant: FieldV) -> Self {
+        Lin {
+            coeffs: BTreeMap::new(),
+            constant,
+        }
+    }
+
+    fn wire(w: Wire, field: &FieldT) -> Self {
+        Lin {
+            coeffs: std::iter::once((w, field.new_v(1))).collect(),
+            constant: field.zero(),
+        }
+    }
+
+    fn as_const(&self) -> Option<&FieldV> {
+        self.coeffs.is_empty().then_some(&self.constant)
+    }
+
+    /// If this is just a wire, that wire.
+    fn as_wire(&self) -> Option<Wire> {
+        match self.coeffs.iter().next() {
+            Some((w, c)) if self.coeffs.len() == 1 && c.is_one() && self.constant.is_zero() => {
+                Some(*w)
+            }
+            _ => None,
+        }
+    }
+
+    fn scale(mut self, c: &FieldV) -> Self {
+        if c.is_zero() {
+            return Lin::constant(c.clone());
+        }
+        for v in self.coeffs.values_mut() {
+            *v *= c;
+        }
+        self.constant *= c;
+        self
+    }
+
+    fn add(mut self, other: &Lin) -> Self {
+        for (w, c) in &other.coeffs {
+            let v = self.coeffs.entry(*w).or_insert_with(|| c.ty().zero());
+            *v += c;
+            if v.is_zero() {
+                self.coeffs.remove(w);
+            }
+        }
+        self.constant += &other.constant;
+        self
+    }
+
+    fn sub(self, other: &Lin) -> Self {
+        let neg_one = -self.constant.ty().new_v(1);
+        self.add(&other.clone().scale(&neg_one))
+    }
+}
+
+/// The terms of an incomplete point addition `(x1, y1) + (x2, y2) = (x3, y3)`
+#[derive(Debug, Clone)]
+struct EcAdd {
+    x1: Term,
+    y1: Term,
+    x2: Term,
+    y2: Term,
+    x3: Term,
+    y3: Term,
+}
+
+/// View `t` as `sum(c_i * t_i) + k`, looking through additions, negations, and products with
+/// constants.
+fn affine(t: &Term, field: &FieldT) -> (TermMap<FieldV>, FieldV) {
+    fn go(t: &Term, coeff: FieldV, acc: &mut TermMap<FieldV>, k: &mut FieldV) {
+        match t.op() {
+            Op::Const(_) => *k += &(coeff * t.as_pf_opt().unwrap()),
+            Op::PfNaryOp(PfNaryOp::Add) => {
+                for c in t.cs() {
+                    go(c, coeff.clone(), acc, k);
+                }
+            }
+            Op::PfUnOp(PfUnOp::Neg) => go(&t.cs()[0], -coeff, acc, k),
+            Op::PfNaryOp(PfNaryOp::Mul)
+                if t.cs().iter().filter(|c| c.as_pf_opt().is_none()).count() <= 1 =>
+            {
+                let mut coeff = coeff;
+                let mut rest = None;
+                for c in t.cs() {
+                    match c.as_pf_opt() {
+                        Some(v) => coeff *= v,
+                        None => rest = Some(c),
+                    }
+                }
+                match rest {
+                    Some(c) => go(c, coeff, acc, k),
+                    None => *k += &coeff,
+                }
+            }
+            _ => {
+                let v = acc.entry(t.clone()).or_insert_with(|| coeff.ty().zero());
+                *v += &coeff;
+            }
+        }
+    }
+    let mut acc = TermMap::default();
+    let mut k = field.zero();
+    go(t, field.new_v(1), &mut acc, &mut k);
+    acc.retain(|_, c| !c.is_zero());
+    (acc, k)
+}
+
+/// If `t` is `x - y`, then `(x, y)`.
+fn as_diff(t: &Term, field: &FieldT) -> Option<(Term, Term)> {
+    let (terms, k) = affine(t, field);
+    if !k.is_zero() || terms.len() != 2 {
+        return None;
+    }
+    let pos = terms.iter().find(|(_, c)| c.is_one())?.0.clone();
+    let neg = terms
+        .iter()
+        .find(|(_, c)| (-(*c).clone()).is_one())?
+        .0
+        .clone();
+    Some((pos, neg))
+}
+
+/// The factors of a product, looking through nested products and divisions (as reciprocals).
+fn factors(t: &Term) -> Vec<Term> {
+    match t.op() {
+        Op::PfNaryOp(PfNaryOp::Mul) => t.cs().iter().flat_map(factors).collect(),
+        Op::PfDiv => {
+            let mut fs = factors(&t.cs()[0]);
+            fs.push(term![PF_RECIP; t.cs()[1].clone()]);
+            fs
+        }
+        _ => vec![t.clone()],
+    }
+}
+
+/// Recognize `y3` in an incomplete point addition (see the module docs).
+fn match_ec_add(y3: &Term, field: &FieldT) -> Option<EcAdd> {
+    let (q, y1) = as_diff(y3, field)?;
+    let fs = factors(&q);
+    if fs.len() != 3 {
+        return None;
+    }
+    let (recips, others): (Vec<_>, Vec<_>) = fs
+        .into_iter()
+        .partition(|f| matches!(f.op(), Op::PfUnOp(PfUnOp::Recip)));
+    if recips.len() != 1 {
+        return None;
+    }
+    let r = &recips[0];
+    let (x2, x1) = as_diff(&r.cs()[0], field)?;
+    for (n, dx) in [(&others[0], &others[1]), (&others[1], &others[0])] {
+        let (y2, x3) = match (as_diff(n, field), as_diff(dx, field)) {
+            (Some((y2, y1_)), Some((x1_, x3))) if y1_ == y1 && x1_ == x1 => (y2, x3),
+            _ => continue,
+        };
+        // x3 = l * l - x1 - x2
+        let (terms, k) = affine(&x3, field);
+        let neg_one = -field.new_v(1);
+        if !k.is_zero()
+            || terms.len() != 3
+            || terms.get(&x1) != Some(&neg_one)
+            || terms.get(&x2) != Some(&neg_one)
+        {
+            continue;
+        }
+        let l2 = match terms
+            .iter()
+            .find(|(t, c)| c.is_one() && **t != x1 && **t != x2)
+        {
+            Some((l2, _)) => l2,
+            None => continue,
+        };
+        let mut l2_factors = factors(l2);
+        l2_factors.sort();
+        let mut expected = vec![n.clone(), n.clone(), r.clone(), r.clone()];
+        expected.sort();
+        if l2_factors == expected {
+            return Some(EcAdd {
+                x1,
+                y1,
+                x2,
+                y2,
+                x3,
+                y3: y3.clone(),
+            });
+        }
+    }
+    None
+}
+
+struct ToPlonkish<'cfg> {
+    cfg: &'cfg CircCfg,
+    field: FieldT,
+    p: Plonkish,
+    cache: TermMap<Lin>,
+    /// Recognized point additions, by `x3` and `y3`
+    ec_adds: TermMap<EcAdd>,
+    zero: FieldV,
+    one: FieldV,
+}
+
+impl<'cfg> ToPlonkish<'cfg> {
+    fn new(cfg: &'cfg CircCfg, custom_gates: &[CustomGate], precompute: precomp::PreComp) -> Self {
+        let field = cfg.field().clone();
+        let mut custom_gates = custom_gates.to_vec();
+        custom_gates.sort();
+        custom_gates.dedup();
+        ToPlonkish {
+            cfg,
+            zero: field.zero(),
+            one: field.new_v(1),
+            p: Plonkish {
+                field: field.clone(),
+                custom_gates,
+                rows: Vec::new(),
+                names: Vec::new(),
+                terms: Vec::new(),
+                public: Vec::new(),
+                precompute,
+            },
+            field,
+            cache: TermMap::default(),
+            ec_adds: TermMap::default(),
+        }
+    }
+
+    fn enabled(&self, g: CustomGate) -> bool {
+        self.p.custom_gates.contains(&g)
+    }
+
+    fn fresh(&mut self, name: String, t: Term) -> Wire {
+        self.p.names.push(name);
+        self.p.terms.push(t);
+        self.p.names.len() - 1
+    }
+
+    fn fresh_wit(&mut self, t: Term) -> Wire {
+        let name = format!("__plonk_w{}", self.p.names.len());
+        self.fresh(name, t)
+    }
+
+    fn row(&mut self, gate: Gate, cells: [Option<Wire>; WIDTH]) {
+        self.p.rows.push(Row { gate, cells });
+    }
+
+    /// `q_l a + q_r b + q_o c + q_m ab + q_c = 0`
+    fn arith(&mut self, cells: [Option<Wire>; WIDTH], [q_l, q_r, q_o, q_m, q_c]: [FieldV; 5]) {
+        self.row(
+            Gate::Arith {
+                q_l,
+                q_r,
+                q_o,
+                q_m,
+                q_c,
+            },
+            cells,
+        );
+    }
+
+    /// A term that computes `x`.
+    fn term_of(&self, x: &Lin) -> Term {
+        if let Some(w) = x.as_wire() {
+            return self.p.terms[w].clone();
+        }
+        term(
+            PF_ADD,
+            std::iter::once(pf_lit(x.constant.clone()))
+                .chain(
+                    x.coeffs
+                        .iter()
+                        .map(|(w, c)| term![PF_MUL; pf_lit(c.clone()), self.p.terms[*w].clone()]),
+                )
+                .collect(),
+        )
+    }
+
+    /// Replace all but the last `n` wires of `x` by (a wire holding) their sum.
+    fn shrink(&mut self, x: &Lin, n: usize) -> Vec<(Wire, FieldV)> {
+        let mut coeffs: Vec<(Wire, FieldV)> =
+            x.coeffs.iter().map(|(w, c)| (*w, c.clone())).collect();
+        while coeffs.len() > n {
+            let rest = coeffs.split_off(2);
+            let head = Lin {
+                coeffs: coeffs.into_iter().collect(),
+                constant: self.zero.clone(),
+            };
+            let w = self.materialize(&head);
+            coeffs = std::iter::once((w, self.one.clone())).chain(rest).collect();
+        }
+        coeffs
+    }
+
+    /// A wire holding `x`.
+    fn materialize(&mut self, x: &Lin) -> Wire {
+        if let Some(w) = x.as_wire() {
+            return w;
+        }
+        let coeffs = self.shrink(x, 2);
+        let out = self.fresh_wit(self.term_of(x));
+        let mut cells = [None, None, Some(out)];
+        let mut qs = [self.zero.clone(), self.zero.clone()];
+        for (i, (w, c)) in coeffs.into_iter().enumerate() {
+            cells[i] = Some(w);
+            qs[i] = c;
+        }
+        let [q_l, q_r] = qs;
+        self.arith(
+            cells,
+            [
+                q_l,
+                q_r,
+                -self.one.clone(),
+                self.zero.clone(),
+                x.constant.clone(),
+            ],
+        );
+        out
+    }
+
+    /// `x` as `c * w + k`
+    fn single(&mut self, x: &Lin) -> (Wire, FieldV, FieldV) {
+        if x.coeffs.len() == 1 {
+            let (w, c) = x.coeffs.iter().next().unwrap();
+            (*w, c.clone(), x.constant.clone())
+        } else {
+            (self.materialize(x), self.one.clone(), self.zero.clone())
+        }
+    }
+
+    fn enforce_zero(&mut self, x: Lin) {
+        let coeffs = self.shrink(&x, WIDTH);
+        let mut cells = [None; WIDTH];
+        let mut qs = [self.zero.clone(), self.zero.clone(), self.zero.clone()];
+        for (i, (w, c)) in coeffs.into_iter().enumerate() {
+            cells[i] = Some(w);
+            qs[i] = c;
+        }
+        let [q_l, q_r, q_o] = qs;
+        self.arith(cells, [q_l, q_r, q_o, self.zero.clone(), x.constant]);
+    }
+
+    fn mul(&mut self, x: Lin, y: Lin) -> Lin {
+        if let Some(c) = x.as_const() {
+            return y.scale(c);
+        }
+        if let Some(c) = y.as_const() {
+            return x.scale(c);
+        }
+        let (wx, cx, kx) = self.single(&x);
+        let (wy, cy, ky) = self.single(&y);
+        let out = self.fresh_wit(term![PF_MUL; self.term_of(&x), self.term_of(&y)]);
+        // (cx wx + kx)(cy wy + ky) = out
+        self.arith(
+            [Some(wx), Some(wy), Some(out)],
+            [
+                cx.clone() * &ky,
+                cy.clone() * &kx,
+                -self.one.clone(),
+                cx * &cy,
+                kx * &ky,
+            ],
+        );
+        Lin::wire(out, &self.field)
+    }
+
+    /// `n / d`, where division by zero follows `--r1cs-div-by-zero`
+    fn div(&mut self, n: Lin, d: Lin) -> Lin {
+        if let Some(c) = d.as_const() {
+            if !c.is_zero() {
+                return n.scale(&c.clone().recip());
+            }
+        }
+        match self.cfg.r1cs.div_by_zero {
+            FieldDivByZero::Incomplete => self.div_incomplete(n, d),
+            FieldDivByZero::Zero => {
+                if d.as_const().is_some() {
+                    return Lin::constant(self.zero.clone());
+                }
+                // inv (1 - d inv) = 0, so inv is 0 if d is
+                let (inv, prod) = self.inverse(&d);
+                self.arith(
+                    [Some(inv), Some(prod), None],
+                    [
+                        self.one.clone(),
+                        self.zero.clone(),
+                        self.zero.clone(),
+                        -self.one.clone(),
+                        self.zero.clone(),
+                    ],
+                );
+                self.mul(n, Lin::wire(inv, &self.field))
+            }
+            FieldDivByZero::NonDet => {
+                // d^2 inv = d, so inv is 1/d if d is not 0 (and anything otherwise)
+                let d2 = self.mul(d.clone(), d.clone());
+                let wd2 = self.materialize(&d2);
+                let wd = self.materialize(&d);
+                let inv = self.fresh_wit(self.recip_or_zero(wd));
+                self.arith(
+                    [Some(wd2), Some(inv), Some(wd)],
+                    [
+                        self.zero.clone(),
+                        self.zero.clone(),
+                        -self.one.clone(),
+                        self.one.clone(),
+                        self.zero.clone(),
+                    ],
+                );
+                self.mul(n, Lin::wire(inv, &self.field))
+            }
+        }
+    }
+
+    /// `n / d`, unsatisfiable if `d` is zero
+    fn div_incomplete(&mut self, n: Lin, d: Lin) -> Lin {
+        let (wd, cd, kd) = self.single(&d);
+        let q = self.fresh_wit(term![PF_DIV; self.term_of(&n), self.term_of(&d)]);
+        // (cd wd + kd) q = n
+        let (wn, q_o, q_c) = match n.as_const() {
+            Some(k) => (None, self.zero.clone(), -k.clone()),
+            None => {
+                let (wn, cn, kn) = self.single(&n);
+                (Some(wn), -cn, -kn)
+            }
+        };
+        self.arith(
+            [Some(wd), Some(q), wn],
+            [self.zero.clone(), kd, q_o, cd, q_c],
+        );
+        Lin::wire(q, &self.field)
+    }
+
+    /// A term for the inverse of wire `w`, or 0 if it is 0.
+    fn recip_or_zero(&self, w: Wire) -> Term {
+        let t = self.p.terms[w].clone();
+        let zero_t = pf_lit(self.zero.clone());
+        term![Op::Ite; term![Op::Eq; t.clone(), zero_t.clone()], zero_t, term![PF_RECIP; t]]
+    }
+
+    /// Wires `inv` and `x inv`, constrained by `x (1 - x inv) = 0`: if `x` is not zero, then
+    /// `inv` is its inverse.
+    fn inverse(&mut self, x: &Lin) -> (Wire, Wire) {
+        let wx = self.materialize(x);
+        let inv = self.fresh_wit(self.recip_or_zero(wx));
+        let prod = self.mul(Lin::wire(wx, &self.field), Lin::wire(inv, &self.field));
+        let wp = prod.as_wire().unwrap();
+        self.arith(
+            [Some(wx), Some(wp), None],
+            [
+                self.one.clone(),
+                self.zero.clone(),
+                self.zero.clone(),
+                -self.one.clone(),
+                self.zero.clone(),
+            ],
+        );
+        (inv, wp)
+    }
+
+    /// 1 if `x` is zero; 0 otherwise
+    fn is_zero(&mut self, x: Lin) -> Lin {
+        if let Some(c) = x.as_const() {
+            return Lin::constant(self.field.new_v(c.is_zero() as u8));
+        }
+        let (_, prod) = self.inverse(&x);
+        Lin::constant(self.one.clone()).sub(&Lin::wire(prod, &self.field))
+    }
+
+    fn not(&self, x: &Lin) -> Lin {
+        Lin::constant(self.one.clone()).sub(x)
+    }
+
+    fn xor(&mut self, x: Lin, y: Lin) -> Lin {
+        let xy = self.mul(x.clone(), y.clone());
+        x.add(&y).sub(&xy.scale(&self.field.new_v(2)))
+    }
+
+    /// Declare an input. Returns its wire if it is a bool, which must be constrained by
+    /// [ToPlonkish::assert_bool] once all public inputs have their rows.
+    fn declare_input(&mut self, var: &Term, public: bool) -> Option<Wire> {
+        let v = match var.op() {
+            Op::Var(v) => v,
+            _ => unreachable!(),
+        };
+        let (t, is_bool) = match &v.sort {
+            Sort::Bool => (
+                term![Op::Ite; var.clone(), pf_lit(self.one.clone()), pf_lit(self.zero.clone())],
+                true,
+            ),
+            Sort::Field(f) => {
+                assert_eq!(f, &self.field, "input {} is in the wrong field", v.name);
+                (var.clone(), false)
+            }
+            s => panic!(
+                "Unsupported input sort in plonkish lowering: {} : {}",
+                v.name, s
+            ),
+        };
+        let w = self.fresh(v.name.to_string(), t);
+        if public {
+            self.p.public.push(w);
+            self.row(Gate::None, [Some(w), None, None]);
+        }
+        self.cache.insert(var.clone(), Lin::wire(w, &self.field));
+        is_bool.then_some(w)
+    }
+
+    /// `w^2 - w = 0`
+    fn assert_bool(&mut self, w: Wire) {
+        self.arith(
+            [Some(w), Some(w), None],
+            [
+                -self.one.clone(),
+                self.zero.clone(),
+                self.zero.clone(),
+                self.one.clone(),
+                self.zero.clone(),
+            ],
+        );
+    }
+
+    /// If `t` is `x^5` and we have the gate for it, then `x`.
+    fn pow5_base(&self, t: &Term) -> Option<Term> {
+        if !self.enabled(CustomGate::Pow5) || t.op() != &PF_MUL {
+            return None;
+        }
+        let fs = factors(t);
+        (fs.len() == 5 && fs.iter().all(|f| f == &fs[0] && f.as_pf_opt().is_none()))
+            .then(|| fs[0].clone())
+    }
+
+    /// The terms that must be lowered before `t`.
+    fn args(&self, t: &Term) -> Vec<Term> {
+        if let Some(e) = self.ec_adds.get(t) {
+            vec![e.x1.clone(), e.y1.clone(), e.x2.clone(), e.y2.clone()]
+        } else if let Some(x) = self.pow5_base(t) {
+            vec![x]
+        } else {
+            t.cs().to_vec()
+        }
+    }
+
+    fn lower(&mut self, t: &Term) -> Lin {
+        let mut stack = vec![(false, t.clone())];
+        while let Some((children_pushed, n)) = stack.pop() {
+            if self.cache.contains_key(&n) {
+                continue;
+            }
+            if children_pushed {
+                self.embed(&n);
+            } else {
+                stack.push((true, n.clone()));
+                for c in self.args(&n) {
+                    if !self.cache.contains_key(&c) {
+                        stack.push((false, c));
+                    }
+                }
+            }
+        }
+        self.cache.get(t).unwrap().clone()
+    }
+
+    fn get(&self, t: &Term) -> Lin {
+        self.cache.get(t).unwrap().clone()
+    }
+
+    /// Only used with incomplete division (see [to_plonkish]): the gate can't express the result
+    /// for `x1 = x2` under the other semantics.
+    fn embed_ec_add(&mut self, e: EcAdd) {
+        assert_eq!(self.cfg.r1cs.div_by_zero, FieldDivByZero::Incomplete);
+        let [x1, y1, x2, y2] = [&e.x1, &e.y1, &e.x2, &e.y2].map(|t| self.get(t));
+        // incomplete addition: x1 != x2
+        self.div(Lin::constant(self.one.clone()), x2.clone().sub(&x1));
+        let [x1, y1, x2, y2] = [x1, y1, x2, y2].map(|x| self.materialize(&x));
+        let x3 = self.fresh_wit(e.x3.clone());
+        let y3 = self.fresh_wit(e.y3.clone());
+        self.row(
+            Gate::Custom(CustomGate::EcAdd),
+            [Some(x1), Some(y1), Some(x2)],
+        );
+        self.row(Gate::None, [Some(y2), Some(x3), Some(y3)]);
+        self.cache.insert(e.x3, Lin::wire(x3, &self.field));
+        self.cache.insert(e.y3, Lin::wire(y3, &self.field));
+    }
+
+    fn embed(&mut self, t: &Term) {
+        if let Some(e) = self.ec_adds.get(t) {
+            let e = e.clone();
+            self.embed_ec_add(e);
+            return;
+        }
+        if let Some(x) = self.pow5_base(t) {
+            let x = self.get(&x);
+            let wx = self.materialize(&x);
+            let out = self.fresh_wit(t.clone());
+            self.row(Gate::Custom(CustomGate::Pow5), [Some(wx), None, Some(out)]);
+            self.cache.insert(t.clone(), Lin::wire(out, &self.field));
+            return;
+        }
+        let args: Vec<Lin> = t.cs().iter().map(|c| self.get(c)).collect();
+        let lin = match t.op() {
+            Op::Var(v) => panic!("Undeclared input in plonkish lowering: {}", v.name),
+            Op::Const(v) => match &**v {
+                Value::Field(f) => Lin::constant(f.as_ty_ref(&self.field)),
+                Value::Bool(b) => Lin::constant(self.field.new_v(*b as u8)),
+                _ => panic!("Unsupported constant in plonkish lowering: {}", t),
+            },
+            Op::PfNaryOp(PfNaryOp::Add) => args
+                .into_iter()
+                .fold(Lin::constant(self.zero.clone()), |acc, x| acc.add(&x)),
+            Op::PfNaryOp(PfNaryOp::Mul) | Op::BoolNaryOp(BoolNaryOp::And) => {
+                let one = Lin::constant(self.one.clone());
+                args.into_iter().fold(one, |acc, x| self.mul(acc, x))
+            }
+            Op::PfUnOp(PfUnOp::Neg) => args[0].clone().scale(&-self.one.clone()),
+            Op::PfUnOp(PfUnOp::Recip) => self.div(Lin::constant(self.one.clone()), args[0].clone()),
+            Op::PfDiv => self.div(args[0].clone(), args[1].clone()),
+            Op::Not => self.not(&args[0]),
+            Op::BoolNaryOp(BoolNaryOp::Or) => {
+                // not any = all not
+                let negated: Vec<Lin> = args.iter().map(|x| self.not(x)).collect();
+                let one = Lin::constant(self.one.clone());
+                let none = negated.into_iter().fold(one, |acc, x| self.mul(acc, x));
+                self.not(&none)
+            }
+            Op::BoolNaryOp(BoolNaryOp::Xor) => {
+                let zero = Lin::constant(self.zero.clone());
+                args.into_iter().fold(zero, |acc, x| self.xor(acc, x))
+            }
+            Op::Implies => {
+                let ab = self.mul(args[0].clone(), args[1].clone());
+                self.not(&args[0]).add(&ab)
+            }
+            Op::Ite => {
+                let diff = args[1].clone().sub(&args[2]);
+                let d = self.mul(args[0].clone(), diff);
+                args[2].clone().add(&d)
+            }
+            Op::Eq if check(&t.cs()[0]) == Sort::Bool => {
+                let x = self.xor(args[0].clone(), args[1].clone());
+                self.not(&x)
+            }
+            Op::Eq => self.is_zero(args[0].clone().sub(&args[1])),
+            o => panic!("Unsupported op in plonkish lowering: {}", o),
+        };
+        self.cache.insert(t.clone(), lin);
+    }
+
+    fn assert(&mut self, t: &Term) {
+        match t.op() {
+            Op::BoolNaryOp(BoolNaryOp::And) => {
+                for c in t.cs() {
+                    self.assert(c);
+                }
+            }
+            Op::Eq => {
+                let a = self.lower(&t.cs()[0]);
+                let b = self.lower(&t.cs()[1]);
+                self.enforce_zero(a.sub(&b));
+            }
+            _ => {
+                let x = self.lower(t);
+                let one = Lin::constant(self.one.clone());
+                self.enforce_zero(x.sub(&one));
+            }
+        }
+    }
+}
+
+/// Lower a proof-mode computation to a PLONKish relation, using the given custom gates.
+pub fn to_plonkish(cs: &Computation, cfg: &CircCfg, custom_gates: &[CustomGate]) -> Plonkish {
+    let mut converter = ToPlonkish::new(cfg, custom_gates, cs.precomputes.clone());
+    // with complete division semantics, point additions are lowered like any other division
+    if converter.enabled(CustomGate::EcAdd) && cfg.r1cs.div_by_zero == FieldDivByZero::Incomplete {
+        for t in PostOrderIter::from_roots_and_skips(cs.outputs.iter().cloned(), Default::default())
+        {
+            if let Some(e) = match_ec_add(&t, &converter.field) {
+                if !converter.ec_adds.contains_key(&e.x3) {
+                    converter.ec_adds.insert(e.x3.clone(), e.clone());
+                    converter.ec_adds.insert(e.y3.clone(), e);
+                }
+            }
+        }
+        debug!("Point additions: {}", converter.ec_adds.len() / 2);
+    }
+    let used_vars = free_variables(term(Op::Tuple, cs.outputs.clone()));
+    let vars = cs.metadata.interactive_vars();
+    if !vars.committed_wit_vecs.is_empty() || vars.rounds.iter().any(|r| !r.challenges.is_empty()) {
+        panic!("The plonkish lowering does not support commitments or challenges");
+    }
+    let inputs = vars.instances.iter().map(|v| (v, true)).chain(
+        vars.rounds
+            .iter()
+            .flat_map(|r| &r.witnesses)
+            .chain(&vars.final_witnesses)
+            .map(|v| (v, false)),
+    );
+    let mut bools = Vec::new();
+    for (v, public) in inputs {
+        if used_vars.contains(v.as_var_name()) {
+            bools.extend(converter.declare_input(v, public));
+        }
+    }
+    // public inputs occupy the first rows, so their booleanity rows come after
+    for w in bools {
+        converter.assert_bool(w);
+    }
+    for c in &cs.outputs {
+        converter.assert(c);
+    }
+    converter.p
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::ir::proof::Constraints;
+
+    fn field() -> FieldT {
+        CircCfg::default().field().clone()
+    }
+
+    fn pf_var(name: &str) -> Term {
+        var(name.to_owned(), Sort::Field(field()))
+    }
+
+    fn lower(t: Term, public: Vec<Term>, gates: &[CustomGate]) -> Plonkish {
+        let cs = Computation::from_constraint_system_parts(vec![t], public);
+        to_plonkish(&cs, &CircCfg::default(), gates)
+    }
+
+    fn values(vs: &[(&str, Value)]) -> HashMap<String, Value> {
+        vs.iter().map(|(n, v)| (n.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn pow5() {
+        let x = pf_var("x");
+        let x2 = term![PF_MUL; x.clone(), x.clone()];
+        let x5 = term![PF_MUL; x2.clone(), x2, x.clone()];
+        let t = term![Op::Eq; x5, pf_var("y")];
+        let env = values(&[
+            ("x", Value::Field(field().new_v(3))),
+            ("y", Value::Field(field().new_v(243))),
+        ]);
+
+        let plain = lower(t.clone(), vec![pf_var("y")], &[]);
+        plain.check_all(&env);
+        assert_eq!(plain.gate_counts().get("pow5"), None);
+
+        let custom = lower(t, vec![pf_var("y")], &[CustomGate::Pow5]);
+        custom.check_all(&env);
+        assert_eq!(custom.gate_counts().get("pow5"), Some(&1));
+        assert!(custom.rows().len() < plain.rows().len());
+        assert_eq!(custom.public().len(), 1);
+    }
+
+    #[test]
+    fn ec_add() {
+        let sub = |a: &Term, b: &Term| term![PF_ADD; a.clone(), term![PF_NEG; b.clone()]];
+        let [x1, y1, x2, y2] = ["x1", "y1", "x2", "y2"].map(pf_var);
+        let l = term![PF_MUL; sub(&y2, &y1), term![PF_RECIP; sub(&x2, &x1)]];
+        let x3 = sub(&sub(&term![PF_MUL; l.clone(), l.clone()], &x1), &x2);
+        let y3 = sub(&term![PF_MUL; l, sub(&x1, &x3)], &y1);
+        let t = term![AND; term![Op::Eq; x3, pf_var("x3")], term![Op::Eq; y3, pf_var("y3")]];
+        let public = vec![pf_var("x3"), pf_var("y3")];
+
+        let f = field();
+        let [x1, y1, x2, y2] = [5, 7, 11, 2].map(|i| f.new_v(i));
+        let l = (y2.clone() - &y1) * &(x2.clone() - &x1).recip();
+        let x3 = l.clone() * &l - &x1 - &x2;
+        let y3 = l * &(x1.clone() - &x3) - &y1;
+        let env = values(&[
+            ("x1", Value::Field(x1)),
+            ("y1", Value::Field(y1)),
+            ("x2", Value::Field(x2)),
+            ("y2", Value::Field(y2)),
+            ("x3", Value::Field(x3)),
+            ("y3", Value::Field(y3)),
+        ]);
+
+        let plain = lower(t.clone(), public.clone(), &[]);
+        plain.check_all(&env);
+        let custom = lower(t.clone(), public.clone(), &[CustomGate::EcAdd]);
+        custom.check_all(&env);
+        assert_eq!(custom.gate_counts().get("ec-add"), Some(&1));
+        assert!(custom.rows().len() < plain.rows().len());
+
+        // the gate can't divide by zero any other way
+        let mut opt = circ_opt::CircOpt::default();
+        opt.r1cs.div_by_zero = FieldDivByZero::Zero;
+        let cs = Computation::from_constraint_system_parts(vec![t.clone()], public.clone());
+        let zero = to_plonkish(&cs, &CircCfg::from(opt), &[CustomGate::EcAdd]);
+        zero.check_all(&env);
+        assert_eq!(zero.gate_counts().get("ec-add"), None);
+    }
+
+    #[test]
+    fn bools_and_witness() {
+        let a = var("a".to_owned(), Sort::Bool);
+        let b = var("b".to_owned(), Sort::Bool);
+        let x = pf_var("x");
+        let nz = term![Op::Not; term![Op::Eq; x.clone(), pf_lit(field().new_v(0))]];
+        let pick =
+            term![Op::Ite; term![XOR; a.clone(), b.clone()], x.clone(), pf_lit(field().new_v(9))];
+        let t = term![AND;
+            term![OR; a, b],
+            nz,
+            term![Op::Eq; pick, term![PF_DIV; pf_var("y"), x]]
+        ];
+        let cs = Computation::from_constraint_system_parts(vec![t], vec![pf_var("y")]);
+        let p = to_plonkish(&cs, &CircCfg::default(), &CustomGate::ALL);
+        let env = values(&[
+            ("a", Value::Bool(true)),
+            ("b", Value::Bool(false)),
+            ("x", Value::Field(field().new_v(4))),
+            ("y", Value::Field(field().new_v(16))),
+        ]);
+        p.check_all(&env);
+
+        let pd = p.finalize(&cs);
+        let w = pd.witness(&env);
+        assert_eq!(w.len(), pd.plonkish.n_wires());
+        pd.check_all(&env);
+        let bad = values(&[
+            ("a", Value::Bool(true)),
+            ("b", Value::Bool(false)),
+            ("x", Value::Field(field().new_v(4))),
+            ("y", Value::Field(field().new_v(15))),
+        ]);
+        assert!(pd.plonkish.check_values(&pd.witness(&bad)).is_err());
+
+        let json = pd.plonkish.to_json();
+        assert_eq!(json["rows"], pd.plonkish.rows().len());
+        assert_eq!(json["instance"][0]["name"], "y");
+        assert_eq!(json["gates"].as_array().unwrap().len(), 3);
+    }
+
+    fn lower_dividing_by_zero(t: Term, semantics: FieldDivByZero) -> Plonkish {
+        let mut opt = circ_opt::CircOpt::default();
+        opt.r1cs.div_by_zero = semantics;
+        let cs = Computation::from_constraint_system_parts(vec![t], vec![pf_var("y")]);
+        to_plonkish(&cs, &CircCfg::from(opt), &[])
+    }
+
+    #[test]
+    fn div_by_zero() {
+        let f = field();
+        // y = x / d
+        let t = term![Op::Eq; pf_var("y"), term![PF_DIV; pf_var("x"), pf_var("d")]];
+        let env = |x: u64, d: u64, y: u64| {
+            values(&[
+                ("x", Value::Field(f.new_v(x))),
+                ("d", Value::Field(f.new_v(d))),
+                ("y", Value::Field(f.new_v(y))),
+            ])
+        };
+
+        let p = lower_dividing_by_zero(t.clone(), FieldDivByZero::Zero);
+        p.check_all(&env(6, 3, 2));
+        p.check_all(&env(6, 0, 0));
+        let mut w = p.eval_wires(&env(6, 0, 0));
+        let y = p.public()[0];
+        w[y] = f.new_v(5);
+        assert!(p.check_values(&w).is_err());
+
+        let p = lower_dividing_by_zero(t, FieldDivByZero::NonDet);
+        p.check_all(&env(6, 3, 2));
+        p.check_all(&env(6, 0, 0));
+        let mut w = p.eval_wires(&env(6, 3, 2));
+        let y = p.public()[0];
+        w[y] = f.new_v(5);
+        assert!(p.check_values(&w).is_err());
+
+        // 1/0 is 0
+        let t = term![Op::Eq; pf_var("y"), term![PF_RECIP; pf_var("d")]];
+        let p = lower_dividing_by_zero(t, FieldDivByZero::Zero);
+        p.check_all(&env(0, 0, 0));
+    }
+
+    #[test]
+    fn public_bools() {
+        let a = var("a".to_owned(), Sort::Bool);
+        let zero = pf_lit(field().new_v(0));
+        // y = if a then x else 0
+        let t = term![Op::Eq; pf_var("y"), term![Op::Ite; a.clone(), pf_var("x"), zero]];
+        let cs = Computation::from_constraint_system_parts(vec![t], vec![a]);
+        let p = to_plonkish(&cs, &CircCfg::default(), &[]);
+        let env = values(&[
+            ("a", Value::Bool(true)),
+            ("x", Value::Field(field().new_v(0))),
+            ("y", Value::Field(field().new_v(0))),
+        ]);
+        let mut w = p.eval_wires(&env);
+        assert!(p.check_values(&w).is_ok());
+        // with x = 0, only booleanity rules out a public "bool" of 2
+        let a = p.public()[0];
+        w[a] = field().new_v(2);
+        assert!(p.check_values(&w).is_err());
+    }
+
+    #[test]
+    fn public_bool_then_field() {
+        let a = var("a".to_owned(), Sort::Bool);
+        let zero = pf_lit(field().new_v(0));
+        let t = term![Op::Eq; pf_var("y"), term![Op::Ite; a.clone(), pf_var("x"), zero]];
+        let cs = Computation::from_constraint_system_parts(vec![t], vec![a, pf_var("y")]);
+        let p = to_plonkish(&cs, &CircCfg::default(), &[]);
+        assert_eq!(p.public().len(), 2);
+        for (i, w) in p.public().iter().enumerate() {
+            assert_eq!(p.rows()[i].cells[0], Some(*w));
+        }
+        let json = p.to_json();
+        for (i, name) in ["a", "y"].iter().enumerate() {
+            assert_eq!(json["instance"][i]["name"], *name);
+            assert_eq!(json["instance"][i]["cell"]["row"], i);
+        }
+        let env = values(&[
+            ("a", Value::Bool(true)),
+            ("x", Value::Field(field().new_v(3))),
+            ("y", Value::Field(field().new_v(3))),
+        ]);
+        let mut w = p.eval_wires(&env);
+        assert!(p.check_values(&w).is_ok());
+        w[p.public()[0]] = field().new_v(2);
+        assert!(p.check_values(&w).is_err());
+    }
+
+    #[test]
+    fn gate_names() {
+        assert_eq!("ec-add".parse(), Ok(CustomGate::EcAdd));
+        assert!("poseidon".parse::<CustomGate>().is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! A persistent, on-disk cache of serialized R1CS artifacts.
+//!
+//! Compiling a large relation down to [super::ProverData], [super::VerifierData] and the
+//! proof-system parameters can take minutes. This cache stores the serialized artifacts under a
+//! key that summarizes everything the compilation depends on:
+//! * the contents of every source file (e.g., the transitive import closure of a Z# program),
+//! * the CirC options, and
+//! * the optimization pass list.
+//!
+//! The key is a SHA-256 digest. The cache directory records the build of CirC that wrote it (its
+//! git commit and compiler version); if that changes, the whole cache is invalidated.
+
+use log::debug;
+use sha2::{Digest, Sha256};
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Bump this whenever the serialized format of a cached artifact changes.
+pub const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// The name of the file (in the cache directory) that records the cache version.
+const VERSION_FILE: &str = "VERSION";
+
+fn version_string() -> String {
+    format!(
+        "circ {} ({}) cache {}",
+        env!("CARGO_PKG_VERSION"),
+        env!("CIRC_BUILD_ID"),
+        CACHE_FORMAT_VERSION
+    )
+}
+
+/// A key identifying one compilation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey([u8; 32]);
+
+impl Display for CacheKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for b in &self.0 {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds a [CacheKey] from the inputs to a compilation.
+pub struct CacheKeyBuilder {
+    hasher: Sha256,
+}
+
+impl Default for CacheKeyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheKeyBuilder {
+    /// Create a new builder. The key always depends on the cache version.
+    pub fn new() -> Self {
+        let mut this = Self {
+            hasher: Sha256::new(),
+        };
+        this.bytes(version_string().as_bytes());
+        this
+    }
+
+    /// Hash `bytes`, prefixed by their length (so that consecutive items can't run together).
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.hasher.update((bytes.len() as u64).to_le_bytes());
+        self.hasher.update(bytes);
+    }
+
+    /// Add a source file: both its canonical path and its contents contribute to the key.
+    pub fn file(&mut self, path: &Path) -> io::Result<&mut Self> {
+        let path = path.canonicalize()?;
+        let contents = fs::read(&path)?;
+        debug!("Cache key: file {}", path.display());
+        self.bytes(b"file");
+        self.bytes(path.to_string_lossy().as_bytes());
+        self.bytes(&contents);
+        Ok(self)
+    }
+
+    /// Add a labeled string, e.g., the [Debug] rendering of some options.
+    pub fn str(&mut self, label: &str, value: &str) -> &mut Self {
+        debug!("Cache key: {} = {}", label, value);
+        self.bytes(label.as_bytes());
+        self.bytes(value.as_bytes());
+        self
+    }
+
+    /// Produce the key.
+    pub fn finish(&self) -> CacheKey {
+        CacheKey(self.hasher.clone().finalize().into())
+    }
+}
+
+/// A directory of cached artifacts, one sub-directory per [CacheKey].
+#[derive(Debug)]
+pub struct ArtifactCache {
+    dir: PathBuf,
+}
+
+impl ArtifactCache {
+    /// Open (creating, if needed) the cache in `dir`.
+    ///
+    /// If the cache was written by a different version, its contents are discarded.
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let version_path = dir.join(VERSION_FILE);
+        let version = version_string();
+        let current = fs::read_to_string(&version_path).ok();
+        if current.as_deref() != Some(version.as_str()) {
+            debug!(
+                "Invalidating cache {}: version {:?}, expected {:?}",
+                dir.display(),
+                current,
+                version
+            );
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    fs::remove_dir_all(&path)?;
+                } else {
+                    fs::remove_file(&path)?;
+                }
+            }
+            fs::write(&version_path, version)?;
+        }
+        Ok(Self { dir })
+    }
+
+    fn entry(&self, key: CacheKey) -> PathBuf {
+        self.dir.join(key.to_string())
+    }
+
+    /// If every named artifact is cached under `key`, copy each to its destination path and
+    /// return `true`. Otherwise, copy nothing and return `false`.
+    pub fn restore(&self, key: CacheKey, artifacts: &[(&str, PathBuf)]) -> io::Result<bool> {
+        let entry = self.entry(key);
+        if !artifacts.iter().all(|(name, _)| entry.join(name).is_file()) {
+            debug!("Cache miss: {}", key);
+            return Ok(false);
+        }
+        debug!("Cache hit: {}", key);
+        for (name, dst) in artifacts {
+            fs::copy(entry.join(name), dst)?;
+        }
+        Ok(true)
+    }
+
+    /// Store the named artifacts (read from their paths) under `key`.
+    ///
+    /// The entry is written to a temporary directory first, so a partially-written entry is
+    /// never visible.
+    pub fn store(&self, key: CacheKey, artifacts: &[(&str, PathBuf)]) -> io::Result<()> {
+        let entry = self.entry(key);
+        let tmp = self.dir.join(format!("{}.tmp", key));
+        if tmp.exists() {
+            fs::remove_dir_all(&tmp)?;
+        }
+        fs::create_dir_all(&tmp)?;
+        for (name, src) in artifacts {
+            fs::copy(src, tmp.join(name))?;
+        }
+        if entry.exists() {
+            fs::remove_dir_all(&entry)?;
+        }
+        fs::rename(&tmp, &entry)?;
+        debug!("Cache store: {}", key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let p =
+            std::env::temp_dir().join(format!("circ_cache_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&p);
+        fs::create_dir_all(&p).unwrap();
+        p
+    }
+
+    #[test]
+    fn key_depends_on_files_and_options() {
+        let dir = scratch("key");
+        let a = dir.join("a.zok");
+        fs::write(&a, "def main() -> field { return 1 }").unwrap();
+        let k1 = CacheKeyBuilder::new()
+            .file(&a)
+            .unwrap()
+            .str("opts", "x")
+            .finish();
+        let k2 = CacheKeyBuilder::new()
+            .file(&a)
+            .unwrap()
+            .str("opts", "x")
+            .finish();
+        let k3 = CacheKeyBuilder::new()
+            .file(&a)
+            .unwrap()
+            .str("opts", "y")
+            .finish();
+        assert_eq!(k1, k2);
+        assert_ne!(k1, k3);
+        fs::write(&a, "def main() -> field { return 2 }").unwrap();
+        let k4 = CacheKeyBuilder::new()
+            .file(&a)
+            .unwrap()
+            .str("opts", "x")
+            .finish();
+        assert_ne!(k1, k4);
+        // The same file, named differently
+        let k5 = CacheKeyBuilder::new()
+            .file(&dir.join(".").join("a.zok"))
+            .unwrap()
+            .str("opts", "x")
+            .finish();
+        assert_eq!(k4, k5);
+        assert_eq!(k4.to_string().len(), 64);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn store_restore_invalidate() {
+        let dir = scratch("store");
+        let cache_dir = dir.join("cache");
+        let src = dir.join("P");
+        let dst = dir.join("P.out");
+        fs::write(&src, b"prover data").unwrap();
+        let key = CacheKeyBuilder::new().str("k", "v").finish();
+
+        let cache = ArtifactCache::open(&cache_dir).unwrap();
+        assert!(!cache.restore(key, &[("P", dst.clone())]).unwrap());
+        cache.store(key, &[("P", src)]).unwrap();
+        assert!(cache.restore(key, &[("P", dst.clone())]).unwrap());
+        assert_eq!(fs::read(&dst).unwrap(), b"prover data");
+
+        // A version change discards the cache.
+        fs::write(cache_dir.join(VERSION_FILE), "some other version").unwrap();
+        let cache = ArtifactCache::open(&cache_dir).unwrap();
+        assert!(!cache.restore(key, &[("P", dst)]).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
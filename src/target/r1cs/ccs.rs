@@ -0,0 +1,198 @@
+//! Customizable constraint systems
+//!
+//! A [CCS](https://eprint.iacr.org/2023/552) generalizes R1CS, Plonkish, and AIR. It is satisfied
+//! by `z = (w, 1, x)` when `sum_i c_i * hadamard_{j in S_i} (M_j z) = 0`. An R1CS `Az * Bz = Cz` is
+//! the CCS with matrices `[A, B, C]`, multisets `[{0, 1}, {2}]`, and constants `[1, -1]`.
+//!
+//! The column layout of `z` (witness, then the constant, then the instance) matches Spartan's.
+
+use super::*;
+
+use std::path::Path;
+
+/// A sparse matrix over a prime field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SparseMatrix {
+    /// Number of rows
+    pub n_rows: usize,
+    /// Number of columns
+    pub n_cols: usize,
+    /// Non-zero entries: (row, column, value)
+    pub entries: Vec<(usize, usize, FieldV)>,
+}
+
+impl SparseMatrix {
+    /// Compute `Mz`.
+    pub fn mul_vec(&self, z: &[FieldV]) -> Vec<FieldV> {
+        assert_eq!(z.len(), self.n_cols, "wrong vector length");
+        let zero = z[0].ty().zero();
+        let mut out = vec![zero; self.n_rows];
+        for (r, c, v) in &self.entries {
+            out[*r] += &(z[*c].clone() * v);
+        }
+        out
+    }
+}
+
+/// A customizable constraint system
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ccs {
+    /// The field
+    pub field: FieldT,
+    /// The matrices `M_j`
+    pub matrices: Vec<SparseMatrix>,
+    /// The multisets `S_i` of matrix indices
+    pub multisets: Vec<Vec<usize>>,
+    /// The constants `c_i`
+    pub constants: Vec<FieldV>,
+    /// The R1CS variables in the witness part of `z`, in order
+    pub witness: Vec<Var>,
+    /// The R1CS variables in the instance part of `z`, in order
+    pub public: Vec<Var>,
+}
+
+impl Ccs {
+    /// The CCS for an R1CS. Only instance and final witness variables are supported.
+    pub fn from_r1cs(r1cs: &R1csFinal) -> Self {
+        let mut witness = Vec::new();
+        let mut public = Vec::new();
+        for v in &r1cs.vars {
+            match v.ty() {
+                VarType::Inst => public.push(*v),
+                VarType::FinalWit => witness.push(*v),
+                ty => panic!("CCS export does not support {:?} variables", ty),
+            }
+        }
+        let const_col = witness.len();
+        let columns: HashMap<Var, usize> = witness
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (*v, i))
+            .chain(
+                public
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (*v, const_col + 1 + i)),
+            )
+            .collect();
+        let n_rows = r1cs.constraints.len();
+        let n_cols = witness.len() + 1 + public.len();
+        let mut matrices = vec![
+            SparseMatrix {
+                n_rows,
+                n_cols,
+                entries: Vec::new(),
+            };
+            3
+        ];
+        for (row, (a, b, c)) in r1cs.constraints.iter().enumerate() {
+            for (m, lc) in matrices.iter_mut().zip([a, b, c]) {
+                if !lc.constant.is_zero() {
+                    m.entries.push((row, const_col, lc.constant.clone()));
+                }
+                let mut entries: Vec<(usize, usize, FieldV)> = lc
+                    .monomials
+                    .iter()
+                    .map(|(v, coeff)| (row, columns[v], coeff.clone()))
+                    .collect();
+                entries.sort_by_key(|e| e.1);
+                m.entries.extend(entries);
+            }
+        }
+        let one = r1cs.field.new_v(1);
+        Ccs {
+            field: r1cs.field.clone(),
+            matrices,
+            multisets: vec![vec![0, 1], vec![2]],
+            constants: vec![one.clone(), -one],
+            witness,
+            public,
+        }
+    }
+
+    /// The number of constraints
+    pub fn n_rows(&self) -> usize {
+        self.matrices.first().map_or(0, |m| m.n_rows)
+    }
+
+    /// The length of `z`
+    pub fn n_cols(&self) -> usize {
+        self.witness.len() + 1 + self.public.len()
+    }
+
+    /// The index of the constant column in `z`
+    pub fn const_col(&self) -> usize {
+        self.witness.len()
+    }
+
+    /// Assemble `z` from an R1CS assignment.
+    pub fn z(&self, values: &HashMap<Var, FieldV>) -> Vec<FieldV> {
+        let get = |v: &Var| values.get(v).expect("missing R1CS value").clone();
+        self.witness
+            .iter()
+            .map(get)
+            .chain(std::iter::once(self.field.new_v(1)))
+            .chain(self.public.iter().map(get))
+            .collect()
+    }
+
+    /// Check `z`, returning the first unsatisfied row.
+    pub fn check(&self, z: &[FieldV]) -> Result<(), usize> {
+        let products: Vec<Vec<FieldV>> = self.matrices.iter().map(|m| m.mul_vec(z)).collect();
+        for row in 0..self.n_rows() {
+            let mut acc = self.field.zero();
+            for (set, c) in self.multisets.iter().zip(&self.constants) {
+                let mut term = c.clone();
+                for j in set {
+                    term *= &products[*j][row];
+                }
+                acc += &term;
+            }
+            if !acc.is_zero() {
+                return Err(row);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write this system as JSON.
+    pub fn write_json(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer(file, self).map_err(std::io::Error::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cfg::CircCfg;
+    use crate::ir::proof::Constraints;
+    use crate::target::r1cs::trans::to_r1cs;
+
+    #[test]
+    fn from_r1cs() {
+        let cfg = CircCfg::default();
+        let field = cfg.field().clone();
+        let x = var("x".to_owned(), Sort::Field(field.clone()));
+        let y = var("y".to_owned(), Sort::Field(field.clone()));
+        let cube = term![PF_MUL; x.clone(), x.clone(), x];
+        let t = term![EQ; term![PF_ADD; cube, pf_lit(field.new_v(5))], y.clone()];
+        let cs = Computation::from_constraint_system_parts(vec![t], vec![y]);
+        let (pd, _) = to_r1cs(&cs, &cfg).finalize(&cs);
+        let ccs = Ccs::from_r1cs(&pd.r1cs);
+        assert_eq!(ccs.n_rows(), pd.r1cs.constraints.len());
+        assert_eq!(ccs.public.len(), 1);
+
+        let inputs: HashMap<String, Value> = vec![
+            ("x".to_owned(), Value::Field(field.new_v(3))),
+            ("y".to_owned(), Value::Field(field.new_v(32))),
+        ]
+        .into_iter()
+        .collect();
+        let mut z = ccs.z(&pd.extend_r1cs_witness(&inputs));
+        assert_eq!(ccs.check(&z), Ok(()));
+        let last = z.len() - 1;
+        z[last] = field.new_v(33);
+        assert!(ccs.check(&z).is_err());
+    }
+}
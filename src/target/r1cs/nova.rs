@@ -0,0 +1,546 @@
+//! Nova-style folding of relaxed R1CS, with a final Spartan proof
+//!
+//! Proving one relation N times (e.g., a step function) normally takes N proofs. Folding
+//! ([Nova](https://eprint.iacr.org/2021/370)) combines N instance-witness pairs into one
+//! *relaxed* pair, which needs one proof.
+//!
+//! A relaxed R1CS instance `(u, x, comm_w, comm_e)` is satisfied by `(W, E)` when `comm_w` and
+//! `comm_e` commit to `W` and `E`, and `Az * Bz = u Cz + E` for `z = (W, u, x)` (the [Ccs] layout,
+//! with `u` in the constant column). An ordinary instance is relaxed with `u = 1` and `E = 0`.
+//! To fold two pairs, the prover commits to the cross term
+//! `T = Az1 * Bz2 + Az2 * Bz1 - u1 Cz2 - u2 Cz1`, gets a challenge `r` from a Fiat-Shamir
+//! transcript, and takes `W = W1 + r W2`, `E = E1 + r T + r^2 E2`. The verifier folds the
+//! instances the same way, using only the commitments.
+//!
+//! Commitments are Pedersen commitments on the Pallas curve, whose scalar field is the field of
+//! Spartan's pasta backend, so they are homomorphic over the R1CS field. The generators are hashed
+//! to the curve from a public label, so setup is transparent.
+//!
+//! The Fiat-Shamir transcript starts with the relation (its matrices) and the commitment key.
+//!
+//! The prover finishes with a Spartan proof ([prove]) that the folded instance is satisfied. The
+//! verifier ([verify]) recomputes the folded instance from the [FoldingProof] and checks that
+//! proof; it never sees a witness. Spartan commits to `(W, E)` with its own commitments, so the
+//! proof does not show that they open `comm_w` and `comm_e`.
+
+use super::ccs::Ccs;
+use super::spartan::int_to_scalar;
+use super::*;
+
+use libspartan::transcript::Keccak256Transcript;
+use libspartan::{InputsAssignment, Instance, NIZKGens, VarsAssignment, NIZK};
+use merlin::Transcript;
+use pasta_curves::arithmetic::CurveExt;
+use pasta_curves::group::ff::PrimeField;
+use pasta_curves::group::{Group, GroupEncoding};
+use pasta_curves::pallas;
+use rug::integer::Order;
+
+/// A Pedersen commitment: a compressed Pallas point
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commitment([u8; 32]);
+
+impl Commitment {
+    fn new(p: pallas::Point) -> Self {
+        Commitment(p.to_bytes())
+    }
+
+    /// The point, if this is a valid encoding of one
+    fn decode(&self) -> Option<pallas::Point> {
+        pallas::Point::from_bytes(&self.0).into()
+    }
+
+    fn point(&self) -> pallas::Point {
+        self.decode().expect("invalid commitment")
+    }
+}
+
+/// The Pallas scalar for a field element
+fn to_scalar(v: &FieldV) -> pallas::Scalar {
+    let mut repr = [0u8; 32];
+    let digits = v.i().to_digits::<u8>(Order::Lsf);
+    repr[..digits.len()].copy_from_slice(&digits);
+    Option::from(pallas::Scalar::from_repr(repr)).expect("field element out of range")
+}
+
+/// Is `field` the Pallas scalar field (the field of Spartan's pasta backend)?
+fn is_pallas_scalar_field(field: &FieldT) -> bool {
+    let modulus = pallas::Scalar::MODULUS.trim_start_matches("0x");
+    field.modulus() == &Integer::from_str_radix(modulus, 16).unwrap()
+}
+
+/// Public parameters for committing to vectors
+#[derive(Debug, Clone)]
+pub struct CommitmentKey {
+    /// Independent Pallas generators
+    gens: Vec<pallas::Point>,
+}
+
+impl CommitmentKey {
+    /// Derive a key for vectors of length up to `n` from `label`.
+    pub fn setup(n: usize, label: &[u8]) -> Self {
+        let hash = pallas::Point::hash_to_curve("circ_nova_setup");
+        let gens = (0..n as u64)
+            .map(|i| hash(&[label, &i.to_le_bytes()[..]].concat()))
+            .collect();
+        CommitmentKey { gens }
+    }
+
+    /// The longest vector this key can commit to
+    pub fn len(&self) -> usize {
+        self.gens.len()
+    }
+
+    /// Is this key for empty vectors only?
+    pub fn is_empty(&self) -> bool {
+        self.gens.is_empty()
+    }
+
+    /// Commit to `v`.
+    pub fn commit(&self, v: &[FieldV]) -> Commitment {
+        assert!(v.len() <= self.gens.len(), "commitment key is too short");
+        let c = v
+            .par_iter()
+            .zip(&self.gens[..v.len()])
+            .filter(|(v, _)| !v.is_zero())
+            .map(|(v, g)| g * to_scalar(v))
+            .reduce(pallas::Point::identity, |a, b| a + b);
+        Commitment::new(c)
+    }
+
+    /// The commitment to `a + b`, given commitments to `a` and `b`
+    fn add(&self, a: &Commitment, b: &Commitment) -> Commitment {
+        Commitment::new(a.point() + b.point())
+    }
+
+    /// The commitment to `ra`, given a commitment to `a`
+    fn scale(&self, a: &Commitment, r: &FieldV) -> Commitment {
+        Commitment::new(a.point() * to_scalar(r))
+    }
+}
+
+/// A relaxed R1CS instance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelaxedInstance {
+    /// The scalar in the constant column
+    pub u: FieldV,
+    /// The public inputs
+    pub x: Vec<FieldV>,
+    /// A commitment to the witness
+    pub comm_w: Commitment,
+    /// A commitment to the error vector
+    pub comm_e: Commitment,
+}
+
+/// A relaxed R1CS witness
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelaxedWitness {
+    /// The witness
+    pub w: Vec<FieldV>,
+    /// The error vector
+    pub e: Vec<FieldV>,
+}
+
+/// The instances that were folded, and the commitments to the cross terms
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FoldingProof {
+    /// The (unrelaxed) instances
+    pub instances: Vec<RelaxedInstance>,
+    /// One cross-term commitment per fold
+    pub cross_terms: Vec<Commitment>,
+}
+
+/// A transcript that starts with the relation and the commitment key.
+fn new_transcript(ccs: &Ccs, key: &CommitmentKey) -> Transcript {
+    let mut t = Transcript::new(b"circ_nova");
+    for n in [ccs.n_rows(), ccs.witness.len(), ccs.public.len()] {
+        t.append_u64(b"shape", n as u64);
+    }
+    for m in &ccs.matrices {
+        t.append_u64(b"entries", m.entries.len() as u64);
+        for (r, c, v) in &m.entries {
+            t.append_u64(b"row", *r as u64);
+            t.append_u64(b"col", *c as u64);
+            t.append_message(b"val", v.i().to_string_radix(16).as_bytes());
+        }
+    }
+    for (set, c) in ccs.multisets.iter().zip(&ccs.constants) {
+        for j in set {
+            t.append_u64(b"multiset", *j as u64);
+        }
+        t.append_message(b"constant", c.i().to_string_radix(16).as_bytes());
+    }
+    for g in &key.gens {
+        t.append_message(b"gen", &g.to_bytes());
+    }
+    t
+}
+
+fn absorb(t: &mut Transcript, inst: &RelaxedInstance) {
+    t.append_message(b"u", inst.u.i().to_string_radix(16).as_bytes());
+    for x in &inst.x {
+        t.append_message(b"x", x.i().to_string_radix(16).as_bytes());
+    }
+    t.append_message(b"comm_w", &inst.comm_w.0);
+    t.append_message(b"comm_e", &inst.comm_e.0);
+}
+
+fn challenge(t: &mut Transcript, field: &FieldT) -> FieldV {
+    let mut bytes = [0u8; 64];
+    t.challenge_bytes(b"r", &mut bytes);
+    field.new_v(Integer::from_digits(&bytes, Order::Lsf) % field.modulus())
+}
+
+/// `z = (W, u, x)`
+fn relaxed_z(w: &[FieldV], u: &FieldV, x: &[FieldV]) -> Vec<FieldV> {
+    w.iter()
+        .chain(std::iter::once(u))
+        .chain(x)
+        .cloned()
+        .collect()
+}
+
+/// `(Az, Bz, Cz)`
+fn products(ccs: &Ccs, z: &[FieldV]) -> [Vec<FieldV>; 3] {
+    [0, 1, 2].map(|i| ccs.matrices[i].mul_vec(z))
+}
+
+fn fold_instance(
+    key: &CommitmentKey,
+    a: &RelaxedInstance,
+    b: &RelaxedInstance,
+    comm_t: &Commitment,
+    r: &FieldV,
+) -> RelaxedInstance {
+    let r2 = r.clone() * r;
+    RelaxedInstance {
+        u: a.u.clone() + &(b.u.clone() * r),
+        x: a.x
+            .iter()
+            .zip(&b.x)
+            .map(|(a, b)| a.clone() + &(b.clone() * r))
+            .collect(),
+        comm_w: key.add(&a.comm_w, &key.scale(&b.comm_w, r)),
+        comm_e: key.add(
+            &key.add(&a.comm_e, &key.scale(comm_t, r)),
+            &key.scale(&b.comm_e, &r2),
+        ),
+    }
+}
+
+/// Folds instance-witness pairs for one relation into a running relaxed pair.
+pub struct Folder<'a> {
+    pd: &'a ProverData,
+    ccs: Ccs,
+    key: &'a CommitmentKey,
+    transcript: Transcript,
+    acc: Option<(RelaxedInstance, RelaxedWitness)>,
+    proof: FoldingProof,
+}
+
+impl<'a> Folder<'a> {
+    /// Start folding instances of `pd`'s relation.
+    pub fn new(pd: &'a ProverData, key: &'a CommitmentKey) -> Self {
+        let ccs = Ccs::from_r1cs(&pd.r1cs);
+        assert!(
+            is_pallas_scalar_field(&ccs.field),
+            "folding needs R1CS over the Pallas scalar field"
+        );
+        assert!(
+            key.len() >= ccs.witness.len().max(ccs.n_rows()),
+            "commitment key is too short for this relation"
+        );
+        Folder {
+            pd,
+            transcript: new_transcript(&ccs, key),
+            ccs,
+            key,
+            acc: None,
+            proof: FoldingProof::default(),
+        }
+    }
+
+    /// The relation, as a CCS
+    pub fn ccs(&self) -> &Ccs {
+        &self.ccs
+    }
+
+    /// Fold in the instance (and witness) computed from these inputs.
+    pub fn fold(&mut self, inputs: &HashMap<String, Value>) {
+        let values = self.pd.extend_r1cs_witness(inputs);
+        let z = self.ccs.z(&values);
+        if let Err(row) = self.ccs.check(&z) {
+            panic!("Folded instance does not satisfy constraint {}", row);
+        }
+        let field = &self.ccs.field;
+        let n_w = self.ccs.witness.len();
+        let wit = RelaxedWitness {
+            w: z[..n_w].to_vec(),
+            e: vec![field.zero(); self.ccs.n_rows()],
+        };
+        let inst = RelaxedInstance {
+            u: field.new_v(1),
+            x: z[n_w + 1..].to_vec(),
+            comm_w: self.key.commit(&wit.w),
+            comm_e: self.key.commit(&[]),
+        };
+        self.proof.instances.push(inst.clone());
+        let (acc_inst, acc_wit) = match self.acc.take() {
+            None => {
+                self.acc = Some((inst, wit));
+                return;
+            }
+            Some(acc) => acc,
+        };
+        // cross term
+        let z1 = relaxed_z(&acc_wit.w, &acc_inst.u, &acc_inst.x);
+        let [a1, b1, c1] = products(&self.ccs, &z1);
+        let [a2, b2, c2] = products(&self.ccs, &z);
+        let t: Vec<FieldV> = (0..self.ccs.n_rows())
+            .map(|i| {
+                a1[i].clone() * &b2[i] + &(a2[i].clone() * &b1[i])
+                    - &(c2[i].clone() * &acc_inst.u)
+                    - &(c1[i].clone() * &inst.u)
+            })
+            .collect();
+        let comm_t = self.key.commit(&t);
+        absorb(&mut self.transcript, &acc_inst);
+        absorb(&mut self.transcript, &inst);
+        self.transcript.append_message(b"comm_t", &comm_t.0);
+        let r = challenge(&mut self.transcript, field);
+        let r2 = r.clone() * &r;
+        let folded_wit = RelaxedWitness {
+            w: acc_wit
+                .w
+                .iter()
+                .zip(&wit.w)
+                .map(|(a, b)| a.clone() + &(b.clone() * &r))
+                .collect(),
+            e: (0..self.ccs.n_rows())
+                .map(|i| acc_wit.e[i].clone() + &(t[i].clone() * &r) + &(wit.e[i].clone() * &r2))
+                .collect(),
+        };
+        let folded_inst = fold_instance(self.key, &acc_inst, &inst, &comm_t, &r);
+        self.proof.cross_terms.push(comm_t);
+        self.acc = Some((folded_inst, folded_wit));
+    }
+
+    /// The folded pair, and a proof that it folds the instances.
+    pub fn finish(self) -> (RelaxedInstance, RelaxedWitness, FoldingProof) {
+        let (inst, wit) = self.acc.expect("nothing was folded");
+        (inst, wit, self.proof)
+    }
+}
+
+/// Recompute the folded instance from a [FoldingProof], as a verifier.
+pub fn fold_instances(
+    ccs: &Ccs,
+    key: &CommitmentKey,
+    proof: &FoldingProof,
+) -> Result<RelaxedInstance, String> {
+    if !is_pallas_scalar_field(&ccs.field) {
+        return Err("folding needs R1CS over the Pallas scalar field".into());
+    }
+    let fresh_e = key.commit(&[]);
+    let one = ccs.field.new_v(1);
+    let mut instances = proof.instances.iter();
+    let mut acc = instances.next().ok_or("no instances")?.clone();
+    if proof.cross_terms.len() != proof.instances.len() - 1 {
+        return Err("wrong number of cross terms".into());
+    }
+    let mut transcript = new_transcript(ccs, key);
+    for inst in proof.instances.iter() {
+        if inst.u != one || inst.comm_e != fresh_e || inst.x.len() != ccs.public.len() {
+            return Err("a folded instance is not a fresh R1CS instance".into());
+        }
+        if inst.comm_w.decode().is_none() {
+            return Err("a witness commitment is not a curve point".into());
+        }
+    }
+    if proof.cross_terms.iter().any(|c| c.decode().is_none()) {
+        return Err("a cross-term commitment is not a curve point".into());
+    }
+    for (inst, comm_t) in instances.zip(&proof.cross_terms) {
+        absorb(&mut transcript, &acc);
+        absorb(&mut transcript, inst);
+        transcript.append_message(b"comm_t", &comm_t.0);
+        let r = challenge(&mut transcript, &ccs.field);
+        acc = fold_instance(key, &acc, inst, comm_t, &r);
+    }
+    Ok(acc)
+}
+
+/// Check that a relaxed pair is satisfied, including its commitments.
+pub fn check_relaxed(
+    ccs: &Ccs,
+    key: &CommitmentKey,
+    inst: &RelaxedInstance,
+    wit: &RelaxedWitness,
+) -> Result<(), String> {
+    if key.commit(&wit.w) != inst.comm_w || key.commit(&wit.e) != inst.comm_e {
+        return Err("commitments do not match the witness".into());
+    }
+    let z = relaxed_z(&wit.w, &inst.u, &inst.x);
+    let [a, b, c] = products(ccs, &z);
+    for i in 0..ccs.n_rows() {
+        if a[i].clone() * &b[i] != c[i].clone() * &inst.u + &wit.e[i] {
+            return Err(format!("relaxed constraint {} is not satisfied", i));
+        }
+    }
+    Ok(())
+}
+
+/// The Spartan instance for a relaxed instance with scalar `u`.
+///
+/// Spartan proves ordinary R1CS over `(vars, 1, inputs)`, so `E` becomes part of the witness and
+/// `u` is folded into the matrices: `A'z' = Az`, `B'z' = Bz`, and `C'z' = u Cz + E` for
+/// `z' = (W, E, 1, x)`.
+fn spartan_instance(ccs: &Ccs, u: &FieldV) -> (Instance, NIZKGens) {
+    let n_w = ccs.witness.len();
+    let num_cons = ccs.n_rows();
+    let num_vars = n_w + num_cons;
+    let num_inputs = ccs.public.len();
+    let col = |c: usize| {
+        if c < n_w {
+            c
+        } else {
+            num_vars + (c - n_w)
+        }
+    };
+    let mut ms: Vec<Vec<(usize, usize, [u8; 32])>> = vec![Vec::new(); 3];
+    for (j, m) in ccs.matrices[..3].iter().enumerate() {
+        for (r, c, v) in &m.entries {
+            let mut v = v.clone();
+            if *c == ccs.const_col() {
+                v *= u;
+            }
+            if j == 2 {
+                v *= u;
+            }
+            ms[j].push((*r, col(*c), int_to_scalar(&v.i()).to_bytes()));
+        }
+    }
+    let one = int_to_scalar(&Integer::from(1)).to_bytes();
+    for r in 0..num_cons {
+        ms[2].push((r, n_w + r, one));
+    }
+    let inst = Instance::new(num_cons, num_vars, num_inputs, &ms[0], &ms[1], &ms[2]).unwrap();
+    let gens = NIZKGens::new(num_cons, num_vars, num_inputs);
+    (inst, gens)
+}
+
+fn spartan_inputs(inst: &RelaxedInstance) -> InputsAssignment {
+    let x: Vec<[u8; 32]> = inst
+        .x
+        .iter()
+        .map(|x| int_to_scalar(&x.i()).to_bytes())
+        .collect();
+    InputsAssignment::new(&x).unwrap()
+}
+
+/// Prove (with Spartan) that a folded pair is satisfied.
+pub fn prove(ccs: &Ccs, inst: &RelaxedInstance, wit: &RelaxedWitness) -> NIZK {
+    assert!(ccs.n_rows() > 0, "No constraints");
+    let (s_inst, gens) = spartan_instance(ccs, &inst.u);
+    let vars: Vec<[u8; 32]> = wit
+        .w
+        .iter()
+        .chain(&wit.e)
+        .map(|v| int_to_scalar(&v.i()).to_bytes())
+        .collect();
+    let vars = VarsAssignment::new(&vars).unwrap();
+    let inputs = spartan_inputs(inst);
+    assert!(
+        s_inst.is_sat(&vars, &inputs).unwrap(),
+        "folded instance is not satisfied"
+    );
+    let mut transcript = Keccak256Transcript::new(b"circ_nova");
+    NIZK::prove(&s_inst, vars, &inputs, &gens, &mut transcript)
+}
+
+/// Check a folding: recompute the folded instance from `proof`, and check the Spartan proof
+/// `nizk` that it is satisfied. Returns the folded instance.
+pub fn verify(
+    ccs: &Ccs,
+    key: &CommitmentKey,
+    proof: &FoldingProof,
+    nizk: &NIZK,
+) -> Result<RelaxedInstance, String> {
+    let inst = fold_instances(ccs, key, proof)?;
+    let (s_inst, gens) = spartan_instance(ccs, &inst.u);
+    let mut transcript = Keccak256Transcript::new(b"circ_nova");
+    nizk.verify(&s_inst, &spartan_inputs(&inst), &mut transcript, &gens)
+        .map_err(|e| format!("{:?}", e))?;
+    Ok(inst)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cfg::{CircCfg, CircOpt};
+    use crate::ir::proof::Constraints;
+    use crate::target::r1cs::trans::to_r1cs;
+
+    #[test]
+    fn fold_three() {
+        let mut opt = CircOpt::default();
+        opt.field.custom_modulus =
+            "28948022309329048855892746252171976963363056481941647379679742748393362948097".into();
+        let cfg = CircCfg::from(opt);
+        let field = cfg.field().clone();
+        let x = var("x".to_owned(), Sort::Field(field.clone()));
+        let y = var("y".to_owned(), Sort::Field(field.clone()));
+        let t = term![EQ; term![PF_MUL; x.clone(), x.clone(), x], y.clone()];
+        let cs = Computation::from_constraint_system_parts(vec![t], vec![y]);
+        let (pd, _) = to_r1cs(&cs, &cfg).finalize(&cs);
+        let key = CommitmentKey::setup(8, b"test");
+
+        let all_inputs: Vec<HashMap<String, Value>> = (2..5u32)
+            .map(|i| {
+                vec![
+                    ("x".to_owned(), Value::Field(field.new_v(i))),
+                    ("y".to_owned(), Value::Field(field.new_v(i * i * i))),
+                ]
+                .into_iter()
+                .collect()
+            })
+            .collect();
+        let mut folder = Folder::new(&pd, &key);
+        for inputs in &all_inputs {
+            folder.fold(inputs);
+        }
+        let ccs = folder.ccs().clone();
+        let (inst, wit, proof) = folder.finish();
+        check_relaxed(&ccs, &key, &inst, &wit).unwrap();
+        assert_eq!(fold_instances(&ccs, &key, &proof), Ok(inst.clone()));
+
+        let mut bad = proof.clone();
+        bad.instances[1].x[0] = field.new_v(9);
+        assert_ne!(fold_instances(&ccs, &key, &bad), Ok(inst.clone()));
+
+        let nizk = prove(&ccs, &inst, &wit);
+        assert_eq!(verify(&ccs, &key, &proof, &nizk), Ok(inst.clone()));
+        assert!(verify(&ccs, &key, &bad, &nizk).is_err());
+
+        // a witness that doesn't open the commitments is rejected, even if it satisfies the
+        // relaxed relation
+        let mut bad = wit.clone();
+        bad.w[0] += &field.new_v(1);
+        let z = relaxed_z(&bad.w, &inst.u, &inst.x);
+        let [a, b, c] = products(&ccs, &z);
+        bad.e = (0..ccs.n_rows())
+            .map(|i| a[i].clone() * &b[i] - &(c[i].clone() * &inst.u))
+            .collect();
+        assert!(check_relaxed(&ccs, &key, &inst, &bad).is_err());
+
+        // the challenges depend on the commitment key
+        let other_key = CommitmentKey::setup(8, b"other");
+        let mut folder = Folder::new(&pd, &other_key);
+        for inputs in &all_inputs {
+            folder.fold(inputs);
+        }
+        let (_, _, other_proof) = folder.finish();
+        let other = fold_instances(&ccs, &other_key, &other_proof).unwrap();
+        assert_ne!(other.u, inst.u);
+    }
+}
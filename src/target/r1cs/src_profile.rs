@@ -0,0 +1,184 @@
+//! Attributing R1CS cost to source locations
+//!
+//! Enabled by `--r1cs-source-profile PREFIX`. The lowering charges the constraints and variables
+//! it creates for each term to that term's [SrcLoc]. A term without a location is charged to the
+//! location of its first located argument (and inherits it, so its users do too).
+//!
+//! Costs are written as:
+//! * `PREFIX.constraints.folded` and `PREFIX.vars.folded`: collapsed stacks, for flamegraph
+//!   tools (e.g., `inferno-flamegraph` or `flamegraph.pl`)
+//! * `PREFIX.annotated.txt`: each source file, with per-line costs
+
+use super::R1csStats;
+use crate::ir::term::src_loc::{self, SrcLoc};
+use crate::ir::term::Term;
+
+use fxhash::FxHashMap;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// The frame for costs with no known source location.
+const UNKNOWN: &str = "(no source)";
+
+/// Costs, by source location
+#[derive(Default)]
+pub struct SourceProfile {
+    costs: FxHashMap<Option<Rc<SrcLoc>>, R1csStats>,
+}
+
+impl SourceProfile {
+    /// The location to charge the cost of lowering `t` to.
+    pub fn loc(t: &Term) -> Option<Rc<SrcLoc>> {
+        src_loc::get(t).or_else(|| {
+            let c = t.cs().iter().find(|c| src_loc::get(c).is_some())?;
+            src_loc::inherit(c, t);
+            src_loc::get(t)
+        })
+    }
+
+    /// Charge `stats` to `loc`.
+    pub fn add(&mut self, loc: Option<Rc<SrcLoc>>, stats: &R1csStats) {
+        *self.costs.entry(loc).or_default() += stats;
+    }
+
+    /// Collapsed stacks (`frame;frame;file:line count`) for the given metric.
+    pub fn folded(&self, metric: impl Fn(&R1csStats) -> u32) -> String {
+        let mut stacks: BTreeMap<String, u64> = BTreeMap::new();
+        for (loc, stats) in &self.costs {
+            let n = metric(stats) as u64;
+            if n > 0 {
+                let frames = match loc {
+                    Some(loc) => loc
+                        .stack
+                        .iter()
+                        .cloned()
+                        .chain(std::iter::once(format!(
+                            "{}:{}",
+                            loc.file.display(),
+                            loc.line
+                        )))
+                        .collect::<Vec<_>>()
+                        .join(";"),
+                    None => UNKNOWN.to_owned(),
+                };
+                *stacks.entry(frames).or_default() += n;
+            }
+        }
+        let mut out = String::new();
+        for (frames, n) in stacks {
+            writeln!(&mut out, "{frames} {n}").unwrap();
+        }
+        out
+    }
+
+    /// Each source file, with the constraints and variables charged to each line.
+    pub fn annotated(&self) -> String {
+        let mut files: BTreeMap<&PathBuf, BTreeMap<usize, (u32, u32)>> = BTreeMap::new();
+        let mut unknown = (0, 0);
+        for (loc, stats) in &self.costs {
+            let entry = match loc {
+                Some(loc) => files
+                    .entry(&loc.file)
+                    .or_default()
+                    .entry(loc.line)
+                    .or_default(),
+                None => &mut unknown,
+            };
+            entry.0 += stats.n_constraints;
+            entry.1 += stats.n_vars;
+        }
+        let mut out = String::new();
+        for (file, lines) in files {
+            let cons: u32 = lines.values().map(|c| c.0).sum();
+            let vars: u32 = lines.values().map(|c| c.1).sum();
+            writeln!(
+                &mut out,
+                "==> {} ({} constraints, {} vars) <==",
+                file.display(),
+                cons,
+                vars
+            )
+            .unwrap();
+            writeln!(&mut out, "{:>10} {:>10}", "cons", "vars").unwrap();
+            match std::fs::read_to_string(file) {
+                Ok(text) => {
+                    for (i, src) in text.lines().enumerate() {
+                        let line = i + 1;
+                        match lines.get(&line) {
+                            Some((c, v)) => {
+                                writeln!(&mut out, "{c:>10} {v:>10} | {line:>5}: {src}")
+                            }
+                            None => writeln!(&mut out, "{:>10} {:>10} | {line:>5}: {src}", "", ""),
+                        }
+                        .unwrap();
+                    }
+                }
+                Err(_) => {
+                    for (line, (c, v)) in lines {
+                        writeln!(&mut out, "{c:>10} {v:>10} | {line:>5}").unwrap();
+                    }
+                }
+            }
+            writeln!(&mut out).unwrap();
+        }
+        if unknown != (0, 0) {
+            writeln!(
+                &mut out,
+                "{}: {} constraints, {} vars",
+                UNKNOWN, unknown.0, unknown.1
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    /// Write the profile to files starting with `prefix`.
+    pub fn write(&self, prefix: &str) -> std::io::Result<()> {
+        std::fs::write(
+            format!("{prefix}.constraints.folded"),
+            self.folded(|s| s.n_constraints),
+        )?;
+        std::fs::write(format!("{prefix}.vars.folded"), self.folded(|s| s.n_vars))?;
+        std::fs::write(format!("{prefix}.annotated.txt"), self.annotated())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cost(n_constraints: u32, n_vars: u32) -> R1csStats {
+        R1csStats {
+            n_constraints,
+            n_vars,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn folded_merges_stacks() {
+        let loc = |line: usize, stack: &[&str]| {
+            Some(Rc::new(SrcLoc {
+                file: "no/such/file.zok".into(),
+                line,
+                stack: stack.iter().map(|s| s.to_string()).collect(),
+            }))
+        };
+        let mut p = SourceProfile::default();
+        p.add(loc(3, &["main"]), &cost(2, 1));
+        p.add(loc(3, &["main"]), &cost(1, 1));
+        p.add(loc(7, &["main", "f"]), &cost(4, 0));
+        p.add(None, &cost(1, 0));
+        assert_eq!(
+            p.folded(|s| s.n_constraints),
+            "(no source) 1\nmain;f;no/such/file.zok:7 4\nmain;no/such/file.zok:3 3\n"
+        );
+        assert_eq!(p.folded(|s| s.n_vars), "main;no/such/file.zok:3 2\n");
+        let a = p.annotated();
+        assert!(a.contains("no/such/file.zok (7 constraints, 2 vars)"));
+        assert!(a.contains("(no source): 1 constraints, 0 vars"));
+    }
+}
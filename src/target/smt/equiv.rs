@@ -0,0 +1,142 @@
+//! Bounded equivalence checking
+//!
+//! Two proof-mode computations are equivalent if, for every input, either both sets of assertions
+//! fail, or both hold and the `return` values agree. Precomputed values (e.g., witnesses) are
+//! substituted by the terms that compute them, so the only free variables are the inputs.
+
+use super::*;
+
+use std::collections::BTreeMap;
+
+/// A computation's inputs, assertions, and `return` values, all over its inputs.
+struct Function {
+    inputs: BTreeMap<String, Sort>,
+    ok: Term,
+    returns: BTreeMap<String, Term>,
+}
+
+impl Function {
+    fn new(c: &Computation) -> Self {
+        let mut precomputes = c.precomputes.clone();
+        precomputes.flatten();
+        let mut subs: TermMap<Term> = precomputes
+            .outputs()
+            .iter()
+            .map(|(name, t)| (var(name.clone(), check(t)), t.clone()))
+            .collect();
+        let ok = extras::substitute_cache(&term(AND, c.outputs.clone()), &mut subs);
+        let returns = precomputes
+            .outputs()
+            .iter()
+            .filter(|(name, _)| *name == "return" || name.starts_with("return."))
+            .map(|(name, t)| (name.clone(), t.clone()))
+            .collect();
+        let inputs = c
+            .metadata
+            .ordered_input_names()
+            .into_iter()
+            .filter(|name| !precomputes.outputs().contains_key(name))
+            .map(|name| {
+                let sort = c.metadata.input_sort(&name);
+                (name, sort)
+            })
+            .collect();
+        Function {
+            inputs,
+            ok,
+            returns,
+        }
+    }
+}
+
+/// Build a miter for `a` and `b`: a term that is satisfiable iff they are not equivalent.
+///
+/// Fails if their inputs or `return` values do not match by name and sort.
+pub fn miter(a: &Computation, b: &Computation) -> Result<Term, String> {
+    let (fa, fb) = (Function::new(a), Function::new(b));
+    for (name, sort) in &fa.inputs {
+        match fb.inputs.get(name) {
+            Some(s) if s == sort => {}
+            Some(s) => return Err(format!("Input {name} is {sort} in A but {s} in B")),
+            None => return Err(format!("Input {name} of A is not an input of B")),
+        }
+    }
+    if let Some(name) = fb.inputs.keys().find(|n| !fa.inputs.contains_key(*n)) {
+        return Err(format!("Input {name} of B is not an input of A"));
+    }
+    if fa.returns.keys().ne(fb.returns.keys()) {
+        return Err("A and B do not return the same type".into());
+    }
+    let mut differ = Vec::new();
+    for ((name, ra), rb) in fa.returns.iter().zip(fb.returns.values()) {
+        if check(ra) != check(rb) {
+            return Err(format!(
+                "{name} is {} in A but {} in B",
+                check(ra),
+                check(rb)
+            ));
+        }
+        differ.push(term![NOT; term![EQ; ra.clone(), rb.clone()]]);
+    }
+    Ok(term![OR;
+        term![XOR; fa.ok.clone(), fb.ok.clone()],
+        term![AND; fa.ok, fb.ok, term(OR, differ)]
+    ])
+}
+
+/// Find an input that distinguishes `a` from `b`, or `None` if they are equivalent.
+pub fn find_distinguishing_input(
+    a: &Computation,
+    b: &Computation,
+) -> Result<Option<HashMap<String, Value>>, String> {
+    Ok(find_model(&miter(a, b)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ir::proof::Constraints;
+
+    /// `return = x op 1`, asserting `x != 0`
+    fn function(op: Op) -> Computation {
+        let x = var("x".into(), Sort::BitVector(4));
+        let ret = term![op; x.clone(), bv_lit(1, 4)];
+        let r = var("return".into(), Sort::BitVector(4));
+        let mut c = Computation::from_constraint_system_parts(
+            vec![
+                term![NOT; term![EQ; x, bv_lit(0, 4)]],
+                term![EQ; r.clone(), ret.clone()],
+            ],
+            vec![r],
+        );
+        c.precomputes.add_output("return".into(), ret);
+        c
+    }
+
+    #[test]
+    fn equivalent() {
+        let a = function(BV_ADD);
+        let b = function(BV_ADD);
+        assert_eq!(find_distinguishing_input(&a, &b), Ok(None));
+    }
+
+    #[test]
+    fn distinguishable() {
+        let a = function(BV_ADD);
+        let b = function(BV_OR);
+        let model = find_distinguishing_input(&a, &b).unwrap().unwrap();
+        let x = model["x"].as_bv().uint().clone();
+        assert_ne!(x, 0);
+        assert!(x.get_bit(0), "x + 1 and x | 1 agree when x is even");
+    }
+
+    #[test]
+    fn mismatched_inputs() {
+        let a = function(BV_ADD);
+        let b = Computation::from_constraint_system_parts(
+            vec![var("y".into(), Sort::Bool)],
+            Vec::new(),
+        );
+        assert!(miter(&a, &b).is_err());
+    }
+}
@@ -0,0 +1,1285 @@
+//! The SMT back-end.
+//!
+//! The solver is chosen by `--smt-solver` (cvc5, z3, yices, or bitwuzla); `--smt-solver-cmd`
+//! overrides its invocation command. For cvc5, the environmental variable
+//! [rsmt2::conf::CVC4_ENV_VAR] also sets the invocation command. Not every solver supports every
+//! sort: finite fields and tuples need cvc5.
+//!
+//! A map of sort `(Map K V)` is encoded as two SMT arrays: an `(Array K Bool)` saying which keys
+//! are present, and an `(Array K V)` giving their values. A map variable `m` is declared as the
+//! two constants `m.keys` and `m.vals`. Selecting an absent key gives the default value of `V`, as
+//! in [ExtOp::MapSelect]'s evaluation. [ExtOp::ArrayToMap] treats every index of the array as
+//! present. [ExtOp::MapFlip] is only supported on the result of [ExtOp::ArrayToMap], whose keys
+//! are known. Maps can't be nested in other sorts.
+
+pub mod equiv;
+
+use crate::cfg::cfg_or_default;
+use crate::ir::term::*;
+
+use rsmt2::errors::SmtRes;
+use rsmt2::parse::{IdentParser, ModelParser, SmtParser};
+use rsmt2::print::{Expr2Smt, Sort2Smt, Sym2Smt};
+
+use circ_opt::SmtSolver;
+use rug::Integer;
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+use std::str::FromStr;
+
+use ieee754::Ieee754;
+
+struct SmtDisp<'a, T>(pub &'a T);
+
+impl<'a, T: Expr2Smt<()> + 'a> Display for SmtDisp<'a, T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut s = Vec::new();
+        <T as Expr2Smt<()>>::expr_to_smt2(self.0, &mut s, ()).unwrap();
+        write!(f, "{}", std::str::from_utf8(&s).unwrap())?;
+        Ok(())
+    }
+}
+
+struct SmtSortDisp<'a, T>(pub &'a T);
+impl<'a, T: Sort2Smt + 'a> Display for SmtSortDisp<'a, T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut s = Vec::new();
+        <T as Sort2Smt>::sort_to_smt2(self.0, &mut s).unwrap();
+        write!(f, "{}", std::str::from_utf8(&s).unwrap())?;
+        Ok(())
+    }
+}
+
+impl Expr2Smt<()> for Value {
+    fn expr_to_smt2<W: Write>(&self, w: &mut W, (): ()) -> SmtRes<()> {
+        match self {
+            Value::Bool(b) => write!(w, "{b}")?,
+            Value::Field(f) => write!(w, "#f{}m{}", f.i(), f.modulus())?,
+            Value::Int(i) if i >= &Integer::new() => write!(w, "{i}")?,
+            Value::Int(i) => write!(w, "(- 0 {})", *i.as_neg())?,
+            Value::BitVector(b) => write!(w, "{b}")?,
+            Value::F32(f) => {
+                let (sign, exp, mant) = f.decompose_raw();
+                write!(w, "(fp #b{} #b", sign as u8)?;
+                for i in (0..8).rev() {
+                    write!(w, "{}", (exp >> i) & 1)?;
+                }
+                write!(w, " #b")?;
+                for i in (0..23).rev() {
+                    write!(w, "{}", (mant >> i) & 1)?;
+                }
+                write!(w, ")")?;
+            }
+            Value::F64(f) => {
+                let (sign, exp, mant) = f.decompose_raw();
+                write!(w, "(fp #b{} #b", sign as u8)?;
+                for i in (0..11).rev() {
+                    write!(w, "{}", (exp >> i) & 1)?;
+                }
+                write!(w, " #b")?;
+                for i in (0..52).rev() {
+                    write!(w, "{}", (mant >> i) & 1)?;
+                }
+                write!(w, ")")?;
+            }
+            Value::Array(Array {
+                key_sort,
+                default,
+                map,
+                size,
+            }) => {
+                for _ in 0..map.len() {
+                    write!(w, "(store ")?;
+                }
+                let val_s = check(&const_((**default).clone()));
+                let s = Sort::new_array(key_sort.clone(), val_s, *size);
+                write!(
+                    w,
+                    "((as const {}) {})",
+                    SmtSortDisp(&s),
+                    SmtDisp(&**default)
+                )?;
+                for (k, v) in map {
+                    write!(w, " {} {})", SmtDisp(k), SmtDisp(v))?;
+                }
+            }
+            Value::Tuple(fs) => {
+                write!(w, "(mkTuple")?;
+                for t in fs.iter() {
+                    write!(w, " {}", SmtDisp(t))?;
+                }
+                write!(w, ")")?;
+            }
+            Value::Map(_) => return Err(MAP_OUTSIDE_MAP_OPS.into()),
+        }
+        Ok(())
+    }
+}
+
+const MAP_OUTSIDE_MAP_OPS: &str =
+    "The SMT backend only supports maps as arguments of map operations and equalities";
+
+/// The name of the array of keys (in the map variable `name`).
+fn map_keys_name(name: &str) -> String {
+    format!("{name}.keys")
+}
+
+/// The name of the array of values (in the map variable `name`).
+fn map_vals_name(name: &str) -> String {
+    format!("{name}.vals")
+}
+
+/// A sort, written out
+struct SmtSortStr(String);
+
+impl Sort2Smt for SmtSortStr {
+    fn sort_to_smt2<W: Write>(&self, w: &mut W) -> SmtRes<()> {
+        write!(w, "{}", self.0)?;
+        Ok(())
+    }
+}
+
+/// The SMT constants (names and sorts) that encode the variable `name` of sort `sort`.
+fn smt_consts(name: &str, sort: &Sort) -> SmtRes<Vec<(String, SmtSortStr)>> {
+    let sort_str = |key: &Sort, val: Option<&Sort>| -> SmtRes<SmtSortStr> {
+        let mut buf = Vec::new();
+        match val {
+            Some(val) => write_array_sort(&mut buf, key, val)?,
+            None => key.sort_to_smt2(&mut buf)?,
+        }
+        Ok(SmtSortStr(String::from_utf8(buf).unwrap()))
+    };
+    Ok(match sort {
+        Sort::Map(m) => vec![
+            (map_keys_name(name), sort_str(&m.key, Some(&Sort::Bool))?),
+            (map_vals_name(name), sort_str(&m.key, Some(&m.val))?),
+        ],
+        s => vec![(name.to_owned(), sort_str(s, None)?)],
+    })
+}
+
+/// Write `(Array key val)`.
+fn write_array_sort<W: Write>(w: &mut W, key: &Sort, val: &Sort) -> SmtRes<()> {
+    write!(w, "(Array ")?;
+    key.sort_to_smt2(w)?;
+    write!(w, " ")?;
+    val.sort_to_smt2(w)?;
+    write!(w, ")")?;
+    Ok(())
+}
+
+/// `t`, written out
+fn smt_string<T: Expr2Smt<()>>(t: &T) -> SmtRes<String> {
+    let mut buf = Vec::new();
+    t.expr_to_smt2(&mut buf, ())?;
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+/// The arrays encoding the map-sorted term `t`: which keys are present, and their values.
+fn map_arrays(t: &Term) -> SmtRes<(String, String)> {
+    match t.op() {
+        Op::Var(v) => Ok((map_keys_name(&v.name), map_vals_name(&v.name))),
+        Op::Const(c) => match &**c {
+            Value::Map(m) => Ok((
+                stores(
+                    &const_array(&m.key_sort, &Sort::Bool, &Value::Bool(false)),
+                    m.map.keys().map(|k| (SmtDisp(k), true)),
+                )?,
+                stores(
+                    &const_array(&m.key_sort, &m.value_sort, &m.value_sort.default_value()),
+                    m.map.iter().map(|(k, v)| (SmtDisp(k), SmtDisp(v))),
+                )?,
+            )),
+            c => unreachable!("map constant {}", c),
+        },
+        Op::Ite => {
+            let c = smt_string(&t.cs()[0])?;
+            let (k1, v1) = map_arrays(&t.cs()[1])?;
+            let (k2, v2) = map_arrays(&t.cs()[2])?;
+            Ok((
+                format!("(ite {c} {k1} {k2})"),
+                format!("(ite {c} {v1} {v2})"),
+            ))
+        }
+        Op::ExtOp(ExtOp::ArrayToMap) => {
+            let a = &t.cs()[0];
+            let a_sort = check(a);
+            let (key, _, size) = a_sort.as_array();
+            let keys: Vec<Value> = key.elems_iter_values().take(size).collect();
+            Ok((
+                stores(
+                    &const_array(key, &Sort::Bool, &Value::Bool(false)),
+                    keys.iter().map(|k| (SmtDisp(k), true)),
+                )?,
+                smt_string(a)?,
+            ))
+        }
+        Op::ExtOp(ExtOp::MapFlip) => {
+            let m = &t.cs()[0];
+            let a = match m.op() {
+                Op::ExtOp(ExtOp::ArrayToMap) => &m.cs()[0],
+                _ => return Err("The SMT backend only supports MapFlip of ArrayToMap".into()),
+            };
+            let a_str = smt_string(a)?;
+            let a_sort = check(a);
+            let (key, val, size) = a_sort.as_array();
+            let keys: Vec<Value> = key.elems_iter_values().take(size).collect();
+            let vals: Vec<String> = keys
+                .iter()
+                .map(|k| format!("(select {} {})", a_str, SmtDisp(k)))
+                .collect();
+            // Store the entries in reverse, so that each value maps to its *first* key.
+            Ok((
+                stores(
+                    &const_array(val, &Sort::Bool, &Value::Bool(false)),
+                    vals.iter().rev().map(|v| (v, true)),
+                )?,
+                stores(
+                    &const_array(val, key, &key.default_value()),
+                    vals.iter().zip(&keys).rev().map(|(v, k)| (v, SmtDisp(k))),
+                )?,
+            ))
+        }
+        o => Err(format!("The SMT backend does not support maps from {o}").into()),
+    }
+}
+
+/// `((as const (Array key val)) default)`
+fn const_array(key: &Sort, val: &Sort, default: &Value) -> String {
+    format!(
+        "((as const (Array {} {})) {})",
+        SmtSortDisp(key),
+        SmtSortDisp(val),
+        SmtDisp(default)
+    )
+}
+
+/// `(store ... (store base k0 v0) ... kn vn)`; later entries take precedence.
+fn stores<K: Display, V: Display>(
+    base: &str,
+    entries: impl IntoIterator<Item = (K, V)>,
+) -> SmtRes<String> {
+    let entries: Vec<(K, V)> = entries.into_iter().collect();
+    let mut w = Vec::new();
+    for _ in 0..entries.len() {
+        write!(w, "(store ")?;
+    }
+    write!(w, "{base}")?;
+    for (k, v) in entries {
+        write!(w, " {k} {v})")?;
+    }
+    Ok(String::from_utf8(w).unwrap())
+}
+
+impl Expr2Smt<()> for Term {
+    fn expr_to_smt2<W: Write>(&self, w: &mut W, (): ()) -> SmtRes<()> {
+        let s_expr_children = match &self.op() {
+            Op::Var(v) => {
+                if let Sort::Map(_) = v.sort {
+                    return Err(MAP_OUTSIDE_MAP_OPS.into());
+                }
+                write!(w, "{}", v.name)?;
+                false
+            }
+            Op::Eq if check(&self.cs()[0]).is_map() => {
+                let (k0, v0) = map_arrays(&self.cs()[0])?;
+                let (k1, v1) = map_arrays(&self.cs()[1])?;
+                write!(w, "(and (= {k0} {k1}) (= {v0} {v1}))")?;
+                false
+            }
+            Op::Eq => {
+                write!(w, "(=")?;
+                true
+            }
+            Op::Ite => {
+                if check(self).is_map() {
+                    return Err(MAP_OUTSIDE_MAP_OPS.into());
+                }
+                write!(w, "(ite")?;
+                true
+            }
+            Op::Not => {
+                write!(w, "(not")?;
+                true
+            }
+            Op::Implies => {
+                write!(w, "(=>")?;
+                true
+            }
+            Op::BoolNaryOp(_)
+            | Op::BvBinPred(_)
+            | Op::BvBinOp(_)
+            | Op::BvNaryOp(_)
+            | Op::BvUnOp(_) => {
+                write!(w, "({}", self.op())?;
+                true
+            }
+            Op::BvUext(s) => {
+                write!(w, "((_ zero_extend {s})")?;
+                true
+            }
+            Op::BvSext(s) => {
+                write!(w, "((_ sign_extend {s})")?;
+                true
+            }
+            Op::BvExtract(high, low) => {
+                write!(w, "((_ extract {high} {low})")?;
+                true
+            }
+            Op::BvConcat => {
+                write!(w, "(concat")?;
+                true
+            }
+            Op::BvBit(i) => {
+                let x = smt_string(&self.cs()[0])?;
+                write!(w, "(= ((_ extract {i} {i}) {x}) #b1)")?;
+                false
+            }
+            Op::BoolToBv => {
+                let x = smt_string(&self.cs()[0])?;
+                write!(w, "(ite {x} #b1 #b0)")?;
+                false
+            }
+            Op::Const(c) => {
+                c.expr_to_smt2(w, ())?;
+                false
+            }
+            Op::Store => {
+                write!(w, "(store")?;
+                true
+            }
+            Op::Select => {
+                write!(w, "(select")?;
+                true
+            }
+            Op::Tuple => {
+                write!(w, "(mkTuple")?;
+                true
+            }
+            Op::Field(i) => {
+                write!(w, "((_ tupSel {i})")?;
+                true
+            }
+            Op::PfNaryOp(PfNaryOp::Mul) => {
+                write!(w, "(ff.mul")?;
+                true
+            }
+            Op::PfNaryOp(PfNaryOp::Add) => {
+                write!(w, "(ff.add")?;
+                true
+            }
+            Op::PfUnOp(PfUnOp::Neg) => {
+                write!(w, "(ff.neg")?;
+                true
+            }
+            Op::IntNaryOp(IntNaryOp::Mul) => {
+                write!(w, "(*")?;
+                true
+            }
+            Op::IntNaryOp(IntNaryOp::Add) => {
+                write!(w, "(+")?;
+                true
+            }
+            Op::IntBinPred(o) => {
+                write!(w, "({o}")?;
+                true
+            }
+            Op::ExtOp(ExtOp::MapSelect) => {
+                let (keys, vals) = map_arrays(&self.cs()[0])?;
+                let k = smt_string(&self.cs()[1])?;
+                let default = smt_string(&check(self).default_value())?;
+                write!(w, "(ite (select {keys} {k}) (select {vals} {k}) {default})")?;
+                false
+            }
+            Op::ExtOp(ExtOp::MapContainsKey) => {
+                let (keys, _) = map_arrays(&self.cs()[0])?;
+                let k = smt_string(&self.cs()[1])?;
+                write!(w, "(select {keys} {k})")?;
+                false
+            }
+            Op::ExtOp(ExtOp::ArrayToMap) | Op::ExtOp(ExtOp::MapFlip) => {
+                return Err(MAP_OUTSIDE_MAP_OPS.into());
+            }
+            o => panic!("Cannot give {} to SMT solver", o),
+        };
+        if s_expr_children {
+            for c in self.cs() {
+                write!(w, " ")?;
+                c.expr_to_smt2(w, ())?;
+            }
+            write!(w, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl Sort2Smt for Sort {
+    fn sort_to_smt2<W: Write>(&self, w: &mut W) -> SmtRes<()> {
+        match self {
+            Sort::BitVector(b) => write!(w, "(_ BitVec {b})")?,
+            Sort::Array(a) => write_array_sort(w, &a.key, &a.val)?,
+            Sort::F64 => write!(w, "Float64")?,
+            Sort::F32 => write!(w, "Float32")?,
+            Sort::Bool => write!(w, "Bool")?,
+            Sort::Int => write!(w, "Int")?,
+            Sort::Tuple(fs) => {
+                write!(w, "(Tuple")?;
+                for t in fs.iter() {
+                    write!(w, " ")?;
+                    t.sort_to_smt2(w)?;
+                }
+                write!(w, ")")?;
+            }
+            Sort::Field(f) => write!(w, "(_ FiniteField {})", f.modulus())?,
+            Sort::Map(_) => return Err("The SMT backend can't nest maps in other sorts".into()),
+        }
+        Ok(())
+    }
+}
+
+impl Expr2Smt<()> for BitVector {
+    fn expr_to_smt2<W: Write>(&self, w: &mut W, (): ()) -> SmtRes<()> {
+        write!(w, "#b")?;
+        for i in (0..self.width()).rev() {
+            write!(w, "{}", self.uint().get_bit(i as u32) as u8)?;
+        }
+        Ok(())
+    }
+}
+
+struct SmtSymDisp<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T: Display + 'a + ?Sized> Sym2Smt<()> for SmtSymDisp<'a, T> {
+    fn sym_to_smt2<W: Write>(&self, w: &mut W, (): ()) -> SmtRes<()> {
+        write!(w, "{}", self.0)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Parser;
+
+impl<'a, R: std::io::BufRead> IdentParser<String, Sort, &'a mut SmtParser<R>> for Parser {
+    fn parse_ident(self, input: &'a mut SmtParser<R>) -> SmtRes<String> {
+        Ok(input
+            .try_sym(|a| -> Result<String, String> { Ok(a.to_owned()) })?
+            .expect("sym"))
+    }
+    fn parse_type(self, input: &'a mut SmtParser<R>) -> SmtRes<Sort> {
+        parse_sort(input)
+    }
+}
+
+impl<'a, Br: ::std::io::BufRead> ModelParser<String, Sort, Value, &'a mut SmtParser<Br>>
+    for Parser
+{
+    fn parse_value(
+        self,
+        input: &'a mut SmtParser<Br>,
+        _: &String,
+        _: &[(String, Sort)],
+        s: &Sort,
+    ) -> SmtRes<Value> {
+        parse_value_of(input, s)
+    }
+}
+
+/// Parse a sort. SMT arrays have no size, so arrays get size 0; see [with_sizes].
+fn parse_sort<R: std::io::BufRead>(input: &mut SmtParser<R>) -> SmtRes<Sort> {
+    if input.try_tag("Bool")? {
+        Ok(Sort::Bool)
+    } else if input.try_tag("Int")? {
+        Ok(Sort::Int)
+    } else if input.try_tag("(_ BitVec")? {
+        let n = input
+            .try_int(|s, b| {
+                if b {
+                    Ok(usize::from_str(s).unwrap())
+                } else {
+                    Err("Non-positive bit-vector width")
+                }
+            })?
+            .unwrap();
+        input.tag(")")?;
+        Ok(Sort::BitVector(n))
+    } else if input.try_tag("(_ FiniteField")? {
+        let n = input
+            .try_int(|s, b| {
+                if b {
+                    Ok(rug::Integer::from_str_radix(s, 10).unwrap())
+                } else {
+                    Err("Non-positive finite field size")
+                }
+            })?
+            .unwrap();
+        input.tag(")")?;
+        Ok(Sort::Field(circ_fields::FieldT::from(n)))
+    } else if input.try_tag("(Array")? {
+        let key = parse_sort(input)?;
+        let val = parse_sort(input)?;
+        input.tag(")")?;
+        Ok(Sort::new_array(key, val, 0))
+    } else {
+        unimplemented!()
+    }
+}
+
+/// Parse a value of sort `s`.
+fn parse_value_of<R: std::io::BufRead>(input: &mut SmtParser<R>, s: &Sort) -> SmtRes<Value> {
+    let r = if let Sort::Array(a) = s {
+        Value::Array(parse_array(input, &a.key, &a.val)?)
+    } else if let Some(b) = input.try_bool()? {
+        Value::Bool(b)
+    } else if input.try_tag("#b")? {
+        let bits = input.get_sexpr()?;
+        let i = Integer::from_str_radix(bits, 2).unwrap();
+        Value::BitVector(BitVector::new(i, bits.len()))
+    } else if input.try_tag("#x")? {
+        let digits = input.get_sexpr()?;
+        let i = Integer::from_str_radix(digits, 16).unwrap();
+        Value::BitVector(BitVector::new(i, 4 * digits.len()))
+    } else if input.try_tag("(_")? {
+        if input.try_tag("bv")? {
+            let val = Integer::from_str_radix(input.get_sexpr()?, 10).unwrap();
+            let width = usize::from_str(input.get_sexpr()?).unwrap();
+            input.tag(")")?;
+            Value::BitVector(BitVector::new(val, width))
+        } else {
+            unimplemented!(
+                "Could not parse model suffix: {}\n after (_ bv",
+                input.buff_rest()
+            )
+        }
+    } else if let Sort::Field(f) = s {
+        let int_literal = input.get_sexpr()?;
+        let i = Integer::from_str_radix(int_literal, 10).unwrap();
+        Value::Field(f.new_v(i))
+    } else if let Sort::Int = s {
+        let int_literal = input.get_sexpr()?;
+        let i = Integer::from_str_radix(int_literal, 10).unwrap();
+        Value::Int(i)
+    } else {
+        unimplemented!("Could not parse model suffix: {}", input.buff_rest())
+    };
+    //if !input.try_tag(")")? {
+    //    input.fail_with("No trailing ')'")?;
+    //}
+    Ok(r)
+}
+
+/// Parse an array: stores into a constant array. Its size is 0; see [with_sizes].
+fn parse_array<R: std::io::BufRead>(
+    input: &mut SmtParser<R>,
+    key: &Sort,
+    val: &Sort,
+) -> SmtRes<Array> {
+    if input.try_tag("(store")? {
+        let mut a = parse_array(input, key, val)?;
+        let k = parse_value_of(input, key)?;
+        let v = parse_value_of(input, val)?;
+        input.tag(")")?;
+        a.map.insert(k, v);
+        Ok(a)
+    } else if input.try_tag("((as const")? {
+        parse_sort(input)?;
+        input.tag(")")?;
+        let default = parse_value_of(input, val)?;
+        input.tag(")")?;
+        // not Array::new, which rejects some key sorts that maps allow
+        Ok(Array {
+            key_sort: key.clone(),
+            default: Box::new(default),
+            map: Default::default(),
+            size: 0,
+        })
+    } else {
+        Err(format!("Could not parse array value: {}", input.buff_rest()).into())
+    }
+}
+
+/// `v`, with the sizes of its arrays (which SMT arrays don't have) taken from its sort, `s`.
+fn with_sizes(v: Value, s: &Sort) -> Value {
+    match (v, s) {
+        (Value::Array(mut a), Sort::Array(sa)) => {
+            a.size = sa.size;
+            a.default = Box::new(with_sizes(*a.default, &sa.val));
+            a.map = a
+                .map
+                .into_iter()
+                .map(|(k, v)| (k, with_sizes(v, &sa.val)))
+                .collect();
+            Value::Array(a)
+        }
+        (v, _) => v,
+    }
+}
+
+/// The map (of sort `m`) encoded by the arrays `keys` and `vals`.
+fn map_from_arrays(m: &MapSort, keys: Array, vals: Array) -> SmtRes<Value> {
+    if *keys.default != Value::Bool(false) {
+        return Err("The model has a map with infinitely many keys".into());
+    }
+    let entries = keys
+        .map
+        .into_iter()
+        .filter(|(_, present)| *present == Value::Bool(true))
+        .map(|(k, _)| {
+            let v = vals.map.get(&k).unwrap_or(&vals.default).clone();
+            (with_sizes(k, &m.key), with_sizes(v, &m.val))
+        });
+    Ok(Value::Map(map::Map::new(
+        m.key.clone(),
+        m.val.clone(),
+        entries,
+    )))
+}
+
+/// The values of the variables in `t`, from a model of the SMT constants that encode them.
+fn model_values(
+    t: &Term,
+    model: Vec<(String, Vec<(String, Sort)>, Sort, Value)>,
+) -> HashMap<String, Value> {
+    let mut consts: HashMap<String, Value> =
+        model.into_iter().map(|(id, _, _, v)| (id, v)).collect();
+    let mut values = HashMap::new();
+    for c in PostOrderIter::new(t.clone()) {
+        if let Op::Var(v) = &c.op() {
+            let value = match &v.sort {
+                Sort::Map(m) => match (
+                    consts.remove(&map_keys_name(&v.name)),
+                    consts.remove(&map_vals_name(&v.name)),
+                ) {
+                    (Some(Value::Array(keys)), Some(Value::Array(vals))) => {
+                        map_from_arrays(m, keys, vals).unwrap()
+                    }
+                    _ => continue,
+                },
+                s => match consts.remove(&*v.name) {
+                    Some(value) => with_sizes(value, s),
+                    None => continue,
+                },
+            };
+            values.insert(v.name.to_string(), value);
+        }
+    }
+    values
+}
+
+/// Create a solver, which can optionally parse models.
+///
+/// The solver is chosen by [circ_opt::SmtOpt]. For cvc5, if [rsmt2::conf::CVC4_ENV_VAR] is set,
+/// uses that as the solver's invocation command.
+fn make_solver<P>(parser: P, models: bool, inc: bool) -> rsmt2::Solver<P> {
+    let opt = &cfg_or_default().smt;
+    let mut conf = match opt.solver {
+        SmtSolver::Cvc5 => {
+            let mut conf = rsmt2::conf::SmtConf::default_cvc4();
+            if let Ok(val) = std::env::var(rsmt2::conf::CVC4_ENV_VAR) {
+                conf.cmd(val);
+            }
+            conf
+        }
+        SmtSolver::Z3 => rsmt2::conf::SmtConf::default_z3(),
+        SmtSolver::Yices => rsmt2::conf::SmtConf::default_yices_2(),
+        SmtSolver::Bitwuzla => bitwuzla_conf(),
+    };
+    if !opt.solver_cmd.is_empty() {
+        conf.cmd(opt.solver_cmd.clone());
+    }
+    if models {
+        conf.models();
+    }
+    // see bitwuzla_conf
+    if opt.solver != SmtSolver::Bitwuzla {
+        conf.set_incremental(inc);
+    }
+    rsmt2::Solver::new(conf, parser).expect("Error creating SMT solver")
+}
+
+/// Bitwuzla's configuration.
+///
+/// rsmt2 has no bitwuzla style. The yices 2 style is the only one that adds no command-line
+/// flags, so this starts from it and passes bitwuzla's own. Bitwuzla is always incremental, so
+/// [make_solver] never sets yices' `--incremental` for it.
+fn bitwuzla_conf() -> rsmt2::conf::SmtConf {
+    let mut conf = rsmt2::conf::SmtStyle::Yices2.new("bitwuzla");
+    conf.option("--lang");
+    conf.option("smt2");
+    conf
+}
+
+/// Write SMT2 the encodes this terms satisfiability to a file
+pub fn write_smt2<W: Write>(mut w: W, t: &Term) {
+    for c in PostOrderIter::new(t.clone()) {
+        if let Op::Var(v) = &c.op() {
+            for (name, sort) in smt_consts(&v.name, &v.sort).unwrap() {
+                write!(w, "(declare-const ").unwrap();
+                SmtSymDisp(&name).sym_to_smt2(&mut w, ()).unwrap();
+                write!(w, " ").unwrap();
+                sort.sort_to_smt2(&mut w).unwrap();
+                writeln!(w, ")").unwrap();
+            }
+        }
+    }
+    assert!(check(t) == Sort::Bool);
+    write!(w, "(assert\n\t").unwrap();
+    t.expr_to_smt2(&mut w, ()).unwrap();
+    writeln!(w, "\n)").unwrap();
+    writeln!(w, "(check-sat)").unwrap();
+}
+
+/// Declare the variables of `t`.
+fn declare_vars<P>(solver: &mut rsmt2::Solver<P>, t: &Term) {
+    for c in PostOrderIter::new(t.clone()) {
+        if let Op::Var(v) = &c.op() {
+            for (name, sort) in smt_consts(&v.name, &v.sort).unwrap() {
+                solver.declare_const(&SmtSymDisp(&name), &sort).unwrap();
+            }
+        }
+    }
+}
+
+/// Check whether some term is satisfiable.
+pub fn check_sat(t: &Term) -> bool {
+    let mut solver = make_solver((), false, false);
+    declare_vars(&mut solver, t);
+    assert!(check(t) == Sort::Bool);
+    solver.assert(t).unwrap();
+    solver.check_sat().unwrap()
+}
+
+fn get_model_solver(t: &Term, inc: bool) -> rsmt2::Solver<Parser> {
+    let mut solver = make_solver(Parser, true, inc);
+    //solver.path_tee("solver_com").unwrap();
+    declare_vars(&mut solver, t);
+    assert!(check(t) == Sort::Bool);
+    solver
+}
+
+/// Get a satisfying assignment for `t`, assuming it is SAT.
+pub fn find_model(t: &Term) -> Option<HashMap<String, Value>> {
+    let mut solver = get_model_solver(t, false);
+    solver.assert(t).unwrap();
+    if solver.check_sat().unwrap() {
+        Some(model_values(t, solver.get_model().unwrap()))
+    } else {
+        None
+    }
+}
+
+/// Get a unique satisfying assignment for `t`, assuming it is SAT.
+pub fn find_unique_model(t: &Term, uniqs: Vec<String>) -> Option<HashMap<String, Value>> {
+    let mut solver = get_model_solver(t, true);
+    solver.assert(t).unwrap();
+    // first, get the result
+    let model: HashMap<String, Value> = if solver.check_sat().unwrap() {
+        model_values(t, solver.get_model().unwrap())
+    } else {
+        return None;
+    };
+    // now, assert that any value in uniq is not the value assigned and check unsat
+    match uniqs
+        .into_iter()
+        .flat_map(|n| {
+            model
+                .get(&n)
+                .map(|v| term![EQ; term![Op::new_var(n, v.sort())], const_(v.clone())])
+        })
+        .reduce(|l, r| term![AND; l, r])
+        .map(|t| term![NOT; t])
+    {
+        None => Some(model),
+        Some(ast) => {
+            solver.push(1).unwrap();
+            solver.assert(&ast).unwrap();
+            match solver.check_sat().unwrap() {
+                true => None,
+                false => Some(model),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ir::term::dist::test::*;
+    use fxhash::FxHashMap as HashMap;
+    use quickcheck_macros::quickcheck;
+    use rug::Integer;
+
+    #[test]
+    fn var_is_sat() {
+        let t = var("a".into(), Sort::Bool);
+        assert!(check_sat(&t));
+    }
+
+    #[test]
+    fn var_is_sat_model() {
+        let t = var("a".into(), Sort::Bool);
+        assert!(
+            find_model(&t)
+                == Some(
+                    vec![("a".to_owned(), Value::Bool(true))]
+                        .into_iter()
+                        .collect()
+                )
+        );
+    }
+
+    #[test]
+    fn var_and_not_is_unsat() {
+        let v = var("a".into(), Sort::Bool);
+        let t = term![Op::BoolNaryOp(BoolNaryOp::And); v.clone(), term![Op::Not; v]];
+        assert!(!check_sat(&t));
+    }
+
+    #[test]
+    fn bv_is_sat() {
+        let t = term![Op::Eq; bv_lit(0,4), var("a".into(), Sort::BitVector(4))];
+        assert!(check_sat(&t));
+    }
+
+    // ignored until FF support in cvc5 is upstreamed.
+    #[ignore]
+    #[test]
+    fn ff_is_sat() {
+        let t = text::parse_term(
+            b"
+        (declare ((a (mod 5)) (b (mod 5)))
+            (and
+                (= (* a a) a)
+                (= (* b b) b)
+                (= a b)
+                (= a #f1m5)
+            )
+        )
+        ",
+        );
+        assert!(check_sat(&t));
+    }
+
+    // ignored until FF support in cvc5 is upstreamed.
+    #[ignore]
+    #[test]
+    fn ff_model() {
+        let t = text::parse_term(
+            b"
+        (declare ((a (mod 5)) (b (mod 5)))
+            (and
+                (= (* a a) a)
+                (= (* b b) b)
+                (= a b)
+                (= a #f1m5)
+            )
+        )
+        ",
+        );
+        let field = circ_fields::FieldT::from(rug::Integer::from(5));
+        assert_eq!(
+            find_model(&t),
+            Some(
+                vec![
+                    ("a".to_owned(), Value::Field(field.new_v(1)),),
+                    ("b".to_owned(), Value::Field(field.new_v(1)),),
+                ]
+                .into_iter()
+                .collect()
+            )
+        )
+    }
+
+    #[test]
+    fn tuple_is_sat() {
+        let t = term![Op::Eq; term![Op::Field(0); term![Op::Tuple; bv_lit(0,4), bv_lit(5,6)]], var("a".into(), Sort::BitVector(4))];
+        assert!(check_sat(&t));
+        let t = term![Op::Eq; term![Op::Tuple; bv_lit(0,4), bv_lit(5,6)], var("a".into(), Sort::new_tuple(vec![Sort::BitVector(4), Sort::BitVector(6)]))];
+        assert!(check_sat(&t));
+    }
+
+    #[test]
+    fn bv_is_sat_model() {
+        let t = term![Op::Eq; bv_lit(0,4), var("a".into(), Sort::BitVector(4))];
+        assert!(
+            find_model(&t)
+                == Some(
+                    vec![(
+                        "a".to_owned(),
+                        Value::BitVector(BitVector::new(Integer::from(0), 4))
+                    ),]
+                    .into_iter()
+                    .collect()
+                )
+        );
+    }
+
+    #[test]
+    fn vars_are_sat_model() {
+        let t = term![Op::BoolNaryOp(BoolNaryOp::And);
+           var("a".into(), Sort::Bool),
+           var("b".into(), Sort::Bool),
+           var("c".into(), Sort::Bool)
+        ];
+        assert!(
+            find_model(&t)
+                == Some(
+                    vec![
+                        ("a".to_owned(), Value::Bool(true)),
+                        ("b".to_owned(), Value::Bool(true)),
+                        ("c".to_owned(), Value::Bool(true)),
+                    ]
+                    .into_iter()
+                    .collect()
+                )
+        );
+    }
+
+    #[quickcheck]
+    fn eval_random_bool(ArbitraryBoolEnv(t, vs): ArbitraryBoolEnv) {
+        assert!(smt_eval_test(t.clone(), &vs));
+        assert!(!smt_eval_alternate_solution(t, &vs));
+    }
+
+    /// Check that `t` evaluates consistently within the SMT solver under `vs`.
+    pub fn smt_eval_test(t: Term, vs: &HashMap<String, Value>) -> bool {
+        let mut solver = make_solver((), false, false);
+        for (v, val) in vs {
+            let s = val.sort();
+            solver.declare_const(&SmtSymDisp(&v), &s).unwrap();
+            solver
+                .assert(&term![Op::Eq; var(v.to_string(), s), const_(val.clone())])
+                .unwrap();
+        }
+        let val = eval(&t, vs);
+        solver.assert(&term![Op::Eq; t, const_(val)]).unwrap();
+        solver.check_sat().unwrap()
+    }
+
+    /// Check that `t` evaluates consistently within the SMT solver under `vs`.
+    pub fn smt_eval_alternate_solution(t: Term, vs: &HashMap<String, Value>) -> bool {
+        let mut solver = make_solver((), false, false);
+        for (v, val) in vs {
+            let s = val.sort();
+            solver.declare_const(&SmtSymDisp(&v), &s).unwrap();
+            solver
+                .assert(&term![Op::Eq; var(v.to_string(), s), const_(val.clone())])
+                .unwrap();
+        }
+        let val = eval(&t, vs);
+        solver
+            .assert(&term![Op::Not; term![Op::Eq; t, const_(val)]])
+            .unwrap();
+        solver.check_sat().unwrap()
+    }
+
+    #[test]
+    fn int_model() {
+        let t = text::parse_term(
+            b"
+        (declare ((a int) (b int))
+            (and
+                (or (= (intadd a b) 1)
+                    (= (intadd a b) 0))
+                (< a 1)
+                (> 1 b)
+                (>= a 0)
+                (<= 0 b)
+            )
+        )
+        ",
+        );
+        assert_eq!(
+            find_model(&t),
+            Some(
+                vec![
+                    ("a".to_owned(), Value::Int(0.into())),
+                    ("b".to_owned(), Value::Int(0.into())),
+                ]
+                .into_iter()
+                .collect()
+            )
+        )
+    }
+
+    #[test]
+    fn int_no_model() {
+        let t = text::parse_term(
+            b"
+        (declare ((a int) (b int))
+            (and
+                (or (= (intadd a b) 1)
+                    (= (intadd a b) 1))
+                (< a 1)
+                (> 1 b)
+                (>= a 0)
+                (<= 0 b)
+            )
+        )
+        ",
+        );
+        assert_eq!(find_model(&t), None)
+    }
+
+    #[test]
+    fn int_model_nia() {
+        let t = text::parse_term(
+            b"
+        (declare ((a int) (b int))
+            (and
+                (= (intmul a a) b)
+                (= (intmul b b) a)
+                (not (= a 0))
+            )
+        )
+        ",
+        );
+        assert_eq!(
+            find_model(&t),
+            Some(
+                vec![
+                    ("a".to_owned(), Value::Int(1.into())),
+                    ("b".to_owned(), Value::Int(1.into())),
+                ]
+                .into_iter()
+                .collect()
+            )
+        )
+    }
+
+    #[test]
+    fn int_model_div() {
+        let t = text::parse_term(
+            b"
+        (declare ((a int) (q int) (r int))
+            (and
+                (= a (intadd (intmul q 5) r))
+                (>= r 0)
+                (< r 5)
+                (= (intadd a (intmul -1 r)) 10)
+                (>= a 14)
+            )
+        )
+        ",
+        );
+        assert_eq!(
+            find_model(&t),
+            Some(
+                vec![
+                    ("a".to_owned(), Value::Int(14.into())),
+                    ("r".to_owned(), Value::Int(4.into())),
+                    ("q".to_owned(), Value::Int(2.into())),
+                ]
+                .into_iter()
+                .collect()
+            )
+        )
+    }
+
+    #[test]
+    fn bv_model_div() {
+        let t = text::parse_term(
+            b"
+        (declare ((a (bv 8)) (q (bv 8)) (r (bv 8)))
+            (and
+                (= a (bvadd (bvmul q #x05) r))
+                (bvuge r #x00)
+                (bvult r #x05)
+                (= (bvsub a r) #x0a)
+                (bvuge a #x0e)
+            )
+        )
+        ",
+        );
+        assert_eq!(
+            find_model(&t),
+            Some(
+                vec![
+                    (
+                        "a".to_owned(),
+                        Value::BitVector(BitVector::new(Integer::from(14), 8))
+                    ),
+                    (
+                        "r".to_owned(),
+                        Value::BitVector(BitVector::new(Integer::from(4), 8))
+                    ),
+                    (
+                        "q".to_owned(),
+                        Value::BitVector(BitVector::new(Integer::from(2), 8))
+                    ),
+                ]
+                .into_iter()
+                .collect()
+            )
+        )
+    }
+
+    #[test]
+    fn bv_model_sext_extract_concat() {
+        let t = text::parse_term(
+            b"
+        (declare ((a (bv 4)) (b (bv 4)))
+            (and
+                (= ((sext 4) a) #xfe)
+                (= (concat ((extract 1 0) a) (bvnot b)) #b101110)
+                ((bit 0) b)
+            )
+        )
+        ",
+        );
+        assert_eq!(
+            find_model(&t),
+            Some(
+                vec![
+                    (
+                        "a".to_owned(),
+                        Value::BitVector(BitVector::new(Integer::from(14), 4))
+                    ),
+                    (
+                        "b".to_owned(),
+                        Value::BitVector(BitVector::new(Integer::from(1), 4))
+                    ),
+                ]
+                .into_iter()
+                .collect()
+            )
+        )
+    }
+
+    #[test]
+    fn map_ops() {
+        let a = var(
+            "a".into(),
+            Sort::new_array(Sort::BitVector(2), Sort::BitVector(4), 3),
+        );
+        let v = var("v".into(), Sort::BitVector(4));
+        let k = var("k".into(), Sort::BitVector(2));
+        let map = term![Op::ExtOp(ExtOp::ArrayToMap); a.clone()];
+        let flip = term![Op::ExtOp(ExtOp::MapFlip); map.clone()];
+        let in_domain = term![Op::ExtOp(ExtOp::MapContainsKey); map.clone(), k.clone()];
+        // index 3 is not in the array
+        let t = term![AND;
+            term![EQ; k.clone(), bv_lit(3, 2)],
+            term![Op::ExtOp(ExtOp::MapContainsKey); flip.clone(), v.clone()]
+        ];
+        assert!(check_sat(&t));
+        let t = term![AND; term![EQ; k.clone(), bv_lit(3, 2)], in_domain];
+        assert!(!check_sat(&t));
+        // the flipped map finds the first matching index
+        let t = term![AND;
+            term![EQ; term![Op::Select; a.clone(), bv_lit(0, 2)], bv_lit(5, 4)],
+            term![EQ; term![Op::Select; a.clone(), bv_lit(1, 2)], bv_lit(7, 4)],
+            term![EQ; term![Op::Select; a, bv_lit(2, 2)], bv_lit(7, 4)],
+            term![EQ; v.clone(), bv_lit(7, 4)],
+            term![NOT; term![EQ; term![Op::ExtOp(ExtOp::MapSelect); flip.clone(), v], bv_lit(1, 2)]]
+        ];
+        assert!(!check_sat(&t));
+        // absent keys select the default value
+        let w = var("w".into(), Sort::BitVector(4));
+        let t = term![AND;
+            term![NOT; term![Op::ExtOp(ExtOp::MapContainsKey); flip.clone(), w.clone()]],
+            term![NOT; term![EQ; term![Op::ExtOp(ExtOp::MapSelect); flip, w], bv_lit(0, 2)]]
+        ];
+        assert!(!check_sat(&t));
+    }
+
+    #[test]
+    fn map_vars() {
+        let m_sort = Sort::new_map(Sort::BitVector(2), Sort::BitVector(4));
+        let m = var("m".into(), m_sort.clone());
+        let n = var("n".into(), m_sort);
+        let k = var("k".into(), Sort::BitVector(2));
+        let t = term![AND;
+            term![EQ; m.clone(), n.clone()],
+            term![Op::ExtOp(ExtOp::MapContainsKey); m.clone(), k.clone()],
+            term![NOT; term![Op::ExtOp(ExtOp::MapContainsKey); n.clone(), k.clone()]]
+        ];
+        assert!(!check_sat(&t));
+        let t = term![AND;
+            term![Op::ExtOp(ExtOp::MapContainsKey); m.clone(), k.clone()],
+            term![NOT; term![EQ; term![Op::ExtOp(ExtOp::MapSelect); m.clone(), k], bv_lit(0, 4)]]
+        ];
+        assert!(check_sat(&t));
+        let mut out = Vec::new();
+        write_smt2(&mut out, &t);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("(declare-const m.keys (Array (_ BitVec 2) Bool))"));
+        assert!(out.contains("(declare-const m.vals (Array (_ BitVec 2) (_ BitVec 4)))"));
+        assert!(!out.contains("Tuple"));
+    }
+
+    #[test]
+    fn map_var_model() {
+        let m_sort = Sort::new_map(Sort::BitVector(2), Sort::BitVector(4));
+        let m = var("m".into(), m_sort.clone());
+        let k = var("k".into(), Sort::BitVector(2));
+        let t = term![AND;
+            term![EQ; k.clone(), bv_lit(1, 2)],
+            term![Op::ExtOp(ExtOp::MapContainsKey); m.clone(), k.clone()],
+            term![EQ; term![Op::ExtOp(ExtOp::MapSelect); m.clone(), k], bv_lit(9, 4)]
+        ];
+        let model: HashMap<String, Value> = find_model(&t).unwrap().into_iter().collect();
+        assert_eq!(model.len(), 2);
+        let map = match &model["m"] {
+            Value::Map(map) => map,
+            v => panic!("{} is not a map", v),
+        };
+        assert_eq!(model["m"].sort(), m_sort);
+        assert_eq!(
+            map.select(&Value::BitVector(BitVector::new(Integer::from(1), 2))),
+            Value::BitVector(BitVector::new(Integer::from(9), 4))
+        );
+        // the model satisfies the term
+        assert_eq!(eval(&t, &model), Value::Bool(true));
+    }
+
+    #[test]
+    fn array_var_model() {
+        let a = var(
+            "a".into(),
+            Sort::new_array(Sort::BitVector(2), Sort::BitVector(4), 3),
+        );
+        let t = term![EQ; term![Op::Select; a, bv_lit(2, 2)], bv_lit(5, 4)];
+        let model: HashMap<String, Value> = find_model(&t).unwrap().into_iter().collect();
+        assert_eq!(
+            model["a"].sort(),
+            Sort::new_array(Sort::BitVector(2), Sort::BitVector(4), 3)
+        );
+        assert_eq!(eval(&t, &model), Value::Bool(true));
+    }
+
+    #[test]
+    fn map_flip_of_var() {
+        let m = var(
+            "m".into(),
+            Sort::new_map(Sort::BitVector(2), Sort::BitVector(4)),
+        );
+        let v = var("v".into(), Sort::BitVector(4));
+        let flip = term![Op::ExtOp(ExtOp::MapFlip); m];
+        let t = term![Op::ExtOp(ExtOp::MapContainsKey); flip, v];
+        assert!(smt_string(&t).is_err());
+    }
+
+    #[test]
+    fn bv_model_uext() {
+        let t = text::parse_term(
+            b"
+        (declare ((a (bv 8)))
+            (= a ((uext 6) #b10))
+        )
+        ",
+        );
+        assert_eq!(
+            find_model(&t),
+            Some(
+                vec![(
+                    "a".to_owned(),
+                    Value::BitVector(BitVector::new(Integer::from(2), 8))
+                ),]
+                .into_iter()
+                .collect()
+            )
+        )
+    }
+}
@@ -332,5 +332,72 @@ mod tests {
             let parse = ZoKratesParser::parse(Rule::iteration_statement, input);
             assert!(parse.is_ok());
         }
+
+        #[test]
+        fn parse_if_else() {
+            let input = "if a < b { \n c = a \n } else { \n c = b \n } endif";
+
+            let parse = ZoKratesParser::parse(Rule::if_else_statement, input);
+            assert!(parse.is_ok());
+
+            let input = "if a < b { \n c = a \n } endif";
+
+            let parse = ZoKratesParser::parse(Rule::if_else_statement, input);
+            assert!(parse.is_ok());
+        }
+
+        #[test]
+        fn parse_wide_uints() {
+            let input = "u256 x = 0x0000000000000000000000000000000000000000000000000000000000000001";
+
+            let parse = ZoKratesParser::parse(Rule::definition_statement, input);
+            assert!(parse.is_ok());
+
+            let input = "u128 y = 12u128";
+
+            let parse = ZoKratesParser::parse(Rule::definition_statement, input);
+            assert!(parse.is_ok());
+        }
+
+        #[test]
+        fn parse_tuples() {
+            let input = "(field, u32) t = (a, 1u32)";
+
+            let parse = ZoKratesParser::parse(Rule::definition_statement, input);
+            assert!(parse.is_ok());
+
+            let input = "(a, b) = f(t.0, t.1)";
+
+            let parse = ZoKratesParser::parse(Rule::definition_statement, input);
+            assert!(parse.is_ok());
+
+            let input = "(field, u32) x, bool y = g()";
+
+            let parse = ZoKratesParser::parse(Rule::definition_statement, input);
+            assert!(parse.is_ok());
+
+            let input = "t.0 = 1";
+
+            let parse = ZoKratesParser::parse(Rule::definition_statement, input);
+            assert!(parse.is_ok());
+        }
+
+        #[test]
+        fn parse_function_attributes() {
+            let input = "#[test]\ndef test_id() -> bool:\n    return true\n";
+
+            let parse = ZoKratesParser::parse(Rule::function_definition, input);
+            assert!(parse.is_ok());
+
+            let input = "#[test] def test_id() -> bool:\n    return true\n";
+
+            let parse = ZoKratesParser::parse(Rule::function_definition, input);
+            assert!(parse.is_ok());
+
+            let input = "#[unroll(32)]\ndef f(field x) -> field:\n    return x\n";
+
+            let parse = ZoKratesParser::parse(Rule::function_definition, input);
+            assert!(parse.is_ok());
+        }
     }
 }
@@ -12,18 +12,21 @@ pub use ast::{
     ArrayParamMetadata, ArrayTranscript, ArrayType, AssertionStatement, Assignee, AssigneeAccess,
     BasicOrStructType, BasicType, BinaryExpression, BinaryOperator, BooleanLiteralExpression,
     BooleanType, CallAccess, CondStoreStatement, ConstantDefinition, ConstantGenericValue, Curve,
-    DecimalLiteralExpression, DecimalNumber, DecimalSuffix, DefinitionStatement, ExplicitGenerics,
-    Expression, FieldSuffix, FieldType, File, FromExpression, FromImportDirective,
-    FunctionDefinition, HexLiteralExpression, HexNumberExpression, IdentifierExpression,
-    ImportDirective, ImportSymbol, InlineArrayExpression, InlineStructExpression,
-    InlineStructMember, IterationStatement, LiteralExpression, MainImportDirective, MemberAccess,
-    NegOperator, NotOperator, Parameter, PosOperator, PostfixExpression, Pragma, PrivateNumber,
-    PrivateVisibility, PublicVisibility, Range, RangeOrExpression, ReturnStatement, Span, Spread,
-    SpreadOrExpression, Statement, StrOperator, StructDefinition, StructField, StructType,
-    SymbolDeclaration, TernaryExpression, ToExpression, Type, TypeDefinition, TypedIdentifier,
-    TypedIdentifierOrAssignee, U16NumberExpression, U16Suffix, U16Type, U32NumberExpression,
-    U32Suffix, U32Type, U64NumberExpression, U64Suffix, U64Type, U8NumberExpression, U8Suffix,
-    U8Type, UnaryExpression, UnaryOperator, Underscore, Visibility, WitnessStatement, EOI,
+    DecimalLiteralExpression, DecimalNumber, DecimalSuffix, DefinitionStatement, ElseClause,
+    ExplicitGenerics, Expression, FieldSuffix, FieldType, File, FromExpression,
+    FromImportDirective, FunctionDefinition, HexLiteralExpression, HexNumberExpression, I16Suffix,
+    I16Type, I32Suffix, I32Type, I64Suffix, I64Type, I8Suffix, I8Type, IdentifierExpression,
+    IfElseStatement, ImportDirective, ImportSymbol, InlineArrayExpression, InlineStructExpression,
+    InlineStructMember, InlineTupleExpression, IterationStatement, LiteralExpression, LogStatement,
+    MainImportDirective, MemberAccess, NegOperator, NotOperator, Parameter, PosOperator,
+    PostfixExpression, Pragma, PrivateNumber, PrivateVisibility, PublicVisibility, Range,
+    RangeOrExpression, ReturnStatement, Span, Spread, SpreadOrExpression, Statement, StrOperator,
+    StructDefinition, StructField, StructType, SymbolDeclaration, TernaryExpression, ToExpression,
+    TupleAccess, TupleIndex, TupleType, Type, TypeDefinition, TypedIdentifier,
+    TypedIdentifierOrAssignee, U128NumberExpression, U128Suffix, U128Type, U16NumberExpression,
+    U16Suffix, U16Type, U256NumberExpression, U256Suffix, U256Type, U32NumberExpression, U32Suffix,
+    U32Type, U64NumberExpression, U64Suffix, U64Type, U8NumberExpression, U8Suffix, U8Type,
+    UnaryExpression, UnaryOperator, Underscore, Visibility, WitnessStatement, EOI,
 };
 
 mod ast {
@@ -181,6 +184,7 @@ mod ast {
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::function_definition))]
     pub struct FunctionDefinition<'ast> {
+        pub attributes: Vec<FunctionAttribute<'ast>>,
         pub id: IdentifierExpression<'ast>,
         pub generics: Vec<IdentifierExpression<'ast>>,
         pub parameters: Vec<Parameter<'ast>>,
@@ -190,6 +194,15 @@ mod ast {
         pub span: Span<'ast>,
     }
 
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::function_attribute))]
+    pub struct FunctionAttribute<'ast> {
+        pub id: IdentifierExpression<'ast>,
+        pub argument: Option<DecimalNumber<'ast>>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::const_definition))]
     pub struct ConstantDefinition<'ast> {
@@ -260,6 +273,7 @@ mod ast {
         Basic(BasicType<'ast>),
         Array(ArrayType<'ast>),
         Struct(StructType<'ast>),
+        Tuple(TupleType<'ast>),
     }
 
     #[derive(Debug, FromPest, PartialEq, Clone)]
@@ -271,6 +285,12 @@ mod ast {
         U16(U16Type<'ast>),
         U32(U32Type<'ast>),
         U64(U64Type<'ast>),
+        U128(U128Type<'ast>),
+        U256(U256Type<'ast>),
+        I8(I8Type<'ast>),
+        I16(I16Type<'ast>),
+        I32(I32Type<'ast>),
+        I64(I64Type<'ast>),
     }
 
     #[derive(Debug, FromPest, PartialEq, Clone)]
@@ -331,6 +351,56 @@ mod ast {
         pub span: Span<'ast>,
     }
 
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::ty_u128))]
+    pub struct U128Type<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::ty_u256))]
+    pub struct U256Type<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::ty_i8))]
+    pub struct I8Type<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::ty_i16))]
+    pub struct I16Type<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::ty_i32))]
+    pub struct I32Type<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::ty_i64))]
+    pub struct I64Type<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::ty_tuple))]
+    pub struct TupleType<'ast> {
+        pub elements: Vec<Type<'ast>>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::ty_struct))]
     pub struct StructType<'ast> {
@@ -410,6 +480,8 @@ mod ast {
         Assertion(AssertionStatement<'ast>),
         CondStore(CondStoreStatement<'ast>),
         Iteration(IterationStatement<'ast>),
+        IfElse(IfElseStatement<'ast>),
+        Log(LogStatement<'ast>),
     }
 
     impl<'ast> Statement<'ast> {
@@ -421,6 +493,8 @@ mod ast {
                 Statement::Assertion(x) => &x.span,
                 Statement::CondStore(x) => &x.span,
                 Statement::Iteration(x) => &x.span,
+                Statement::IfElse(x) => &x.span,
+                Statement::Log(x) => &x.span,
             }
         }
     }
@@ -453,6 +527,15 @@ mod ast {
         pub span: Span<'ast>,
     }
 
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::log_statement))]
+    pub struct LogStatement<'ast> {
+        pub message: AnyString<'ast>,
+        pub expressions: Vec<Expression<'ast>>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::cond_store_statement))]
     pub struct CondStoreStatement<'ast> {
@@ -476,6 +559,24 @@ mod ast {
         pub span: Span<'ast>,
     }
 
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::if_else_statement))]
+    pub struct IfElseStatement<'ast> {
+        pub condition: Expression<'ast>,
+        pub consequence: Vec<Statement<'ast>>,
+        pub alternative: Option<ElseClause<'ast>>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::else_clause))]
+    pub struct ElseClause<'ast> {
+        pub statements: Vec<Statement<'ast>>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::return_statement))]
     pub struct ReturnStatement<'ast> {
@@ -517,12 +618,14 @@ mod ast {
         Literal(LiteralExpression<'ast>),
         InlineArray(InlineArrayExpression<'ast>),
         InlineStruct(InlineStructExpression<'ast>),
+        InlineTuple(InlineTupleExpression<'ast>),
         ArrayInitializer(ArrayInitializerExpression<'ast>),
     }
 
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::term))]
     pub enum Term<'ast> {
+        InlineTuple(InlineTupleExpression<'ast>),
         Expression(Expression<'ast>),
         InlineStruct(InlineStructExpression<'ast>),
         Ternary(TernaryExpression<'ast>),
@@ -620,6 +723,7 @@ mod ast {
                 Term::Primary(e) => e.into(),
                 Term::InlineArray(e) => Expression::InlineArray(e),
                 Term::InlineStruct(e) => Expression::InlineStruct(e),
+                Term::InlineTuple(e) => Expression::InlineTuple(e),
                 Term::ArrayInitializer(e) => Expression::ArrayInitializer(e),
             }
         }
@@ -731,6 +835,14 @@ mod ast {
         pub span: Span<'ast>,
     }
 
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::inline_tuple_expression))]
+    pub struct InlineTupleExpression<'ast> {
+        pub expressions: Vec<Expression<'ast>>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::array_initializer_expression))]
     pub struct ArrayInitializerExpression<'ast> {
@@ -764,6 +876,7 @@ mod ast {
         Call(CallAccess<'ast>),
         Select(ArrayAccess<'ast>),
         Member(MemberAccess<'ast>),
+        Tuple(TupleAccess<'ast>),
     }
 
     #[derive(Debug, FromPest, PartialEq, Clone)]
@@ -771,6 +884,7 @@ mod ast {
     pub enum AssigneeAccess<'ast> {
         Select(ArrayAccess<'ast>),
         Member(MemberAccess<'ast>),
+        Tuple(TupleAccess<'ast>),
     }
 
     #[derive(Debug, FromPest, PartialEq, Clone)]
@@ -829,6 +943,23 @@ mod ast {
         pub span: Span<'ast>,
     }
 
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::tuple_access))]
+    pub struct TupleAccess<'ast> {
+        pub index: TupleIndex<'ast>,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::tuple_index))]
+    pub struct TupleIndex<'ast> {
+        #[pest_ast(outer(with(span_into_usize)))]
+        pub value: usize,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
     #[derive(Debug, PartialEq, Clone)]
     pub struct BinaryExpression<'ast> {
         pub op: BinaryOperator,
@@ -892,6 +1023,7 @@ mod ast {
                 Expression::Postfix(p) => &p.span,
                 Expression::InlineArray(a) => &a.span,
                 Expression::InlineStruct(s) => &s.span,
+                Expression::InlineTuple(t) => &t.span,
                 Expression::ArrayInitializer(a) => &a.span,
                 Expression::Unary(u) => &u.span,
             }
@@ -946,6 +1078,12 @@ mod ast {
         U16(U16Suffix<'ast>),
         U32(U32Suffix<'ast>),
         U64(U64Suffix<'ast>),
+        U128(U128Suffix<'ast>),
+        U256(U256Suffix<'ast>),
+        I8(I8Suffix<'ast>),
+        I16(I16Suffix<'ast>),
+        I32(I32Suffix<'ast>),
+        I64(I64Suffix<'ast>),
         Field(FieldSuffix<'ast>),
     }
 
@@ -977,6 +1115,48 @@ mod ast {
         pub span: Span<'ast>,
     }
 
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::decimal_suffix_u128))]
+    pub struct U128Suffix<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::decimal_suffix_u256))]
+    pub struct U256Suffix<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::decimal_suffix_i8))]
+    pub struct I8Suffix<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::decimal_suffix_i16))]
+    pub struct I16Suffix<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::decimal_suffix_i32))]
+    pub struct I32Suffix<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::decimal_suffix_i64))]
+    pub struct I64Suffix<'ast> {
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::decimal_suffix_field))]
     pub struct FieldSuffix<'ast> {
@@ -1024,6 +1204,8 @@ mod ast {
         U16(U16NumberExpression<'ast>),
         U32(U32NumberExpression<'ast>),
         U64(U64NumberExpression<'ast>),
+        U128(U128NumberExpression<'ast>),
+        U256(U256NumberExpression<'ast>),
     }
 
     #[derive(Debug, FromPest, PartialEq, Clone)]
@@ -1062,6 +1244,24 @@ mod ast {
         pub span: Span<'ast>,
     }
 
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::hex_number_u128))]
+    pub struct U128NumberExpression<'ast> {
+        #[pest_ast(outer(with(span_into_str)))]
+        pub value: String,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
+    #[derive(Debug, FromPest, PartialEq, Clone)]
+    #[pest_ast(rule(Rule::hex_number_u256))]
+    pub struct U256NumberExpression<'ast> {
+        #[pest_ast(outer(with(span_into_str)))]
+        pub value: String,
+        #[pest_ast(outer())]
+        pub span: Span<'ast>,
+    }
+
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::identifier))]
     pub struct IdentifierExpression<'ast> {
@@ -1084,6 +1284,10 @@ mod ast {
         span.as_str().to_string()
     }
 
+    fn span_into_usize(span: Span) -> usize {
+        span.as_str().parse().unwrap()
+    }
+
     #[derive(Debug, FromPest, PartialEq, Clone)]
     #[pest_ast(rule(Rule::EOI))]
     #[allow(clippy::upper_case_acronyms)]
@@ -1107,6 +1311,15 @@ impl fmt::Display for Error {
     }
 }
 
+impl Error {
+    /// The line and column (both starting at 1) where parsing failed.
+    pub fn line_col(&self) -> (usize, usize) {
+        match self.0.line_col {
+            pest::error::LineColLocation::Pos(p) | pest::error::LineColLocation::Span(p, _) => p,
+        }
+    }
+}
+
 pub fn generate_ast(input: &str) -> Result<ast::File, Error> {
     let parse_tree = parse(input).map_err(Error)?;
     Ok(Prog::from(parse_tree).0)
@@ -1189,6 +1402,7 @@ mod tests {
                         span: Span::new(&source, 0, 29).unwrap()
                     })),
                     SymbolDeclaration::Function(FunctionDefinition {
+                        attributes: vec![],
                         generics: vec![],
                         id: IdentifierExpression {
                             value: String::from("main"),
@@ -1250,6 +1464,7 @@ mod tests {
                         span: Span::new(&source, 0, 29).unwrap()
                     })),
                     SymbolDeclaration::Function(FunctionDefinition {
+                        attributes: vec![],
                         generics: vec![],
                         id: IdentifierExpression {
                             value: String::from("main"),
@@ -1335,6 +1550,7 @@ mod tests {
                         span: Span::new(&source, 0, 29).unwrap()
                     })),
                     SymbolDeclaration::Function(FunctionDefinition {
+                        attributes: vec![],
                         generics: vec![],
                         id: IdentifierExpression {
                             value: String::from("main"),
@@ -1395,6 +1611,7 @@ mod tests {
             Ok(File {
                 pragma: None,
                 declarations: vec![SymbolDeclaration::Function(FunctionDefinition {
+                    attributes: vec![],
                     generics: vec![],
                     id: IdentifierExpression {
                         value: String::from("main"),
@@ -1433,6 +1650,7 @@ mod tests {
             Ok(File {
                 pragma: None,
                 declarations: vec![SymbolDeclaration::Function(FunctionDefinition {
+                    attributes: vec![],
                     generics: vec![],
                     id: IdentifierExpression {
                         value: String::from("main"),